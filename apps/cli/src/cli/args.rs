@@ -3,9 +3,13 @@
 //! Uses clap's derive API for declarative argument parsing with
 //! support for subcommands and global options.
 
+use std::num::{NonZeroU64, NonZeroUsize};
 use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
 
-use clap::{Parser, Subcommand};
+use clap::{Arg, ArgAction, ArgGroup, Parser, Subcommand, ValueEnum};
+
+use crate::core::EmailHeaderClass;
 
 /// A CLI tool to clear metadata from files on Windows NTFS filesystems.
 ///
@@ -38,26 +42,491 @@ pub struct Cli {
 }
 
 /// Global options available for all commands.
+///
+/// `-v`, `--errors-only` and `-q` have no fields of their own: they only set
+/// [`verbosity`](Self::verbosity), and at most one of them (or `--verbosity`) is allowed.
 #[derive(Debug, Parser)]
+#[command(
+    arg(Arg::new("verbose")
+        .short('v')
+        .long("verbose")
+        .global(true)
+        .group("output")
+        .action(ArgAction::SetTrue)
+        .help("Same as --verbosity verbose: every file with its steps and changes")),
+    arg(Arg::new("errors_only")
+        .long("errors-only")
+        .visible_alias("summary-only")
+        .global(true)
+        .group("output")
+        .action(ArgAction::SetTrue)
+        .help("Same as --verbosity errors-only: only the files that failed, plus the summary")),
+    arg(Arg::new("quiet")
+        .short('q')
+        .long("quiet")
+        .global(true)
+        .group("output")
+        .action(ArgAction::SetTrue)
+        .help("Same as --verbosity quiet: nothing, not even the summary (the exit code still reports failures)")),
+    group(ArgGroup::new("output").multiple(false))
+)]
 pub struct GlobalOptions {
     /// Run in dry-run mode (no actual changes will be made)
-    #[arg(short = 'n', long, global = true)]
+    #[arg(short = 'n', long, global = true, overrides_with = "no_dry_run")]
     pub dry_run: bool,
 
-    /// Enable verbose output
-    #[arg(short, long, global = true)]
-    pub verbose: bool,
+    /// Make changes even if the config file sets `dry_run = true`
+    #[arg(long, global = true, overrides_with = "dry_run")]
+    pub no_dry_run: bool,
+
+    /// Dry run that also prints the current Office document properties that would be cleared
+    #[arg(long, global = true)]
+    pub dry_run_diff: bool,
+
+    /// How much to print while cleaning (`-q`, `--errors-only` and `-v` are shorthands)
+    ///
+    /// Unset, it comes from `verbose` in the config file, or `normal`.
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "LEVEL",
+        group = "output",
+        default_value_ifs = [
+            ("quiet", "true", "quiet"),
+            ("errors_only", "true", "errors-only"),
+            ("verbose", "true", "verbose"),
+        ]
+    )]
+    pub verbosity: Option<Verbosity>,
+
+    /// Print the clean report (or `info`/`verify` results) as a single JSON document
+    ///
+    /// Progress output is suppressed and confirmation prompts are skipped.
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Number of files to clean in parallel (default: one per CPU)
+    #[arg(short = 'j', long, global = true, value_name = "N")]
+    pub jobs: Option<NonZeroUsize>,
+
+    /// Most Office documents, EPUBs and ZIPs to rewrite at once (default: up to --jobs)
+    ///
+    /// Rewriting an archive re-compresses every entry, so many large documents
+    /// at once can saturate the disk; other files keep using all --jobs threads.
+    #[arg(long, global = true, value_name = "N")]
+    pub parallel_archives: Option<NonZeroUsize>,
 
     /// Skip confirmation prompts
-    #[arg(short = 'y', long, global = true)]
+    #[arg(short = 'y', long, global = true, overrides_with = "no_yes")]
     pub yes: bool,
 
+    /// Ask for confirmation even if the config file sets `yes = true`
+    #[arg(long, global = true, overrides_with = "yes")]
+    pub no_yes: bool,
+
     /// Run with admin privileges (attempts to clear file owner)
     ///
     /// Without this flag, only non-privileged operations are performed.
     /// Use this when running as Administrator to also clear the NTFS file owner.
-    #[arg(short = 'a', long, global = true)]
+    #[arg(short = 'a', long, global = true, overrides_with = "no_admin")]
     pub admin: bool,
+
+    /// Skip the admin-only steps even if the config file sets `admin = true`
+    #[arg(long, global = true, overrides_with = "admin")]
+    pub no_admin: bool,
+
+    /// Keep original timestamps instead of resetting them
+    #[arg(long, global = true)]
+    pub no_timestamps: bool,
+
+    /// Keep alternate data streams (extended attributes elsewhere)
+    #[arg(long, global = true)]
+    pub no_streams: bool,
+
+    /// Keep the NTFS object ID (Windows), which link tracking uses to find moved files
+    #[arg(long, global = true)]
+    pub no_object_id: bool,
+
+    /// Keep document, email, image, audio and PDF properties
+    #[arg(long, global = true)]
+    pub no_properties: bool,
+
+    /// SID to make the owner when --admin clears it (default S-1-1-0, Everyone)
+    ///
+    /// Any string SID is accepted, e.g. S-1-5-18 (SYSTEM) or S-1-5-32-544
+    /// (BUILTIN\Administrators); it is checked before any file is touched.
+    #[arg(long, global = true, value_name = "SID")]
+    pub owner_sid: Option<String>,
+
+    /// Ensure no file claims to be created after it was last modified
+    ///
+    /// Copied or edited files often have a creation time later than their
+    /// modification time. This moves the creation time back to match.
+    #[arg(long, global = true)]
+    pub fix_timestamp_order: bool,
+
+    /// Set timestamps to this RFC 3339 time instead of January 1, 2000
+    #[arg(long, global = true, value_name = "RFC3339", value_parser = parse_rfc3339)]
+    pub set_time: Option<SystemTime>,
+
+    /// Set each file's timestamps to a random time within this long before now
+    ///
+    /// Takes the same durations as --older-than, e.g. `1y` for the past year.
+    /// Every file gets its own time, from a generator seeded afresh each run,
+    /// so cleaned files neither carry the tell-tale January 1, 2000 nor share
+    /// one time. --verbose shows the time each file got.
+    #[arg(long, global = true, value_name = "DURATION", value_parser = parse_age, conflicts_with = "set_time")]
+    pub randomize_time: Option<Duration>,
+
+    /// Append a timestamped line per processed file to FILE (created if missing)
+    ///
+    /// Records the outcome, streams removed, what each cleaner changed and any
+    /// error. Written regardless of --verbose or --json.
+    #[arg(long, global = true, value_name = "FILE")]
+    pub log: Option<PathBuf>,
+
+    /// Write the full JSON report to FILE (replacing it), alongside the usual output
+    ///
+    /// The same report --json prints, written whether or not --json is given,
+    /// so a run can stay interactive and still leave a machine-readable record.
+    #[arg(long, global = true, value_name = "FILE")]
+    pub report_file: Option<PathBuf>,
+
+    /// Copy each file into DIR before cleaning it, mirroring its full path
+    ///
+    /// A file whose backup can't be written is left untouched and reported as failed.
+    #[arg(long, global = true, value_name = "DIR")]
+    pub backup: Option<PathBuf>,
+
+    /// Write cleaned copies into DIR instead of modifying the originals
+    ///
+    /// Files keep their path relative to the target folder (or the file's own
+    /// folder); other files, e.g. from `batch`, mirror their full path like --backup.
+    #[arg(long, global = true, value_name = "DIR", conflicts_with = "backup")]
+    pub output_dir: Option<PathBuf>,
+
+    /// Overwrite rewritten files through their existing handle instead of replacing them
+    ///
+    /// Keeps the file's identity (inode, file ID, object ID) and works without
+    /// write access to the directory: the file itself is locked and rewrites are
+    /// staged in the system temp folder. An interruption can corrupt the file.
+    #[arg(long, global = true)]
+    pub in_place: bool,
+
+    /// Remove every alternate data stream, not just known metadata streams
+    ///
+    /// By default only streams such as Zone.Identifier and the property streams
+    /// are removed, so custom application streams survive.
+    #[arg(long, global = true)]
+    pub all_streams: bool,
+
+    /// Never remove the stream with this name (repeatable; wins over --all-streams)
+    #[arg(long, global = true, value_name = "NAME")]
+    pub keep_stream: Vec<String>,
+
+    /// Never remove streams whose name matches this regex (repeatable; wins over --all-streams)
+    ///
+    /// For applications that create streams with varying names, e.g.
+    /// `^com\.dropbox\.` or `^AFP_`. Matched case-insensitively anywhere in the
+    /// name unless anchored; an invalid pattern is rejected before any file is touched.
+    #[arg(long, global = true, value_name = "REGEX", value_parser = parse_stream_pattern)]
+    pub keep_stream_pattern: Vec<regex_lite::Regex>,
+
+    /// Overwrite each stream with zeros before deleting it
+    ///
+    /// Deleting a stream only frees its clusters, so its content can linger on
+    /// disk until reused. Overwriting first defeats recovery on hard drives, but
+    /// not on SSDs, whose wear leveling writes the zeros somewhere else.
+    #[arg(long, global = true)]
+    pub shred_streams: bool,
+
+    /// Only change the property parts when rewriting Office documents
+    ///
+    /// Every other archive entry is copied with its original compressed bytes,
+    /// CRC and timestamps instead of being recompressed, so the document differs
+    /// from the original only in docProps/core.xml and docProps/app.xml.
+    #[arg(long, global = true)]
+    pub metadata_only: bool,
+
+    /// Make read-only files read-only again after cleaning them
+    ///
+    /// Read-only files are always made writable so they can be cleaned; without
+    /// this flag they are left writable afterwards.
+    #[arg(long, global = true)]
+    pub preserve_readonly: bool,
+
+    /// Also clean the documents, images and other files inside .zip archives
+    #[arg(long, global = true)]
+    pub into_archives: bool,
+
+    /// How many levels of archives inside archives `--into-archives` opens
+    #[arg(long, global = true, value_name = "N", default_value_t = 2)]
+    pub max_archive_depth: usize,
+
+    /// Clean files even when they already look clean (skipped by default)
+    ///
+    /// Also cleans digitally signed Office documents, which are otherwise left
+    /// alone because rewriting them invalidates the signature.
+    #[arg(long, global = true)]
+    pub force: bool,
+
+    /// Record each file's SHA-256 before and after cleaning in the JSON report
+    ///
+    /// Files are hashed in chunks, so large files aren't loaded into memory.
+    /// Ignored in dry runs, which don't modify anything.
+    #[arg(long, global = true)]
+    pub hash: bool,
+
+    /// Re-check each file after cleaning it, failing it if metadata remains
+    ///
+    /// Runs the same read-only checks as the `verify` command, so a stream
+    /// whose deletion silently didn't take or a property a rewrite missed shows
+    /// up as a failure instead of a success. Metadata a cleaner deliberately
+    /// left alone (e.g. in a signed document) doesn't count.
+    #[arg(long, global = true)]
+    pub verify: bool,
+
+    /// Allow cleaning system folders, a drive root or your profile folder itself
+    ///
+    /// These targets are refused otherwise, even with --yes, since rewriting
+    /// files the OS and installed programs rely on can break them.
+    #[arg(long, global = true)]
+    pub i_know_what_im_doing: bool,
+
+    /// Retry files that are in use by another process up to N times
+    ///
+    /// Waits 250ms before the first retry, doubling up to 4s between attempts.
+    /// Files still in use afterwards are reported as failed.
+    #[arg(long, global = true, value_name = "N", default_value_t = 0)]
+    pub retry: u32,
+
+    /// Give up on a file that takes longer than SECS to clean and mark it failed
+    ///
+    /// Keeps unattended runs from stalling on one pathological file, such as a
+    /// huge archive or an unresponsive network path. Steps already finished stay
+    /// done; the file isn't modified further once it has timed out.
+    #[arg(long, global = true, value_name = "SECS")]
+    pub timeout: Option<NonZeroU64>,
+
+    /// Show timestamps in the local timezone (default)
+    #[arg(long, global = true, overrides_with = "utc")]
+    pub local_time: bool,
+
+    /// Show timestamps in UTC instead of the local timezone
+    #[arg(long, global = true, overrides_with = "local_time")]
+    pub utc: bool,
+
+    /// Encoding to assume for document XML that doesn't declare one (e.g. windows-1252)
+    ///
+    /// Parts with a byte order mark or an `encoding="..."` declaration are always
+    /// decoded using that; this only applies to undeclared parts. Defaults to UTF-8.
+    #[arg(long, global = true, value_name = "LABEL")]
+    pub input_encoding: Option<String>,
+
+    /// Which email header classes to strip from .eml/.msg files
+    ///
+    /// `routing` removes Received chains and originating IPs, `client` removes the
+    /// mail client (User-Agent, X-Mailer), `vendor` removes other X- headers.
+    /// From, To, Subject, Date and MIME headers are always kept.
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_delimiter = ',',
+        value_name = "CLASS",
+        default_values = ["routing", "client"]
+    )]
+    pub email_headers: Vec<HeaderClass>,
+
+    /// Only clean files matching this glob in dir/recursive modes (repeatable)
+    ///
+    /// Globs match the path relative to the folder being cleaned, e.g. `*.docx`
+    /// or `reports/**/*.pdf`; `*` also matches across folders.
+    #[arg(long, global = true, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Pick which of the found files to clean in dir/recursive modes
+    ///
+    /// Shows a checklist of the files with every one selected; uncheck the ones
+    /// to skip. Ignored with --yes or --json, which clean everything found.
+    #[arg(short = 'i', long, global = true)]
+    pub interactive: bool,
+
+    /// Ask before cleaning each file, showing the metadata it was found to carry
+    ///
+    /// y cleans the file, n skips it and Esc cancels the whole run. Replaces the
+    /// single confirmation for all files.
+    #[arg(long, global = true, conflicts_with_all = ["yes", "json"])]
+    pub confirm_each: bool,
+
+    /// Descend at most N folder levels in recursive mode (1 = only the folder itself)
+    #[arg(long, global = true, value_name = "N", value_parser = parse_max_depth)]
+    pub max_depth: Option<usize>,
+
+    /// Follow symlinks in dir/recursive modes and clean their targets
+    ///
+    /// By default symlinked files and folders are skipped (listed with --verbose),
+    /// so cleaning never leaves the folder tree or loops.
+    #[arg(long, global = true)]
+    pub follow_symlinks: bool,
+
+    /// Skip files and folders matching this glob in dir/recursive modes (repeatable)
+    ///
+    /// Matched against the path relative to the folder being cleaned; takes
+    /// precedence over --include. An excluded folder (e.g. `.git`) is not entered.
+    #[arg(long, global = true, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Only clean files last modified at least this long ago in dir/recursive modes
+    ///
+    /// Takes a duration such as `30d`, `2w`, `1y` or `1d12h` (units s, m, h, d,
+    /// w and y, a year being 365 days), measured back from when the scan starts.
+    #[arg(long, global = true, value_name = "DURATION", value_parser = parse_age)]
+    pub older_than: Option<Duration>,
+
+    /// Only clean files last modified at most this long ago in dir/recursive modes
+    ///
+    /// Takes the same durations as --older-than; both together select a window.
+    #[arg(long, global = true, value_name = "DURATION", value_parser = parse_age)]
+    pub newer_than: Option<Duration>,
+
+    /// Also reset folder timestamps in dir/recursive modes
+    ///
+    /// Each folder whose contents are scanned is reset after the files inside
+    /// it, deepest first, so cleaning the files doesn't bump it again. Excluded
+    /// folders and those past --max-depth are left alone; ignored with --output-dir.
+    #[arg(long, global = true)]
+    pub clean_dirs: bool,
+
+    /// Delete Thumbs.db, desktop.ini and .DS_Store files in dir/recursive modes
+    ///
+    /// These are created by Windows Explorer and macOS Finder and hold thumbnails
+    /// of the folder's pictures and its view settings. They are real files, so
+    /// you are asked before they are deleted unless --yes is passed; --backup
+    /// keeps a copy. Ignored with --output-dir.
+    #[arg(long, global = true)]
+    pub remove_junk: bool,
+
+    /// Only clean files likely to contain sensitive metadata
+    ///
+    /// Files are scored by a read-only scan (Office authorship, download origin,
+    /// email routing headers, extra streams); files below the threshold are skipped.
+    #[arg(long, global = true)]
+    pub only_sensitive: bool,
+
+    /// Clean the files listed in a previous JSON clean report instead of scanning
+    ///
+    /// Applies to the file, dir and recursive commands; their path is ignored.
+    #[arg(long, global = true, value_name = "REPORT")]
+    pub retry_from: Option<PathBuf>,
+
+    /// With --retry-from, only retry files that failed in that report
+    #[arg(long, global = true, requires = "retry_from")]
+    pub only_failed: bool,
+
+    /// Record each cleaned file in FILE, and skip the files already listed there
+    ///
+    /// Paths are appended as files finish, so rerunning an interrupted run with
+    /// the same FILE resumes where it stopped, without re-checking the files
+    /// it had already cleaned. Not available with --output-dir.
+    #[arg(long, global = true, value_name = "FILE", conflicts_with = "output_dir")]
+    pub state: Option<PathBuf>,
+
+    /// Minimum score for --only-sensitive (see `info` for a file's score)
+    #[arg(long, global = true, value_name = "SCORE", default_value_t = 3)]
+    pub sensitivity_threshold: u32,
+}
+
+/// How much the cleaning commands print, from `--quiet` to `--verbose`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Verbosity {
+    /// Nothing but fatal errors
+    Quiet,
+    /// Failed files and the summary
+    ErrorsOnly,
+    /// Progress and the summary
+    #[default]
+    Normal,
+    /// Every file with its steps and changes
+    Verbose,
+}
+
+/// Email header classes selectable on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HeaderClass {
+    /// Received chains, originating IPs, authentication results
+    Routing,
+    /// User-Agent, X-Mailer and similar
+    Client,
+    /// Any other X- vendor header
+    Vendor,
+}
+
+impl From<HeaderClass> for EmailHeaderClass {
+    fn from(class: HeaderClass) -> Self {
+        match class {
+            HeaderClass::Routing => EmailHeaderClass::Routing,
+            HeaderClass::Client => EmailHeaderClass::Client,
+            HeaderClass::Vendor => EmailHeaderClass::Vendor,
+        }
+    }
+}
+
+/// Parses an RFC 3339 timestamp such as `2020-06-01T12:00:00Z`.
+pub(super) fn parse_rfc3339(value: &str) -> Result<SystemTime, String> {
+    time::OffsetDateTime::parse(value, &time::format_description::well_known::Rfc3339)
+        .map(SystemTime::from)
+        .map_err(|e| format!("expected an RFC 3339 time like 2020-06-01T12:00:00Z ({})", e))
+}
+
+/// Compiles a `--keep-stream-pattern` regex, case-insensitive like NTFS stream names.
+fn parse_stream_pattern(value: &str) -> Result<regex_lite::Regex, String> {
+    regex_lite::RegexBuilder::new(value)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| format!("invalid regex: {}", e))
+}
+
+/// Parses an `--older-than`/`--newer-than` age: whole numbers with a unit,
+/// optionally chained (`30d`, `2w`, `1d12h`).
+fn parse_age(value: &str) -> Result<Duration, String> {
+    const UNITS: &[(char, u64)] = &[('s', 1), ('m', 60), ('h', 3600), ('d', 86_400), ('w', 604_800), ('y', 31_536_000)];
+    let invalid = || format!("expected a duration like 30d, 2w or 1d12h (units: s, m, h, d, w, y), got '{}'", value);
+
+    let mut total: u64 = 0;
+    let mut parts = 0;
+    let mut digits = String::new();
+    for c in value.trim().chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+
+        let seconds = UNITS.iter().find(|(unit, _)| *unit == c.to_ascii_lowercase()).ok_or_else(invalid)?.1;
+        let count: u64 = digits.parse().map_err(|_| invalid())?;
+        total = count
+            .checked_mul(seconds)
+            .and_then(|part| total.checked_add(part))
+            .ok_or_else(|| format!("duration '{}' is too long", value))?;
+        parts += 1;
+        digits.clear();
+    }
+
+    // A trailing number without a unit is ambiguous
+    if parts == 0 || !digits.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// Parses a `--max-depth` level, which must be at least 1.
+fn parse_max_depth(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("must be at least 1 (1 cleans only the folder itself)".to_string()),
+        Ok(depth) => Ok(depth),
+        Err(e) => Err(format!("expected a whole number of folder levels ({})", e)),
+    }
 }
 
 /// Available commands for the CLI.
@@ -95,6 +564,18 @@ pub enum Commands {
         path: Option<PathBuf>,
     },
 
+    /// Clean every file named in a newline-delimited list
+    ///
+    /// Reads the list from a file, or from stdin when omitted or `-` (e.g. piped
+    /// from `find`). Blank lines and lines starting with `#` are ignored; relative
+    /// paths resolve against the current directory. Produces a single report.
+    #[command(visible_alias = "b")]
+    Batch {
+        /// File listing the paths to clean (defaults to stdin)
+        #[arg(short, long, value_name = "FILE")]
+        list: Option<PathBuf>,
+    },
+
     /// Display information about what metadata a file contains
     ///
     /// If no path is provided, prompts to select a file from the current directory.
@@ -104,4 +585,77 @@ pub enum Commands {
         #[arg(short, long, value_name = "FILE")]
         path: Option<PathBuf>,
     },
+
+    /// Check that a file carries no remaining metadata
+    ///
+    /// Runs the same checks as `info` and prints a pass/fail line per category.
+    /// Exits with status 2 if anything is found, for gating releases in CI.
+    #[command(visible_alias = "v")]
+    Verify {
+        /// Path to the file to verify
+        #[arg(short, long, value_name = "FILE")]
+        path: PathBuf,
+    },
+
+    /// List a file's alternate data streams, or remove a single one
+    ///
+    /// Only touches streams: contents, timestamps and properties are left alone.
+    /// Elsewhere than Windows, lists the download-provenance extended attributes.
+    #[command(visible_alias = "s")]
+    Streams {
+        /// Path to the file whose streams to list
+        #[arg(short, long, value_name = "FILE")]
+        path: PathBuf,
+
+        /// Delete only the stream with this name (e.g. Zone.Identifier)
+        #[arg(long, value_name = "NAME")]
+        remove: Option<String>,
+    },
+
+    /// Count the files a clean would process, without reading their metadata
+    ///
+    /// Walks the folder with the same filters as `dir` and `recursive` (--include,
+    /// --exclude, --older-than, --max-depth, ...) and prints the file count, total
+    /// size and a breakdown by extension, to estimate how long a run will take.
+    Count {
+        /// Directory to count (defaults to current directory)
+        #[arg(short, long, value_name = "DIRECTORY")]
+        path: Option<PathBuf>,
+
+        /// Include subfolders, as `recursive` would
+        #[arg(short, long)]
+        recursive: bool,
+    },
+
+    /// Copy files back from a --backup directory, reversing a clean
+    ///
+    /// Backups mirror each file's full path, so the target (a file or a folder)
+    /// is matched to its backups by path; a folder restores every backup below it.
+    /// Asks before overwriting unless --yes is passed.
+    Restore {
+        /// Directory passed to --backup when the files were cleaned
+        #[arg(value_name = "BACKUP_DIR")]
+        backup_dir: PathBuf,
+
+        /// File or folder to restore (defaults to current directory)
+        #[arg(short, long, value_name = "PATH")]
+        target: Option<PathBuf>,
+    },
+
+    /// Check which cleaning operations work on this system
+    ///
+    /// Creates scratch files, applies each cleaning operation to them and reports
+    /// a capability matrix (e.g. streams, owner clearing, Office documents).
+    /// Defaults to the system temp directory; pass a path to test a specific drive.
+    Doctor {
+        /// Directory to create scratch files in (defaults to the temp directory)
+        #[arg(short, long, value_name = "DIRECTORY")]
+        path: Option<PathBuf>,
+    },
+
+    /// Print the JSON Schema of the machine-readable clean report
+    ///
+    /// Describes the report and per-file result structures so tooling can
+    /// validate the output or generate bindings against it.
+    ReportSchema,
 }
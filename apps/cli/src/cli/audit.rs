@@ -0,0 +1,77 @@
+//! Append-only audit log written with `--log`.
+//!
+//! One tab-separated line per processed file: UTC time, outcome, path, streams
+//! removed, what each cleaner changed, any warnings, and the error for failed
+//! files. Junk files deleted with `--remove-junk` get a `deleted` line each.
+//! The log is written regardless of `--verbose`/`--json`, so quiet runs still
+//! leave a record.
+
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::core::{CleanReport, FileResult, StepStatus};
+
+/// Appends one line per file in `report` to the log at `path`, creating it if missing.
+pub fn append(path: &Path, report: &CleanReport, dry_run: bool) -> std::io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut log = BufWriter::new(file);
+
+    // Every line of a run shares its timestamp
+    let now = OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default();
+
+    for result in &report.file_results {
+        writeln!(log, "{}", line(&now, result, dry_run))?;
+    }
+
+    // Deleted junk files have no cleaning results, only the deletion
+    let deleted = if dry_run { "planned" } else { "deleted" };
+    for path in &report.junk_files {
+        writeln!(log, "{}\t{}\t{}\tchanges=junk", now, deleted, path.display())?;
+    }
+
+    log.flush()
+}
+
+/// Formats the log line for one file.
+fn line(time: &str, result: &FileResult, dry_run: bool) -> String {
+    let outcome = match (result.success, dry_run) {
+        (false, _) => "failed",
+        (true, true) => "planned",
+        (true, false) => "cleaned",
+    };
+
+    // What the cleaners changed, or in a dry run what they would change
+    let changes: Vec<String> = if dry_run {
+        result.planned_actions.clone()
+    } else {
+        result
+            .steps
+            .iter()
+            .filter(|step| step.status == StepStatus::Ran)
+            .map(|step| match &step.detail {
+                Some(detail) => format!("{} ({})", step.name, detail),
+                None => step.name.clone(),
+            })
+            .collect()
+    };
+
+    let mut line = format!(
+        "{}\t{}\t{}\tstreams_removed={}\tchanges={}",
+        time,
+        outcome,
+        result.display_path.display(),
+        result.streams_removed,
+        changes.join("; ")
+    );
+    if !result.warnings.is_empty() {
+        line.push_str(&format!("\twarnings={}", result.warnings.join("; ").replace(['\t', '\n'], " ")));
+    }
+    if let Some(error) = &result.error {
+        line.push_str(&format!("\terror={}", error.replace(['\t', '\n'], " ")));
+    }
+    line
+}
@@ -0,0 +1,191 @@
+//! Default options loaded from a `rs-mahito.toml` config file.
+//!
+//! The file is looked up in the current directory first, then in the user's
+//! config directory (e.g. `~/.config` or `%APPDATA%`). Values only fill in
+//! defaults: anything given on the command line wins.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use super::args::{parse_rfc3339, GlobalOptions, Verbosity};
+use crate::core::TimestampTarget;
+
+/// Name of the config file in both lookup locations.
+pub const CONFIG_FILE_NAME: &str = "rs-mahito.toml";
+
+/// Defaults read from the config file. Every field is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Default for `--dry-run`.
+    pub dry_run: Option<bool>,
+    /// Default for `--verbose` (`false` means `--verbosity normal`).
+    pub verbose: Option<bool>,
+    /// Default for `--admin`.
+    pub admin: Option<bool>,
+    /// Default for `--yes`.
+    pub yes: Option<bool>,
+    /// Whether the file owner is cleared without the rest of admin mode
+    /// (`--admin` always clears it).
+    pub clear_owner: Option<bool>,
+    /// Whether document, email and image properties are cleared (default true).
+    pub clear_properties: Option<bool>,
+    /// `"2000"` (default), `"now"`, `"unchanged"` or an RFC 3339 time.
+    pub timestamp_target: Option<String>,
+    /// Default `--include` globs.
+    pub include: Vec<String>,
+    /// Default `--exclude` globs.
+    pub exclude: Vec<String>,
+}
+
+impl Config {
+    /// Loads the first config file found, or the empty config if there is none.
+    pub fn load() -> anyhow::Result<Self> {
+        match Self::find() {
+            Some(path) => Self::from_file(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Returns the config file to use, checking the current directory first.
+    fn find() -> Option<PathBuf> {
+        let local = PathBuf::from(CONFIG_FILE_NAME);
+        if local.is_file() {
+            return Some(local);
+        }
+
+        dirs::config_dir()
+            .map(|dir| dir.join(CONFIG_FILE_NAME))
+            .filter(|path| path.is_file())
+    }
+
+    /// Parses a config file.
+    fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read config file '{}'", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Invalid config file '{}'", path.display()))
+    }
+
+    /// Fills in global options the command line left unset.
+    ///
+    /// A flag takes the config's value only when neither it nor its `--no-`
+    /// counterpart was given; lists given on the command line replace the
+    /// config's. `clear_owner` is not a global option and is read by the runner.
+    pub fn apply(&self, global: &mut GlobalOptions) {
+        fill_flag(&mut global.dry_run, global.no_dry_run, self.dry_run);
+        if global.verbosity.is_none() {
+            global.verbosity = self.verbose.map(|verbose| if verbose { Verbosity::Verbose } else { Verbosity::Normal });
+        }
+        fill_flag(&mut global.yes, global.no_yes, self.yes);
+        fill_flag(&mut global.admin, global.no_admin, self.admin);
+
+        if global.include.is_empty() {
+            global.include = self.include.clone();
+        }
+        if global.exclude.is_empty() {
+            global.exclude = self.exclude.clone();
+        }
+    }
+
+    /// Returns whether the file owner is cleared, given whether admin mode is on.
+    pub fn clear_owner(&self, admin: bool) -> bool {
+        admin || self.clear_owner.unwrap_or(false)
+    }
+
+    /// Returns the configured timestamp target, if any.
+    pub fn timestamp_target(&self) -> anyhow::Result<Option<TimestampTarget>> {
+        let Some(value) = &self.timestamp_target else {
+            return Ok(None);
+        };
+
+        let target = match value.as_str() {
+            "2000" => TimestampTarget::Epoch2000,
+            "now" => TimestampTarget::Now,
+            "unchanged" => TimestampTarget::Unchanged,
+            time => TimestampTarget::Fixed(parse_rfc3339(time).map_err(|_| {
+                anyhow::anyhow!(
+                    "Invalid timestamp_target '{}' in config (use \"2000\", \"now\", \"unchanged\" or an RFC 3339 time)",
+                    time
+                )
+            })?),
+        };
+
+        Ok(Some(target))
+    }
+}
+
+/// Sets `flag` to the config's `value` unless the command line gave the flag
+/// (already `true`) or its `--no-` form (`negated`).
+fn fill_flag(flag: &mut bool, negated: bool, value: Option<bool>) {
+    if !*flag && !negated {
+        *flag = value.unwrap_or(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::args::Cli;
+    use clap::Parser;
+
+    /// Parses a command line and applies `config` to it.
+    fn apply(config: &Config, args: &[&str]) -> GlobalOptions {
+        let mut cli = Cli::parse_from([&["rs-mahito"], args, &["file", "-p", "x"]].concat());
+        config.apply(&mut cli.global);
+        cli.global
+    }
+
+    #[test]
+    fn config_flags_fill_in_unset_flags() {
+        let config = Config {
+            dry_run: Some(true),
+            yes: Some(true),
+            ..Config::default()
+        };
+        let global = apply(&config, &[]);
+        assert!(global.dry_run && global.yes && !global.admin);
+    }
+
+    #[test]
+    fn no_flags_switch_off_config_flags() {
+        let config = Config {
+            dry_run: Some(true),
+            verbose: Some(true),
+            yes: Some(true),
+            admin: Some(true),
+            ..Config::default()
+        };
+        let global = apply(&config, &["--no-dry-run", "--verbosity", "normal", "--no-yes", "--no-admin"]);
+        assert!(!global.dry_run && !global.yes && !global.admin);
+        assert_eq!(global.verbosity, Some(Verbosity::Normal));
+
+        // The last of a flag and its negation wins
+        let global = apply(&Config::default(), &["--no-dry-run", "--dry-run"]);
+        assert!(global.dry_run);
+    }
+
+    #[test]
+    fn clear_owner_does_not_enable_admin_mode() {
+        let config = Config {
+            clear_owner: Some(true),
+            ..Config::default()
+        };
+        let global = apply(&config, &[]);
+        assert!(!global.admin);
+        assert!(config.clear_owner(global.admin));
+        assert!(Config::default().clear_owner(true));
+    }
+
+    #[test]
+    fn verbosity_shorthands_set_the_level() {
+        let config = Config { verbose: Some(true), ..Config::default() };
+        assert_eq!(apply(&config, &[]).verbosity, Some(Verbosity::Verbose));
+        assert_eq!(apply(&config, &["-q"]).verbosity, Some(Verbosity::Quiet));
+        assert_eq!(apply(&config, &["--errors-only"]).verbosity, Some(Verbosity::ErrorsOnly));
+        assert_eq!(apply(&Config::default(), &["-v"]).verbosity, Some(Verbosity::Verbose));
+        assert_eq!(apply(&Config::default(), &[]).verbosity, None);
+        assert!(Cli::try_parse_from(["rs-mahito", "file", "-q", "-v"]).is_err());
+    }
+}
@@ -4,7 +4,12 @@
 //! following best practices for subcommand organization and argument handling.
 
 mod args;
+mod audit;
+mod config;
+mod protected;
 mod runner;
+mod state;
 
 pub use args::Cli;
+pub use config::Config;
 pub use runner::Runner;
@@ -0,0 +1,76 @@
+//! Detection of system folders that cleaning would damage.
+//!
+//! Resetting timestamps and rewriting files under the OS or installed programs
+//! breaks code signatures, update checks and backups that rely on them. The
+//! runner refuses such targets unless `--i-know-what-im-doing` is passed:
+//! anything inside a system folder, a drive or filesystem root, and the user's
+//! profile folder itself (its subfolders are ordinary targets).
+
+use std::path::{Path, PathBuf};
+
+/// Folders whose whole subtree belongs to the system or installed programs.
+#[cfg(windows)]
+fn system_folders() -> Vec<(PathBuf, &'static str)> {
+    [
+        ("SystemRoot", "the Windows system folder"),
+        ("windir", "the Windows system folder"),
+        ("ProgramFiles", "the installed programs folder"),
+        ("ProgramFiles(x86)", "the installed programs folder"),
+        ("ProgramW6432", "the installed programs folder"),
+        ("ProgramData", "the shared application data folder"),
+    ]
+    .into_iter()
+    .filter_map(|(variable, label)| std::env::var_os(variable).map(|dir| (PathBuf::from(dir), label)))
+    .collect()
+}
+
+#[cfg(not(windows))]
+fn system_folders() -> Vec<(PathBuf, &'static str)> {
+    [
+        ("/bin", "a system binaries folder"),
+        ("/sbin", "a system binaries folder"),
+        ("/usr", "the system programs folder"),
+        ("/lib", "a system libraries folder"),
+        ("/lib64", "a system libraries folder"),
+        ("/etc", "the system configuration folder"),
+        ("/boot", "the boot folder"),
+        ("/System", "the macOS system folder"),
+        ("/Library", "the macOS shared library folder"),
+        ("/Applications", "the installed applications folder"),
+    ]
+    .into_iter()
+    .map(|(dir, label)| (PathBuf::from(dir), label))
+    .collect()
+}
+
+/// Canonicalizes a path so `C:\windows` and `\\?\C:\Windows` compare equal,
+/// keeping it as given if it can't be resolved.
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Returns what makes `path` a risky target (e.g. "inside the Windows system
+/// folder"), or `None` for ordinary files and folders.
+pub fn risky_location(path: &Path) -> Option<String> {
+    let path = canonical(path);
+
+    if path.parent().is_none() {
+        return Some("the root of a drive or filesystem".to_string());
+    }
+
+    for (folder, label) in system_folders() {
+        let folder = canonical(&folder);
+        if path == folder {
+            return Some(label.to_string());
+        }
+        if path.starts_with(&folder) {
+            return Some(format!("inside {}", label));
+        }
+    }
+
+    if dirs::home_dir().is_some_and(|home| canonical(&home) == path) {
+        return Some("your user profile folder".to_string());
+    }
+
+    None
+}
@@ -3,37 +3,106 @@
 //! This module bridges the CLI arguments with the core cleaning logic,
 //! providing user-friendly output and progress indication.
 
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Once};
+use std::time::Duration;
 
 use colored::Colorize;
 use console::Term;
-use dialoguer::{Confirm, FuzzySelect};
-use indicatif::{ProgressBar, ProgressStyle};
+use dialoguer::{Confirm, FuzzySelect, MultiSelect};
+use indicatif::{HumanBytes, ProgressBar, ProgressState, ProgressStyle};
+use serde::Serialize;
+
+use crate::core::{
+    dedupe_paths, extended_length_path, validate_sid, CapabilityStatus, CleanMode, CleanOptions, CleanReport,
+    FileResult, MetadataCleaner, ScanSummary, TimestampTarget, FORMAT_CLEANERS,
+};
+
+use super::args::{Cli, Commands, Verbosity};
+use super::audit;
+use super::config::Config;
+use super::protected;
+use super::state::ResumeState;
+
+/// How a command finished, mapped to the process exit code by `main`.
+///
+/// Hard errors (bad arguments, unreadable paths) are returned as `Err` instead
+/// and exit with 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    /// Everything succeeded (exit code 0).
+    Success,
+    /// Some files failed to clean, or `verify` found metadata (exit code 2).
+    PartialFailure,
+}
 
-use crate::core::{CleanMode, CleanOptions, CleanReport, MetadataCleaner};
+impl RunOutcome {
+    /// Returns the process exit code for this outcome.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            RunOutcome::Success => 0,
+            RunOutcome::PartialFailure => 2,
+        }
+    }
+}
 
-use super::args::{Cli, Commands};
+/// What `count` found: the files a clean would process and their size.
+#[derive(Serialize)]
+struct FileCounts {
+    files: usize,
+    bytes: u64,
+    /// Per-extension totals, most common first.
+    extensions: Vec<ExtensionCount>,
+}
+
+/// Number and combined size of the files with one extension.
+#[derive(Serialize)]
+struct ExtensionCount {
+    /// Lowercase with its dot (e.g. ".jpg"), or "(none)".
+    extension: String,
+    files: usize,
+    bytes: u64,
+}
 
 /// The command runner that executes CLI commands.
 pub struct Runner {
     cli: Cli,
-    #[allow(dead_code)]
+    config: Config,
+    /// Where the spinner and progress bar draw; they are only animated on a terminal.
     term: Term,
+    /// Set by Ctrl-C while files are being cleaned, so the run stops between files.
+    interrupted: Arc<AtomicBool>,
 }
 
 impl Runner {
-    /// Creates a new runner with the parsed CLI arguments.
-    pub fn new(cli: Cli) -> Self {
+    /// Creates a new runner with the parsed CLI arguments and config file defaults.
+    ///
+    /// The config should already have been applied to `cli.global`.
+    pub fn new(cli: Cli, config: Config) -> Self {
         Self {
             cli,
+            config,
             term: Term::stderr(),
+            interrupted: Arc::new(AtomicBool::new(false)),
         }
     }
 
     /// Runs the appropriate command based on CLI arguments.
-    pub fn run(&self) -> anyhow::Result<()> {
+    pub fn run(&self) -> anyhow::Result<RunOutcome> {
         let cwd = std::env::current_dir()?;
 
+        // A previous report replaces scanning for any of the cleaning commands
+        if let Some(report) = &self.cli.global.retry_from {
+            if matches!(
+                self.cli.command,
+                Commands::File { .. } | Commands::Dir { .. } | Commands::Recursive { .. }
+            ) {
+                return self.run_retry(report);
+            }
+        }
+
         match &self.cli.command {
             Commands::File { path } => {
                 let target = self.resolve_file_path(path.clone(), &cwd)?;
@@ -47,16 +116,34 @@ impl Runner {
                 let target = path.clone().unwrap_or_else(|| cwd.clone());
                 self.run_recursive(&target)
             }
+            Commands::Batch { list } => self.run_batch(list.as_deref()),
             Commands::Info { path } => {
                 let target = self.resolve_file_path(path.clone(), &cwd)?;
-                self.run_info(&target)
+                self.run_info(&target).map(|()| RunOutcome::Success)
+            }
+            Commands::Verify { path } => self.run_verify(path),
+            Commands::Streams { path, remove } => self.run_streams(path, remove.as_deref()).map(|()| RunOutcome::Success),
+            Commands::Doctor { path } => {
+                let target = path.clone().unwrap_or_else(std::env::temp_dir);
+                self.run_doctor(&target).map(|()| RunOutcome::Success)
+            }
+            Commands::Count { path, recursive } => {
+                let target = path.clone().unwrap_or_else(|| cwd.clone());
+                let mode = if *recursive { CleanMode::Deep } else { CleanMode::Shallow };
+                self.run_count(&target, mode).map(|()| RunOutcome::Success)
+            }
+            Commands::Restore { backup_dir, target } => {
+                let target = target.clone().unwrap_or_else(|| cwd.clone());
+                self.run_restore(backup_dir, &target)
             }
+            Commands::ReportSchema => self.run_report_schema().map(|()| RunOutcome::Success),
         }
     }
 
     /// Resolves a file path, prompting user to select if path is a directory or not provided.
     fn resolve_file_path(&self, path: Option<PathBuf>, cwd: &Path) -> anyhow::Result<PathBuf> {
-        let target = path.unwrap_or_else(|| cwd.to_path_buf());
+        // Names ending in a dot or space are only reachable through the `\\?\` form on Windows
+        let target = extended_length_path(&path.unwrap_or_else(|| cwd.to_path_buf()));
 
         if target.is_file() {
             return Ok(target);
@@ -66,6 +153,11 @@ impl Runner {
             anyhow::bail!("Path does not exist: {}", target.display());
         }
 
+        // The file picker would block a script waiting on JSON output
+        if self.cli.global.json {
+            anyhow::bail!("Not a file: {} (--json needs a file path)", target.display());
+        }
+
         // Collect files in the directory
         let files: Vec<PathBuf> = std::fs::read_dir(&target)?
             .filter_map(|entry| entry.ok())
@@ -95,90 +187,518 @@ impl Runner {
     }
 
     /// Cleans a single file.
-    fn run_file(&self, path: &Path) -> anyhow::Result<()> {
+    fn run_file(&self, path: &Path) -> anyhow::Result<RunOutcome> {
         self.print_header("Single File Mode");
 
-        let cleaner = self.create_cleaner();
+        let cleaner = self.create_cleaner()?;
 
-        // Show what we're about to do
-        println!("{} {}", "Target:".cyan(), path.display());
+        if !self.is_silent() {
+            // Show what we're about to do
+            println!("{} {}", "Target:".cyan(), path.display());
 
-        if self.cli.global.dry_run {
-            println!("{}", "[DRY RUN] No changes will be made".yellow());
+            if self.cli.global.dry_run {
+                println!("{}", "[DRY RUN] No changes will be made".yellow());
+            }
         }
 
+        self.guard_protected_location(path)?;
+
+        self.warn_camera_raw(&cleaner, std::slice::from_ref(&path.to_path_buf()));
+
         // Confirm unless --yes is passed
         if !self.confirm_action(&format!("Clean metadata from '{}'?", path.display()))? {
             println!("{}", "Operation cancelled.".yellow());
-            return Ok(());
+            return Ok(RunOutcome::Success);
         }
 
         let spinner = self.create_spinner("Cleaning file...");
 
         match cleaner.clean_file(path) {
+            Ok(result) if self.cli.global.json => {
+                spinner.finish_and_clear();
+                let mut report = CleanReport::new();
+                report.add_result(result);
+                self.write_log(&report)?;
+                self.print_report(&report)
+            }
             Ok(result) => {
                 spinner.finish_and_clear();
-                if result.success {
+
+                let mut report = CleanReport::new();
+                report.add_result(result.clone());
+                self.write_log(&report)?;
+                self.save_report(&report)?;
+                if result.success && self.cli.global.dry_run {
+                    self.print_success(&format!("Inspected: {} (no changes made)", path.display()));
+                    if result.planned_actions.is_empty() {
+                        println!("  {}", "nothing to clean".dimmed());
+                    }
+                    for action in &result.planned_actions {
+                        println!("  {} {}", "→".cyan(), action);
+                    }
+                    for warning in &result.warnings {
+                        self.print_warning(warning);
+                    }
+                    for line in self.office_diff(&cleaner, path) {
+                        println!("      {}", line);
+                    }
+                } else if result.success && self.verbosity() < Verbosity::Normal {
+                    // --errors-only and --quiet keep successes to themselves, but not their warnings
+                    if self.verbosity() == Verbosity::ErrorsOnly {
+                        for warning in &result.warnings {
+                            self.print_warning(warning);
+                        }
+                    }
+                } else if result.already_clean {
+                    self.print_success(&format!("Already clean: {} (use --force to clean anyway)", path.display()));
+                } else if result.success {
                     self.print_success(&format!(
                         "Cleaned: {} (streams removed: {}, timestamps reset: {})",
                         path.display(),
                         result.streams_removed,
                         if result.timestamps_reset { "yes" } else { "no" }
                     ));
+                    if self.cli.global.output_dir.is_some() {
+                        println!("  {} {}", "Copy:".cyan(), result.path.display());
+                    }
+                    for warning in &result.warnings {
+                        self.print_warning(warning);
+                    }
                 } else {
                     self.print_error(&format!(
                         "Failed: {} - {}",
                         path.display(),
-                        result.error.unwrap_or_default()
+                        result.error.as_deref().unwrap_or_default()
                     ));
                 }
+
+                if self.is_verbose() && !result.steps.is_empty() {
+                    println!("  {}", result.steps_summary().dimmed());
+                }
+
+                if self.is_verbose() {
+                    for line in result.changes_summary() {
+                        println!("  {}", line);
+                    }
+                }
+
+                if self.is_verbose() {
+                    if let Some(size) = result.office_size {
+                        println!(
+                            "  {} {} -> {}",
+                            "Document size:".cyan(),
+                            HumanBytes(size.before),
+                            HumanBytes(size.after)
+                        );
+                    }
+                }
+
+                if !result.success {
+                    return Ok(RunOutcome::PartialFailure);
+                }
+                self.offer_sidecars(&cleaner, path)
             }
             Err(e) => {
                 spinner.finish_and_clear();
-                self.print_error(&format!("Error: {}", e));
-                return Err(e.into());
+
+                let mut report = CleanReport::new();
+                report.add_result(FileResult::failure(path.to_path_buf(), e.to_string()));
+                self.write_log(&report)?;
+
+                if self.cli.global.json {
+                    self.print_report(&report)?;
+                } else {
+                    self.save_report(&report)?;
+                    self.print_error(&format!("Error: {}", e));
+                }
+                Err(e.into())
             }
         }
+    }
 
-        Ok(())
+    /// Offers to clean the XMP sidecars next to an image, which keep its creator
+    /// and edit history apart from the image itself.
+    fn offer_sidecars(&self, cleaner: &MetadataCleaner, image: &Path) -> anyhow::Result<RunOutcome> {
+        let mut outcome = RunOutcome::Success;
+
+        for sidecar in cleaner.xmp_sidecars(image) {
+            if self.cli.global.dry_run {
+                if !self.is_silent() {
+                    println!("  {} would offer to clean its XMP sidecar {}", "→".cyan(), sidecar.display());
+                }
+                continue;
+            }
+            if !self.confirm_action(&format!("Also clean its XMP sidecar '{}'?", sidecar.display()))? {
+                continue;
+            }
+
+            let result = cleaner
+                .clean_file(&sidecar)
+                .unwrap_or_else(|e| FileResult::failure(sidecar.clone(), e.to_string()));
+            let mut report = CleanReport::new();
+            report.add_result(result.clone());
+            self.write_log(&report)?;
+
+            if result.success {
+                if !self.is_silent() {
+                    self.print_success(&format!("Cleaned: {}", sidecar.display()));
+                }
+            } else {
+                outcome = RunOutcome::PartialFailure;
+                self.print_error(&format!(
+                    "Failed: {} - {}",
+                    sidecar.display(),
+                    result.error.as_deref().unwrap_or_default()
+                ));
+            }
+        }
+
+        Ok(outcome)
     }
 
     /// Cleans a directory (non-recursive).
-    fn run_dir(&self, path: &Path) -> anyhow::Result<()> {
+    fn run_dir(&self, path: &Path) -> anyhow::Result<RunOutcome> {
         self.print_header("Directory Mode (Non-Recursive)");
         self.run_directory_clean(path, CleanMode::Shallow)
     }
 
     /// Cleans a directory recursively.
-    fn run_recursive(&self, path: &Path) -> anyhow::Result<()> {
+    fn run_recursive(&self, path: &Path) -> anyhow::Result<RunOutcome> {
         self.print_header("Recursive Mode");
         self.run_directory_clean(path, CleanMode::Deep)
     }
 
     /// Common logic for directory cleaning.
-    fn run_directory_clean(&self, path: &Path, mode: CleanMode) -> anyhow::Result<()> {
-        let cleaner = self.create_cleaner();
+    fn run_directory_clean(&self, path: &Path, mode: CleanMode) -> anyhow::Result<RunOutcome> {
+        let cleaner = self.create_cleaner()?;
 
         // First, collect files to show the user what will be processed
-        println!("{} {}", "Target:".cyan(), path.display());
-        println!("{} {}", "Mode:".cyan(), mode);
+        if !self.is_silent() {
+            println!("{} {}", "Target:".cyan(), path.display());
+            println!("{} {}", "Mode:".cyan(), mode);
 
-        if self.cli.global.dry_run {
-            println!("{}", "[DRY RUN] No changes will be made".yellow());
+            if self.cli.global.dry_run {
+                println!("{}", "[DRY RUN] No changes will be made".yellow());
+            }
         }
 
+        self.guard_protected_location(path)?;
+
         let spinner = self.create_spinner("Scanning files...");
-        let files = cleaner.collect_files(path, mode)?;
+        let (files, symlinks) = cleaner.collect_files_and_symlinks(path, mode)?;
+        let junk = cleaner.collect_junk(path, mode)?;
         spinner.finish_and_clear();
 
-        if files.is_empty() {
+        if self.is_verbose() && !self.cli.global.json && !symlinks.is_empty() {
+            println!("{} {} symlinks (use --follow-symlinks to clean their targets)", "Skipped:".yellow(), symlinks.len());
+            for link in &symlinks {
+                println!("  {} {}", "-".yellow(), link.display());
+            }
+        }
+
+        if files.is_empty() && junk.is_empty() {
+            if self.cli.global.json {
+                return self.print_report(&CleanReport::new());
+            }
             println!("{}", "No files found to process.".yellow());
-            return Ok(());
+            return Ok(RunOutcome::Success);
+        }
+
+        if !self.is_silent() {
+            println!("{} {} files", "Found:".cyan(), files.len());
+            if !junk.is_empty() {
+                println!("{} {} junk files", "Junk:".cyan(), junk.len());
+            }
+        }
+
+        if self.is_verbose() && !self.cli.global.json && !junk.is_empty() {
+            println!("\n{}", "Junk files to delete:".cyan().bold());
+            for file in &junk {
+                println!("  {}", file.display());
+            }
+        }
+
+        // Junk files are deleted outright, so they get their own confirmation
+        let junk = if junk.is_empty()
+            || self.confirm_action(&format!("Delete {} junk files (Thumbs.db, desktop.ini, .DS_Store)?", junk.len()))?
+        {
+            junk
+        } else {
+            println!("{}", "Keeping junk files.".yellow());
+            Vec::new()
+        };
+
+        // --yes and --json never prompt, so everything found is cleaned
+        let files = if self.cli.global.interactive && !self.cli.global.yes && !self.cli.global.json && !files.is_empty() {
+            let selected = self.select_files(path, &files)?;
+            if selected.is_empty() {
+                println!("{}", "No files selected.".yellow());
+                return Ok(RunOutcome::Success);
+            }
+            selected
+        } else {
+            files
+        };
+
+        let dirs = cleaner.collect_directories(path, mode)?;
+        self.clean_files_and_dirs(&cleaner, files, junk, dirs)
+    }
+
+    /// Lets the user uncheck files to skip; every file starts checked.
+    fn select_files(&self, root: &Path, files: &[PathBuf]) -> anyhow::Result<Vec<PathBuf>> {
+        let display_names: Vec<String> = files
+            .iter()
+            .map(|p| p.strip_prefix(root).unwrap_or(p).display().to_string())
+            .collect();
+
+        let selection = MultiSelect::new()
+            .with_prompt("Select files to clean (space toggles, enter confirms)")
+            .items(&display_names)
+            .defaults(&vec![true; files.len()])
+            .interact()?;
+
+        Ok(selection.into_iter().map(|index| files[index].clone()).collect())
+    }
+
+    /// Asks about each file in turn, showing what its metadata scores as, and returns
+    /// the approved ones. Declined files are counted as skipped in `report`.
+    ///
+    /// Returns `None` if the user cancels the run with Esc.
+    fn confirm_each_file(
+        &self,
+        cleaner: &MetadataCleaner,
+        files: Vec<PathBuf>,
+        report: &mut CleanReport,
+    ) -> anyhow::Result<Option<Vec<PathBuf>>> {
+        println!("{}", "y cleans the file, n skips it, Esc cancels the run".dimmed());
+
+        let mut approved = Vec::with_capacity(files.len());
+        for (index, file) in files.into_iter().enumerate() {
+            let score = cleaner.sensitivity(&file);
+            let summary = if score.reasons.is_empty() {
+                "no metadata detected".to_string()
+            } else {
+                score.reasons.join(", ")
+            };
+            println!("{} {}", format!("[{}]", index + 1).cyan(), file.display());
+            println!("    {}", summary.dimmed());
+
+            match Confirm::new().with_prompt("Clean this file?").default(true).interact_opt()? {
+                Some(true) => approved.push(file),
+                Some(false) => report.add_skipped(),
+                None => return Ok(None),
+            }
         }
 
-        println!("{} {} files", "Found:".cyan(), files.len());
+        Ok(Some(approved))
+    }
+
+    /// Cleans the files named in a list file, or on stdin.
+    fn run_batch(&self, list: Option<&Path>) -> anyhow::Result<RunOutcome> {
+        use std::io::BufRead;
+
+        self.print_header("Batch Mode");
+
+        let cleaner = self.create_cleaner()?;
 
-        if self.cli.global.verbose {
+        let reader: Box<dyn BufRead> = match list {
+            Some(list) if list != Path::new("-") => {
+                let file = std::fs::File::open(list)
+                    .map_err(|e| anyhow::anyhow!("Cannot open list '{}': {}", list.display(), e))?;
+                Box::new(std::io::BufReader::new(file))
+            }
+            _ => Box::new(std::io::stdin().lock()),
+        };
+
+        let mut files = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            files.push(PathBuf::from(line));
+        }
+
+        if !self.is_silent() {
+            let source = match list {
+                Some(list) if list != Path::new("-") => list.display().to_string(),
+                _ => "stdin".to_string(),
+            };
+            println!("{} {}", "List:".cyan(), source);
+
+            if self.cli.global.dry_run {
+                println!("{}", "[DRY RUN] No changes will be made".yellow());
+            }
+        }
+
+        if files.is_empty() {
+            if self.cli.global.json {
+                return self.print_report(&CleanReport::new());
+            }
+            println!("{}", "No files listed.".yellow());
+            return Ok(RunOutcome::Success);
+        }
+
+        // The same file listed twice would be cleaned and counted twice
+        let listed = files.len();
+        let files = dedupe_paths(files);
+
+        if !self.is_silent() {
+            if files.len() < listed {
+                println!("{} {} files ({} duplicates ignored)", "Found:".cyan(), files.len(), listed - files.len());
+            } else {
+                println!("{} {} files", "Found:".cyan(), files.len());
+            }
+        }
+
+        self.clean_files(&cleaner, files)
+    }
+
+    /// Re-cleans the files listed in a previous JSON report.
+    fn run_retry(&self, report_path: &Path) -> anyhow::Result<RunOutcome> {
+        self.print_header("Retry Mode");
+
+        let cleaner = self.create_cleaner()?;
+
+        let file = std::fs::File::open(report_path)
+            .map_err(|e| anyhow::anyhow!("Cannot open report '{}': {}", report_path.display(), e))?;
+        let previous: CleanReport = serde_json::from_reader(std::io::BufReader::new(file))
+            .map_err(|e| anyhow::anyhow!("Invalid report '{}': {}", report_path.display(), e))?;
+
+        let files = if self.cli.global.only_failed {
+            previous.failed_paths()
+        } else {
+            previous.file_results.iter().map(|r| r.path.clone()).collect()
+        };
+
+        if !self.is_silent() {
+            println!("{} {}", "Report:".cyan(), report_path.display());
+            println!(
+                "{} {}",
+                "Mode:".cyan(),
+                if self.cli.global.only_failed { "failed files only" } else { "all reported files" }
+            );
+
+            if self.cli.global.dry_run {
+                println!("{}", "[DRY RUN] No changes will be made".yellow());
+            }
+        }
+
+        if files.is_empty() {
+            if self.cli.global.json {
+                return self.print_report(&CleanReport::new());
+            }
+            println!("{}", "No files to retry.".yellow());
+            return Ok(RunOutcome::Success);
+        }
+
+        // The same file listed twice would be cleaned and counted twice
+        let listed = files.len();
+        let files = dedupe_paths(files);
+
+        if !self.is_silent() {
+            if files.len() < listed {
+                println!("{} {} files ({} duplicates ignored)", "Found:".cyan(), files.len(), listed - files.len());
+            } else {
+                println!("{} {} files", "Found:".cyan(), files.len());
+            }
+        }
+
+        self.clean_files(&cleaner, files)
+    }
+
+    /// Confirms and cleans a list of files with a progress bar, then prints the summary.
+    fn clean_files(&self, cleaner: &MetadataCleaner, files: Vec<PathBuf>) -> anyhow::Result<RunOutcome> {
+        self.clean_files_and_dirs(cleaner, files, Vec::new(), Vec::new())
+    }
+
+    /// Like [`clean_files`](Self::clean_files), then deletes the already
+    /// confirmed `junk` files and resets the timestamps of `dirs` (deepest
+    /// first) once the files inside them are done.
+    fn clean_files_and_dirs(
+        &self,
+        cleaner: &MetadataCleaner,
+        files: Vec<PathBuf>,
+        junk: Vec<PathBuf>,
+        dirs: Vec<PathBuf>,
+    ) -> anyhow::Result<RunOutcome> {
+        let mut report = CleanReport::new();
+        let found = files.len();
+
+        // Files an earlier run with the same --state already cleaned are skipped
+        let mut state = match &self.cli.global.state {
+            Some(path) => Some(
+                ResumeState::open(path).map_err(|e| anyhow::anyhow!("Cannot read state '{}': {}", path.display(), e))?,
+            ),
+            None => None,
+        };
+        let files = match &state {
+            Some(state) => {
+                let (done, remaining): (Vec<_>, Vec<_>) = files.into_iter().partition(|file| state.contains(file));
+                if !done.is_empty() && !self.is_silent() {
+                    println!("{} {} files already cleaned by an earlier run", "Resuming:".cyan(), done.len());
+                }
+                for _ in &done {
+                    report.add_skipped();
+                }
+                remaining
+            }
+            None => files,
+        };
+        let unfinished = files.len();
+
+        // Optionally narrow down to files whose metadata looks sensitive
+        let files = if self.cli.global.only_sensitive {
+            let spinner = self.create_spinner("Scoring files...");
+            let (sensitive, ordinary): (Vec<_>, Vec<_>) = files
+                .into_iter()
+                .map(|file| {
+                    let score = cleaner.sensitivity(&file);
+                    (file, score)
+                })
+                .partition(|(_, score)| score.score >= self.cli.global.sensitivity_threshold);
+            spinner.finish_and_clear();
+
+            if !self.is_silent() {
+                println!(
+                    "{} {} files at or above sensitivity {}",
+                    "Sensitive:".cyan(),
+                    sensitive.len(),
+                    self.cli.global.sensitivity_threshold
+                );
+            }
+
+            if self.is_verbose() && !self.cli.global.json {
+                for (file, score) in &sensitive {
+                    println!("  {} {}", file.display(), format!("[{}]", score).dimmed());
+                }
+            }
+
+            for _ in &ordinary {
+                report.add_skipped();
+            }
+
+            sensitive.into_iter().map(|(file, _)| file).collect()
+        } else {
+            files
+        };
+
+        if files.is_empty() && junk.is_empty() {
+            if !self.is_silent() {
+                let message = if found == 0 {
+                    "No files to process."
+                } else if unfinished == 0 {
+                    "No files left to process: an earlier run with --state already cleaned them."
+                } else {
+                    "No sensitive files to process."
+                };
+                println!("{}", message.yellow());
+            }
+            return self.print_report(&report);
+        }
+
+        if self.is_verbose() && !self.cli.global.only_sensitive && !self.cli.global.json {
             println!("\n{}", "Files to process:".cyan().bold());
             for file in &files {
                 println!("  {}", file.display());
@@ -186,65 +706,198 @@ impl Runner {
             println!();
         }
 
-        // Confirm unless --yes is passed
-        if !self.confirm_action(&format!("Clean metadata from {} files?", files.len()))? {
-            println!("{}", "Operation cancelled.".yellow());
-            return Ok(());
+        self.warn_camera_raw(cleaner, &files);
+
+        // A read-only scan helps decide at the prompt; --yes and --confirm-each skip it
+        if !files.is_empty() && !self.cli.global.yes && !self.cli.global.confirm_each && !self.is_silent() {
+            let spinner = self.create_spinner("Scanning metadata...");
+            let summary = cleaner.preview_scan(&files)?;
+            spinner.finish_and_clear();
+            print_scan_summary(&summary);
         }
 
-        // Process with progress bar
+        // Confirm unless --yes is passed, once or file by file
+        let files = if files.is_empty() {
+            files
+        } else if self.cli.global.confirm_each {
+            let Some(approved) = self.confirm_each_file(cleaner, files, &mut report)? else {
+                println!("{}", "Operation cancelled.".yellow());
+                return Ok(RunOutcome::Success);
+            };
+            if approved.is_empty() && junk.is_empty() {
+                println!("{}", "No files approved.".yellow());
+                return self.print_report(&report);
+            }
+            approved
+        } else if self.confirm_action(&format!("Clean metadata from {} files?", files.len()))? {
+            files
+        } else {
+            println!("{}", "Operation cancelled.".yellow());
+            return Ok(RunOutcome::Success);
+        };
+
+        self.handle_interrupts();
+
+        // Process with progress bar; files are cleaned in parallel, results keep input order
         let progress = self.create_progress_bar(files.len() as u64);
-        let mut report = CleanReport::new();
 
-        for file in &files {
-            progress.set_message(format!("{}", file.file_name().unwrap_or_default().to_string_lossy()));
-
-            match cleaner.clean_file(file) {
-                Ok(result) => {
-                    if self.cli.global.verbose {
-                        if result.success {
-                            progress.println(format!(
-                                "  {} {}",
-                                "✓".green(),
-                                file.display()
-                            ));
-                        } else {
-                            progress.println(format!(
-                                "  {} {} - {}",
-                                "✗".red(),
-                                file.display(),
-                                result.error.as_deref().unwrap_or("unknown error")
-                            ));
-                        }
-                    }
-                    report.add_result(result);
-                }
-                Err(e) => {
-                    if self.cli.global.verbose {
-                        progress.println(format!(
-                            "  {} {} - {}",
-                            "✗".red(),
-                            file.display(),
-                            e
-                        ));
-                    }
-                    report.add_result(crate::core::FileResult::failure(file.clone(), e.to_string()));
-                }
+        // Protected files are skipped by the cleaner without a result, so account for them here
+        for file in files.iter().filter(|file| cleaner.is_protected(file)) {
+            if self.is_verbose() && !self.is_silent() {
+                print_above(&progress, format!(
+                    "  {} {} - {}",
+                    "-".yellow(),
+                    file.display(),
+                    "skipped (in use by rs-mahito)"
+                ));
             }
+            progress.inc(1);
+        }
 
+        let mut state_error = None;
+        let mut cleaned = cleaner.clean_files_with_progress(&files, |result| {
+            self.print_file_result(cleaner, result, &progress);
             progress.inc(1);
+
+            if let Some(state) = state.as_mut().filter(|_| result.success && !self.cli.global.dry_run) {
+                if let Err(e) = state.record(&result.path) {
+                    state_error.get_or_insert(e);
+                }
+            }
+        })?;
+        if let (Some(path), Some(e)) = (&self.cli.global.state, state_error) {
+            self.print_warning(&format!("Cannot write state '{}', so a rerun may clean some files again: {}", path.display(), e));
+        }
+        for _ in 0..report.skipped {
+            cleaned.add_skipped();
         }
 
+        // Junk and folders are left alone once interrupted
+        for file in junk.iter().take_while(|_| !cleaner.is_cancelled()) {
+            let result = cleaner.remove_junk_file(file);
+            self.print_file_result(cleaner, &result, &progress);
+            cleaned.add_junk_result(result);
+        }
+
+        // Folders last, as cleaning the files inside them updates their times
+        for dir in dirs.iter().take_while(|_| !cleaner.is_cancelled()) {
+            let result = cleaner.reset_directory_timestamps(dir);
+            self.print_file_result(cleaner, &result, &progress);
+            cleaned.add_directory_result(result);
+        }
+        let report = cleaned;
+
+        let elapsed = progress.elapsed();
         progress.finish_and_clear();
 
+        // The bar is gone once cleared, so leave a line saying how long it took
+        if !self.is_silent() {
+            let processed = files.len() - report.not_processed;
+            let rate = processed as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+            println!(
+                "{} {} files in {:.1?} ({:.1} files/s)",
+                "Processed:".cyan(),
+                processed,
+                elapsed,
+                rate
+            );
+        }
+
+        self.write_log(&report)?;
+
         // Print summary
-        self.print_report(&report);
+        self.print_report(&report)
+    }
 
-        Ok(())
+    /// Prints one file's outcome for `clean_files` in verbose mode (only failures
+    /// with `--errors-only`), and its planned changes in a dry run.
+    fn print_file_result(&self, cleaner: &MetadataCleaner, result: &FileResult, progress: &ProgressBar) {
+        progress.set_message(format!("{}", result.path.file_name().unwrap_or_default().to_string_lossy()));
+        if self.is_silent() {
+            return;
+        }
+
+        if self.verbosity() == Verbosity::ErrorsOnly && !result.success {
+            print_above(progress, format!(
+                "  {} {} - {}",
+                "✗".red(),
+                result.display_path.display(),
+                result.error.as_deref().unwrap_or("unknown error")
+            ));
+        }
+
+        if self.is_verbose() {
+            if result.already_clean {
+                print_above(progress, format!(
+                    "  {} {} - {}",
+                    "-".yellow(),
+                    result.display_path.display(),
+                    "skipped (already clean)"
+                ));
+                return;
+            } else if result.success {
+                print_above(progress, format!(
+                    "  {} {}",
+                    "✓".green(),
+                    result.display_path.display()
+                ));
+            } else {
+                print_above(progress, format!(
+                    "  {} {} - {}",
+                    "✗".red(),
+                    result.display_path.display(),
+                    result.error.as_deref().unwrap_or("unknown error")
+                ));
+            }
+
+            if !result.steps.is_empty() {
+                print_above(progress, format!("      {}", result.steps_summary().dimmed()));
+            }
+            for line in result.changes_summary() {
+                print_above(progress, format!("      {}", line));
+            }
+        }
+
+        if self.verbosity() >= Verbosity::ErrorsOnly {
+            for warning in &result.warnings {
+                print_above(progress, format!("  {} {}: {}", "!".yellow(), result.display_path.display(), warning));
+            }
+        }
+
+        // Planned changes are the point of a dry run, so show them without --verbose
+        if self.cli.global.dry_run && !self.cli.global.json && !result.planned_actions.is_empty() {
+            print_above(progress, format!("  {} {}: {}", "→".cyan(), result.display_path.display(), result.planned_actions.join(", ")));
+            for line in self.office_diff(cleaner, &result.path) {
+                print_above(progress, format!("      {}", line));
+            }
+        }
     }
 
-    /// Displays metadata information about a file.
+    /// With `--dry-run-diff`, describes each populated Office property of `path`
+    /// and what cleaning would change it to, e.g. `Author: "Jane Doe" → (cleared)`.
+    fn office_diff(&self, cleaner: &MetadataCleaner, path: &Path) -> Vec<String> {
+        if !self.cli.global.dry_run_diff || !cleaner.options().clear_properties {
+            return Vec::new();
+        }
+
+        match cleaner.read_office_properties(path) {
+            Ok(Some(properties)) => properties
+                .into_iter()
+                .map(|property| format!("{}: {:?} {} {}", property.field, property.value, "→".cyan(), "(cleared)".dimmed()))
+                .collect(),
+            Ok(None) => Vec::new(),
+            Err(e) => vec![format!("{} could not read document properties: {}", "!".yellow(), e)],
+        }
+    }
+
+    /// Displays metadata information about a file, or prints it as JSON with `--json`.
     fn run_info(&self, path: &Path) -> anyhow::Result<()> {
+        if self.cli.global.json {
+            let info = self.create_cleaner()?.file_info(path)?;
+            println!("{}", serde_json::to_string_pretty(&info)?);
+            return Ok(());
+        }
+
         self.print_header("File Information");
 
         if !path.exists() {
@@ -252,59 +905,469 @@ impl Runner {
             return Ok(());
         }
 
-        println!("{} {}\n", "File:".cyan(), path.display());
+        let info = self.create_cleaner()?.file_info(path)?;
+        let utc = self.cli.global.utc;
 
-        // Get file metadata
-        let metadata = std::fs::metadata(path)?;
+        println!("{} {}", "File:".cyan(), info.path.display());
 
-        println!("{}", "Timestamps:".cyan().bold());
-        if let Ok(created) = metadata.created() {
-            println!("  Created:  {}", format_system_time(created));
+        print_section("Timestamps");
+        if let Some(created) = info.created {
+            print_field("Created", format_system_time(created.into(), utc));
         }
-        if let Ok(modified) = metadata.modified() {
-            println!("  Modified: {}", format_system_time(modified));
+        if let Some(modified) = info.modified {
+            print_field("Modified", format_system_time(modified.into(), utc));
         }
-        if let Ok(accessed) = metadata.accessed() {
-            println!("  Accessed: {}", format_system_time(accessed));
+        if let Some(accessed) = info.accessed {
+            print_field("Accessed", format_system_time(accessed.into(), utc));
         }
 
-        println!("\n{}", "Attributes:".cyan().bold());
-        println!("  Size:     {} bytes", metadata.len());
-        println!("  Readonly: {}", metadata.permissions().readonly());
-
-        // List alternate data streams
-        #[cfg(windows)]
-        {
-            let _cleaner = MetadataCleaner::new();
-            println!("\n{}", "Alternate Data Streams:".cyan().bold());
+        if info.inverted_timestamps {
+            println!(
+                "  {} {}",
+                "⚠".yellow(),
+                "Created after last modification (copied or altered; fix with --fix-timestamp-order)".yellow()
+            );
+        }
 
-            // Use a simple approach to list streams
-            let streams = list_streams_for_display(path);
-            if streams.is_empty() {
+        if let Some(properties) = &info.office_properties {
+            print_section("Document Properties");
+            if properties.is_empty() {
                 println!("  {}", "(none found)".dimmed());
+            }
+            let rows: Vec<Vec<String>> = properties
+                .iter()
+                .map(|property| vec![format!("{}:", property.field), property.value.clone()])
+                .collect();
+            print_table(&[], &rows);
+        }
+
+        print_section("Sensitivity");
+        print_field("Score", info.sensitivity);
+
+        print_section("Remaining Metadata");
+        if info.remaining_metadata.is_empty() {
+            println!("  {}", "(none found)".dimmed());
+        } else {
+            for leak in &info.remaining_metadata {
+                println!("  {}", leak);
+            }
+        }
+
+        print_section("Attributes");
+        print_field("Size", format!("{} ({} bytes)", HumanBytes(info.size), info.size));
+        print_field("Readonly", info.readonly);
+        if let Some(object_id) = &info.object_id {
+            print_field("Object ID", object_id);
+        }
+
+        print_section("Alternate Data Streams");
+        if info.streams.is_empty() {
+            println!("  {}", "(none found)".dimmed());
+        } else {
+            let rows: Vec<Vec<String>> = info
+                .streams
+                .iter()
+                .map(|stream| vec![stream.name.clone(), HumanBytes(stream.size).to_string()])
+                .collect();
+            print_table(&["Name", "Size"], &rows);
+
+            if info.streams.len() > 1 {
+                let total: u64 = info.streams.iter().map(|stream| stream.size).sum();
+                println!("  {}", format!("{} streams, {} in total", info.streams.len(), HumanBytes(total)).dimmed());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lists a file's streams, first removing the one named by `--remove`.
+    fn run_streams(&self, path: &Path, remove: Option<&str>) -> anyhow::Result<()> {
+        if !path.is_file() {
+            anyhow::bail!("File not found: {}", path.display());
+        }
+
+        let cleaner = self.create_cleaner()?;
+
+        if !self.cli.global.json {
+            self.print_header("Streams");
+            println!("{} {}\n", "File:".cyan(), path.display());
+        }
+
+        if let Some(name) = remove {
+            if self.cli.global.dry_run {
+                if !self.cli.global.json {
+                    println!("{} {}\n", "[DRY RUN] Would remove:".yellow(), name);
+                }
             } else {
-                for stream in streams {
-                    println!("  {}", stream);
+                cleaner.remove_stream_by_name(path, name)?;
+                if !self.cli.global.json {
+                    println!("{} {}\n", "Removed:".green(), name);
+                }
+            }
+        }
+
+        let streams = cleaner.list_streams(path)?;
+
+        if self.cli.global.json {
+            println!("{}", serde_json::to_string_pretty(&streams)?);
+            return Ok(());
+        }
+
+        if streams.is_empty() {
+            println!("  {}", "(none found)".dimmed());
+        } else {
+            let rows: Vec<Vec<String>> = streams
+                .iter()
+                .map(|stream| vec![stream.name.clone(), HumanBytes(stream.size).to_string()])
+                .collect();
+            print_table(&["Name", "Size"], &rows);
+        }
+
+        Ok(())
+    }
+
+    /// Checks a file for remaining metadata, failing if any category finds some.
+    fn run_verify(&self, path: &Path) -> anyhow::Result<RunOutcome> {
+        if !path.is_file() {
+            anyhow::bail!("File not found: {}", path.display());
+        }
+
+        let cleaner = self.create_cleaner()?;
+        let leaks = cleaner.verify(path);
+
+        if self.cli.global.json {
+            println!("{}", serde_json::to_string_pretty(&leaks)?);
+        } else {
+            self.print_header("Verify");
+            println!("{} {}\n", "File:".cyan(), path.display());
+
+            let categories = cleaner.verified_categories();
+            let width = categories.iter().map(|c| c.len()).max().unwrap_or(0);
+
+            for category in categories {
+                let found: Vec<&str> = leaks
+                    .iter()
+                    .filter(|leak| leak.category == category)
+                    .map(|leak| leak.detail.as_str())
+                    .collect();
+
+                if found.is_empty() {
+                    println!("  {:width$}  {}", category, "pass".green(), width = width);
+                } else {
+                    println!("  {:width$}  {} ({})", category, "FAIL".red().bold(), found.join(", "), width = width);
                 }
             }
         }
 
+        if !leaks.is_empty() {
+            if !self.cli.global.json {
+                println!("\n{}", format!("{} item(s) of metadata remain.", leaks.len()).red().bold());
+            }
+            return Ok(RunOutcome::PartialFailure);
+        }
+
+        if !self.cli.global.json {
+            println!("\n{}", "No remaining metadata found.".green().bold());
+        }
+
+        Ok(RunOutcome::Success)
+    }
+
+    /// Counts the files a clean of `path` would process, with their total size
+    /// by extension, or prints the counts as JSON with `--json`.
+    fn run_count(&self, path: &Path, mode: CleanMode) -> anyhow::Result<()> {
+        let spinner = self.create_spinner("Scanning files...");
+        let files = self.create_cleaner()?.collect_files(path, mode)?;
+        spinner.finish_and_clear();
+
+        // Files without an extension are grouped under "(none)"
+        let mut by_extension: BTreeMap<String, ExtensionCount> = BTreeMap::new();
+        for file in &files {
+            let extension = file
+                .extension()
+                .map_or_else(|| "(none)".to_string(), |e| format!(".{}", e.to_string_lossy().to_lowercase()));
+            let count = by_extension.entry(extension.clone()).or_insert_with(|| ExtensionCount {
+                extension,
+                files: 0,
+                bytes: 0,
+            });
+            count.files += 1;
+            count.bytes += std::fs::metadata(file).map_or(0, |metadata| metadata.len());
+        }
+
+        // Most common first; ties keep alphabetical order
+        let mut extensions: Vec<ExtensionCount> = by_extension.into_values().collect();
+        extensions.sort_by_key(|count| std::cmp::Reverse(count.files));
+        let counts = FileCounts {
+            files: files.len(),
+            bytes: extensions.iter().map(|count| count.bytes).sum(),
+            extensions,
+        };
+
+        if self.cli.global.json {
+            println!("{}", serde_json::to_string_pretty(&counts)?);
+            return Ok(());
+        }
+
+        self.print_header("Count");
+        println!("{} {}", "Target:".cyan(), path.display());
+        println!("{} {}", "Mode:".cyan(), mode);
+
+        print_section("Totals");
+        print_field("Files", counts.files);
+        print_field("Size", format!("{} ({} bytes)", HumanBytes(counts.bytes), counts.bytes));
+
+        if !counts.extensions.is_empty() {
+            print_section("By Extension");
+            let rows: Vec<Vec<String>> = counts
+                .extensions
+                .iter()
+                .map(|count| vec![count.extension.clone(), count.files.to_string(), HumanBytes(count.bytes).to_string()])
+                .collect();
+            print_table(&["Extension", "Files", "Size"], &rows);
+        }
+
+        Ok(())
+    }
+
+    /// Probes each cleaning capability against scratch files and prints the results.
+    fn run_doctor(&self, dir: &Path) -> anyhow::Result<()> {
+        self.print_header("Doctor");
+
+        println!("{} {}\n", "Scratch directory:".cyan(), dir.display());
+
+        let spinner = self.create_spinner("Probing capabilities...");
+        let capabilities = self.create_cleaner()?.diagnose(dir)?;
+        spinner.finish_and_clear();
+
+        let width = capabilities.iter().map(|c| c.name.len()).max().unwrap_or(0);
+
+        for capability in &capabilities {
+            let status = capability.status.to_string();
+            let status = match capability.status {
+                CapabilityStatus::Available => status.green(),
+                CapabilityStatus::Limited(_) | CapabilityStatus::NeedsAdmin => status.yellow(),
+                CapabilityStatus::Unsupported(_) => status.dimmed(),
+                CapabilityStatus::Failed(_) => status.red(),
+            };
+            println!("  {:width$}  {}", capability.name, status, width = width);
+        }
+
+        if capabilities.iter().any(|c| matches!(c.status, CapabilityStatus::Failed(_))) {
+            println!("\n{}", "Some capabilities failed on this system.".yellow());
+        } else {
+            println!("\n{}", "All supported capabilities are working.".green().bold());
+        }
+
+        Ok(())
+    }
+
+    /// Copies the backups of `target` in `backup_dir` back over the cleaned files.
+    fn run_restore(&self, backup_dir: &Path, target: &Path) -> anyhow::Result<RunOutcome> {
+        if !backup_dir.is_dir() {
+            anyhow::bail!("Backup directory not found: {}", backup_dir.display());
+        }
+
+        let cleaner = self.create_cleaner()?;
+        let backups = cleaner.find_backups(backup_dir, target)?;
+
+        self.print_header("Restore");
+        if !self.is_silent() {
+            println!("{} {}", "Backups:".cyan(), backup_dir.display());
+            println!("{} {}", "Target:".cyan(), target.display());
+            println!("{} {} backups\n", "Found:".cyan(), backups.len());
+        }
+
+        if backups.is_empty() {
+            if !self.is_silent() {
+                println!("{}", "No backups found for the target.".yellow());
+            }
+            return Ok(RunOutcome::Success);
+        }
+
+        if self.cli.global.dry_run {
+            for (_, original) in &backups {
+                println!("{} {}", "Would restore:".yellow(), original.display());
+            }
+            return Ok(RunOutcome::Success);
+        }
+
+        if !self.confirm_action(&format!("Overwrite {} files with their backups?", backups.len()))? {
+            println!("{}", "Operation cancelled.".yellow());
+            return Ok(RunOutcome::Success);
+        }
+
+        let mut failed = 0;
+        for (backup, original) in &backups {
+            match cleaner.restore_backup(backup, original) {
+                Ok(()) if self.verbosity() >= Verbosity::Normal => {
+                    self.print_success(&format!("Restored: {}", original.display()));
+                }
+                Ok(()) => {}
+                Err(e) => {
+                    failed += 1;
+                    if self.verbosity() >= Verbosity::ErrorsOnly {
+                        self.print_error(&e.to_string());
+                    }
+                }
+            }
+        }
+
+        if failed > 0 {
+            if !self.is_silent() {
+                println!("\n{}", format!("{} of {} backups could not be restored.", failed, backups.len()).yellow());
+            }
+            return Ok(RunOutcome::PartialFailure);
+        }
+        if !self.is_silent() {
+            println!("\n{}", format!("Restored {} files.", backups.len()).green().bold());
+        }
+        Ok(RunOutcome::Success)
+    }
+
+    /// Prints the JSON Schema of `CleanReport`.
+    fn run_report_schema(&self) -> anyhow::Result<()> {
+        let schema = schemars::schema_for!(CleanReport);
+        println!("{}", serde_json::to_string_pretty(&schema)?);
         Ok(())
     }
 
+    /// Picks what timestamps are reset to: the --set-time, a random time per
+    /// file within --randomize-time, or the config file's target.
+    fn timestamp_target(&self) -> anyhow::Result<TimestampTarget> {
+        if let Some(time) = self.cli.global.set_time {
+            return Ok(TimestampTarget::Fixed(time));
+        }
+
+        Ok(match self.cli.global.randomize_time {
+            // A fresh seed per run, so separate runs never share a pattern of times
+            Some(range) => TimestampTarget::Random { range, seed: fastrand::u64(..) },
+            None => self.config.timestamp_target()?.unwrap_or_default(),
+        })
+    }
+
     /// Creates a cleaner with the appropriate options.
-    fn create_cleaner(&self) -> MetadataCleaner {
-        let options = CleanOptions::all()
+    fn create_cleaner(&self) -> anyhow::Result<MetadataCleaner> {
+        let xml_encoding = match &self.cli.global.input_encoding {
+            Some(label) => Some(
+                encoding_rs::Encoding::for_label(label.as_bytes())
+                    .ok_or_else(|| anyhow::anyhow!("Unknown encoding: {}", label))?,
+            ),
+            None => None,
+        };
+
+        let mut options = CleanOptions::all()
             .with_dry_run(self.cli.global.dry_run)
-            .with_verbose(self.cli.global.verbose)
-            .with_admin(self.cli.global.admin);
+            .with_verbose(self.is_verbose())
+            .with_admin(self.cli.global.admin)
+            .with_clear_owner(self.config.clear_owner(self.cli.global.admin))
+            .with_owner_sid(self.cli.global.owner_sid.clone())
+            .with_fix_timestamp_order(self.cli.global.fix_timestamp_order)
+            .with_in_place(self.cli.global.in_place)
+            .with_preserve_readonly(self.cli.global.preserve_readonly)
+            .with_follow_symlinks(self.cli.global.follow_symlinks)
+            .with_max_depth(self.cli.global.max_depth)
+            .with_retries(self.cli.global.retry)
+            .with_timeout(self.cli.global.timeout.map(|secs| Duration::from_secs(secs.get())))
+            .with_force(self.cli.global.force)
+            .with_hash(self.cli.global.hash)
+            .with_verify(self.cli.global.verify)
+            .with_into_archives(self.cli.global.into_archives, self.cli.global.max_archive_depth)
+            .with_metadata_only(self.cli.global.metadata_only)
+            .with_all_streams(self.cli.global.all_streams)
+            .with_keep_streams(self.cli.global.keep_stream.clone())
+            .with_keep_stream_patterns(self.cli.global.keep_stream_pattern.clone())
+            .with_shred_streams(self.cli.global.shred_streams)
+            .with_jobs(self.cli.global.jobs.map(|jobs| jobs.get()))
+            .with_parallel_archives(self.cli.global.parallel_archives.map(|limit| limit.get()))
+            .with_cancel_flag(Arc::clone(&self.interrupted))
+            .with_backup_dir(self.cli.global.backup.clone())
+            .with_output_dir(self.cli.global.output_dir.clone(), self.output_root())
+            .with_include_globs(self.cli.global.include.clone())
+            .with_exclude_globs(self.cli.global.exclude.clone())
+            .with_older_than(self.cli.global.older_than)
+            .with_newer_than(self.cli.global.newer_than)
+            .with_clean_dirs(self.cli.global.clean_dirs)
+            .with_remove_junk(self.cli.global.remove_junk)
+            .with_clear_timestamps(!self.cli.global.no_timestamps)
+            .with_clear_streams(!self.cli.global.no_streams)
+            .with_clear_object_id(!self.cli.global.no_object_id)
+            .with_clear_properties(self.config.clear_properties.unwrap_or(true) && !self.cli.global.no_properties)
+            .with_timestamp_target(self.timestamp_target()?)
+            .with_xml_fallback_encoding(xml_encoding)
+            .with_email_header_classes(self.cli.global.email_headers.iter().map(|&c| c.into()).collect());
+
+        if let Some(sid) = &options.owner_sid {
+            validate_sid(sid)?;
+        }
 
-        MetadataCleaner::with_options(options)
+        if !options.has_enabled_step() {
+            anyhow::bail!("Nothing to clean: every cleaning step is disabled");
+        }
+
+        // Never clean the running binary if the target folder contains it
+        if let Ok(exe) = std::env::current_exe() {
+            options = options.with_protected_path(exe);
+        }
+
+        // Nor the audit log being written
+        if let Some(log) = &self.cli.global.log {
+            options = options.with_protected_path(log);
+        }
+
+        // Nor the resume state being appended to
+        if let Some(state) = &self.cli.global.state {
+            options = options.with_protected_path(state);
+        }
+
+        // Nor the report this run replaces
+        if let Some(report) = &self.cli.global.report_file {
+            options = options.with_protected_path(report);
+        }
+
+        Ok(MetadataCleaner::with_options(options))
+    }
+
+    /// Makes Ctrl-C stop the run between files instead of killing the process,
+    /// which could cut a file off halfway through being rewritten.
+    ///
+    /// Installed only once cleaning starts, so Ctrl-C at a prompt still quits
+    /// at once; a second Ctrl-C quits without waiting for the files in progress.
+    fn handle_interrupts(&self) {
+        static INSTALL: Once = Once::new();
+
+        INSTALL.call_once(|| {
+            let interrupted = Arc::clone(&self.interrupted);
+            let installed = ctrlc::set_handler(move || {
+                if interrupted.swap(true, Ordering::SeqCst) {
+                    std::process::exit(130);
+                }
+                eprintln!("\n{}", "Interrupted; finishing the files in progress (Ctrl-C again to quit now)...".yellow());
+            });
+            if let Err(e) = installed {
+                self.print_warning(&format!("Ctrl-C will stop cleaning immediately: {}", e));
+            }
+        });
+    }
+
+    /// Returns the folder whose layout `--output-dir` mirrors: the target folder of
+    /// `dir`/`recursive`, or the folder holding the file of `file`.
+    fn output_root(&self) -> Option<PathBuf> {
+        let cwd = std::env::current_dir().ok()?;
+        match &self.cli.command {
+            Commands::Dir { path } | Commands::Recursive { path } => Some(path.clone().unwrap_or(cwd)),
+            Commands::File { path: Some(path) } if !path.is_dir() => {
+                Some(path.parent().filter(|parent| !parent.as_os_str().is_empty()).map_or(cwd, Path::to_path_buf))
+            }
+            Commands::File { path } => Some(path.clone().unwrap_or(cwd)),
+            _ => None,
+        }
     }
 
     /// Confirms an action with the user.
     fn confirm_action(&self, message: &str) -> anyhow::Result<bool> {
-        if self.cli.global.yes {
+        // A prompt would block scripts reading JSON output
+        if self.cli.global.yes || self.cli.global.json {
             return Ok(true);
         }
 
@@ -314,8 +1377,27 @@ impl Runner {
             .interact()?)
     }
 
+    /// Returns how much to print, from the command line or the config file.
+    fn verbosity(&self) -> Verbosity {
+        self.cli.global.verbosity.unwrap_or_default()
+    }
+
+    /// Returns true if every file's steps and changes should be printed.
+    fn is_verbose(&self) -> bool {
+        self.verbosity() == Verbosity::Verbose
+    }
+
+    /// Returns true if nothing but JSON or fatal errors should be printed.
+    fn is_silent(&self) -> bool {
+        self.cli.global.json || self.verbosity() == Verbosity::Quiet
+    }
+
     /// Prints a header for a command.
     fn print_header(&self, title: &str) {
+        if self.is_silent() {
+            return;
+        }
+
         println!("\n{}", "━".repeat(50).dimmed());
         println!("{} {}", "▶".cyan(), title.bold());
         println!("{}\n", "━".repeat(50).dimmed());
@@ -326,13 +1408,89 @@ impl Runner {
         println!("{} {}", "✓".green().bold(), message);
     }
 
+    /// Prints a warning message.
+    fn print_warning(&self, message: &str) {
+        eprintln!("{} {}", "⚠".yellow().bold(), message.yellow());
+    }
+
+    /// Refuses system folders, drive roots and the profile folder itself unless
+    /// `--i-know-what-im-doing` is passed; `--yes` is not enough. Dry runs only warn.
+    fn guard_protected_location(&self, path: &Path) -> anyhow::Result<()> {
+        let Some(location) = protected::risky_location(path) else {
+            return Ok(());
+        };
+
+        self.print_warning(&format!("{} is {}.", path.display(), location));
+        self.print_warning("Cleaning it rewrites files the OS, installed programs and your settings rely on:");
+        self.print_warning("signatures can stop verifying, updates and backups can misbehave, and without");
+        self.print_warning("--backup it can't be undone.");
+
+        if self.cli.global.dry_run || self.cli.global.i_know_what_im_doing {
+            return Ok(());
+        }
+        anyhow::bail!("Refusing to clean {}; pass --i-know-what-im-doing to proceed anyway", path.display())
+    }
+
+    /// Warns before camera RAW files are edited, since RAW layouts vary by camera
+    /// and the support is experimental.
+    fn warn_camera_raw(&self, cleaner: &MetadataCleaner, files: &[PathBuf]) {
+        let options = cleaner.options();
+        if self.cli.global.json || options.dry_run || !options.clear_properties {
+            return;
+        }
+
+        let raw = files.iter().filter(|file| cleaner.is_camera_raw(file)).count();
+        if raw > 0 {
+            self.print_warning(&format!(
+                "Experimental: {} camera RAW file(s) will have their GPS position and dates edited in place. \
+                 Keep a copy (--backup or --output-dir) until you've checked they still open.",
+                raw
+            ));
+        }
+    }
+
     /// Prints an error message.
     fn print_error(&self, message: &str) {
         eprintln!("{} {}", "✗".red().bold(), message);
     }
 
-    /// Prints a summary report.
-    fn print_report(&self, report: &CleanReport) {
+    /// Appends the report's files to the `--log` audit trail, if one was requested.
+    fn write_log(&self, report: &CleanReport) -> anyhow::Result<()> {
+        let Some(log) = &self.cli.global.log else {
+            return Ok(());
+        };
+
+        audit::append(log, report, self.cli.global.dry_run)
+            .map_err(|e| anyhow::anyhow!("Cannot write log '{}': {}", log.display(), e))
+    }
+
+    /// Writes the report as JSON to `--report-file`, if given.
+    fn save_report(&self, report: &CleanReport) -> anyhow::Result<()> {
+        let Some(path) = &self.cli.global.report_file else {
+            return Ok(());
+        };
+
+        let json = serde_json::to_string_pretty(report)?;
+        std::fs::write(path, json + "\n")
+            .map_err(|e| anyhow::anyhow!("Cannot write report '{}': {}", path.display(), e))
+    }
+
+    /// Prints a summary report, or the whole report as JSON with `--json`, and
+    /// saves it to `--report-file`.
+    ///
+    /// Returns `PartialFailure` if any file failed.
+    fn print_report(&self, report: &CleanReport) -> anyhow::Result<RunOutcome> {
+        self.save_report(report)?;
+        let outcome = if report.is_complete_success() { RunOutcome::Success } else { RunOutcome::PartialFailure };
+
+        if self.cli.global.json {
+            println!("{}", serde_json::to_string_pretty(report)?);
+            return Ok(outcome);
+        }
+        if self.is_silent() {
+            return Ok(outcome);
+        }
+
         println!("\n{}", "━".repeat(50).dimmed());
         println!("{}", "Summary".bold());
         println!("{}", "━".repeat(50).dimmed());
@@ -346,17 +1504,53 @@ impl Runner {
             println!("  Failed:          {}", report.failed);
         }
 
+        if report.with_warnings > 0 {
+            println!("  {} {}", "With warnings:".yellow(), report.with_warnings);
+        }
+
+        if report.skipped > 0 {
+            println!("  {} {}", "Skipped:".yellow(), report.skipped);
+        }
+
         println!("  Streams removed: {}", report.total_streams_removed);
+        println!("  EXIF stripped:   {}", report.total_exif_stripped);
+
+        if report.junk_removed > 0 {
+            println!("  Junk removed:    {}", report.junk_removed);
+        }
 
-        if report.is_complete_success() {
+        if report.directories_reset > 0 {
+            println!("  Folders reset:   {}", report.directories_reset);
+        }
+
+        // Only worth a line when cleaning actually changed sizes
+        match report.bytes_reclaimed() {
+            0 => {}
+            saved if saved > 0 => println!("  Reclaimed:       {}", HumanBytes(saved as u64)),
+            grown => println!("  Size increase:   {}", HumanBytes(grown.unsigned_abs())),
+        }
+
+        if report.not_processed > 0 {
+            println!("  {} {}", "Not processed:".yellow(), report.not_processed);
+        }
+
+        if report.not_processed > 0 {
+            println!("\n{}", "Interrupted; the remaining files were left untouched.".yellow());
+        } else if report.is_complete_success() {
             println!("\n{}", "All files cleaned successfully!".green().bold());
         } else {
             println!("\n{}", "Some files could not be cleaned.".yellow());
         }
+
+        Ok(outcome)
     }
 
     /// Creates a spinner for indeterminate progress.
     fn create_spinner(&self, message: &str) -> ProgressBar {
+        if self.is_silent() || !self.term.is_term() {
+            return ProgressBar::hidden();
+        }
+
         let spinner = ProgressBar::new_spinner();
         spinner.set_style(
             ProgressStyle::default_spinner()
@@ -371,11 +1565,18 @@ impl Runner {
 
     /// Creates a progress bar for determinate progress.
     fn create_progress_bar(&self, total: u64) -> ProgressBar {
+        if self.is_silent() || !self.term.is_term() {
+            return ProgressBar::hidden();
+        }
+
         let progress = ProgressBar::new(total);
         progress.set_style(
             ProgressStyle::default_bar()
-                .template("{spinner:.cyan} [{elapsed_precise}] [{bar:40.cyan/dim}] {pos}/{len} {msg}")
+                .template("{spinner:.cyan} [{elapsed_precise}] [{bar:40.cyan/dim}] {pos}/{len} ({rate}, ETA {eta}) {msg}")
                 .unwrap()
+                .with_key("rate", |state: &ProgressState, w: &mut dyn std::fmt::Write| {
+                    let _ = write!(w, "{:.1} files/s", state.per_sec());
+                })
                 .progress_chars("█▓░"),
         );
         progress.enable_steady_tick(std::time::Duration::from_millis(100));
@@ -383,109 +1584,156 @@ impl Runner {
     }
 }
 
-/// Formats a SystemTime for display.
-fn format_system_time(time: std::time::SystemTime) -> String {
-    use std::time::UNIX_EPOCH;
-
-    match time.duration_since(UNIX_EPOCH) {
-        Ok(duration) => {
-            let secs = duration.as_secs();
-            chrono_lite(secs)
-        }
-        Err(_) => "(invalid time)".to_string(),
+/// Prints a line above the progress bar, or plainly when the bar is hidden (off a
+/// terminal), where `ProgressBar::println` would drop it.
+fn print_above(progress: &ProgressBar, line: String) {
+    if progress.is_hidden() {
+        println!("{}", line);
+    } else {
+        progress.println(line);
     }
 }
 
-/// Simple timestamp formatting without external chrono dependency.
-fn chrono_lite(unix_secs: u64) -> String {
-    // Calculate date components from Unix timestamp
-    let days = unix_secs / 86400;
-    let remaining_secs = unix_secs % 86400;
-    let hours = remaining_secs / 3600;
-    let minutes = (remaining_secs % 3600) / 60;
-    let seconds = remaining_secs % 60;
-
-    // Approximate year/month/day (simplified, not accounting for all leap years)
-    let mut year = 1970u64;
-    let mut remaining_days = days;
+/// Describes the files a verifier category found metadata in, e.g. "Office docs with authorship".
+fn category_description(category: &str) -> String {
+    if let Some(format) = FORMAT_CLEANERS.iter().find(|format| format.name() == category) {
+        return format.description().to_string();
+    }
 
-    loop {
-        let days_in_year = if is_leap_year(year) { 366 } else { 365 };
-        if remaining_days < days_in_year {
-            break;
-        }
-        remaining_days -= days_in_year;
-        year += 1;
+    match category {
+        "streams" => "with alternate streams",
+        "timestamps" => "with non-neutral timestamps",
+        "object-id" => "with NTFS object IDs",
+        "attributes" => "with hidden, system or archive attributes",
+        "timestamp-order" => "created after they were modified",
+        other => return format!("with {} metadata", other),
     }
+    .to_string()
+}
 
-    let months = [31, 28 + if is_leap_year(year) { 1 } else { 0 }, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
-    let mut month = 1;
-    for days_in_month in months {
-        if remaining_days < days_in_month {
-            break;
-        }
-        remaining_days -= days_in_month;
-        month += 1;
+/// Prints what a preview scan found, most common categories first, e.g.
+/// "120 files, 34 with alternate streams, 12 Office docs with authorship".
+fn print_scan_summary(summary: &ScanSummary) {
+    let mut categories: Vec<(&String, &usize)> = summary.categories.iter().collect();
+    categories.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+
+    let mut parts = vec![format!("{} files", summary.total_files)];
+    if categories.is_empty() {
+        parts.push("no metadata detected".to_string());
     }
-    let day = remaining_days + 1;
+    parts.extend(categories.iter().map(|(category, count)| format!("{} {}", count, category_description(category))));
 
-    format!(
-        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
-        year, month, day, hours, minutes, seconds
-    )
+    println!("{} {}", "Detected:".cyan(), parts.join(", "));
 }
 
-fn is_leap_year(year: u64) -> bool {
-    (year % 4 == 0 && year % 100 != 0) || (year % 400 == 0)
+/// Width of the labels in `info` sections, so values line up from one section to the next.
+const FIELD_LABEL_WIDTH: usize = 10;
+
+/// Prints an `info` section heading, separated from the previous section by a blank line.
+fn print_section(title: &str) {
+    println!("\n{}", format!("{}:", title).cyan().bold());
 }
 
-/// Lists alternate data streams for display purposes.
-#[cfg(windows)]
-fn list_streams_for_display(path: &Path) -> Vec<String> {
-    use windows::core::PCWSTR;
-    use windows::Win32::Storage::FileSystem::{FindClose, FindFirstStreamW, FindNextStreamW, WIN32_FIND_STREAM_DATA};
-
-    let wide_path: Vec<u16> = path.as_os_str()
-        .to_string_lossy()
-        .encode_utf16()
-        .chain(std::iter::once(0))
-        .collect();
-
-    let mut streams = Vec::new();
-    let mut find_data = WIN32_FIND_STREAM_DATA::default();
-
-    unsafe {
-        let handle = FindFirstStreamW(
-            PCWSTR(wide_path.as_ptr()),
-            windows::Win32::Storage::FileSystem::FindStreamInfoStandard,
-            &mut find_data as *mut _ as *mut _,
-            0,
-        );
+/// Prints an indented `label: value` line with the value aligned to the other fields.
+fn print_field(label: &str, value: impl std::fmt::Display) {
+    println!("  {:width$}  {}", format!("{}:", label), value, width = FIELD_LABEL_WIDTH);
+}
 
-        if let Ok(h) = handle {
-            if !h.is_invalid() {
-                loop {
-                    let stream_name = String::from_utf16_lossy(
-                        &find_data.cStreamName[..find_data.cStreamName.iter().position(|&c| c == 0).unwrap_or(find_data.cStreamName.len())]
-                    );
+/// Prints rows as an indented table, padding each column to its widest cell
+/// (and at least to the field labels' width).
+///
+/// With `headers`, a dimmed header row comes first and the last column (sizes,
+/// counts) is right-aligned; without, the rows are plain label/value pairs.
+fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let columns = rows.iter().map(Vec::len).chain([headers.len()]).max().unwrap_or(0);
+    let mut widths = vec![0; columns];
+    for row in rows.iter().map(|row| row.iter().map(String::as_str).collect::<Vec<_>>()).chain([headers.to_vec()]) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(console::measure_text_width(cell));
+        }
+    }
+    if let Some(first) = widths.first_mut() {
+        *first = (*first).max(FIELD_LABEL_WIDTH);
+    }
 
-                    if !stream_name.is_empty() && stream_name != "::$DATA" {
-                        streams.push(format!("{} ({} bytes)", stream_name, find_data.StreamSize));
-                    }
+    let right_align_last = !headers.is_empty();
+    let format_row = |cells: &[&str]| {
+        let last = cells.len().saturating_sub(1);
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(index, (cell, &width))| match index {
+                // Trailing padding on a left-aligned last column would only add spaces
+                _ if index == last && !right_align_last => cell.to_string(),
+                _ if index == last => console::pad_str(cell, width, console::Alignment::Right, None).into_owned(),
+                _ => console::pad_str(cell, width, console::Alignment::Left, None).into_owned(),
+            })
+            .collect();
+        format!("  {}", padded.join("  "))
+    };
 
-                    if FindNextStreamW(h, &mut find_data as *mut _ as *mut _).is_err() {
-                        break;
-                    }
-                }
-                let _ = FindClose(h);
-            }
-        }
+    if !headers.is_empty() {
+        println!("{}", format_row(headers).dimmed());
+    }
+    for row in rows {
+        println!("{}", format_row(&row.iter().map(String::as_str).collect::<Vec<_>>()));
     }
+}
 
-    streams
+/// Formats a SystemTime for display, labelled with its timezone.
+///
+/// Local time is used unless `utc` is set. If the local offset can't be
+/// determined (e.g. another thread is running on Unix), UTC is shown instead.
+fn format_system_time(time: std::time::SystemTime, utc: bool) -> String {
+    use time::macros::format_description;
+    use time::{OffsetDateTime, UtcOffset};
+
+    let time = OffsetDateTime::from(time);
+    let offset = if utc {
+        UtcOffset::UTC
+    } else {
+        UtcOffset::local_offset_at(time).unwrap_or(UtcOffset::UTC)
+    };
+    let time = time.to_offset(offset);
+
+    let formatted = if offset.is_utc() {
+        time.format(format_description!("[year]-[month]-[day] [hour]:[minute]:[second] UTC"))
+    } else {
+        time.format(format_description!(
+            "[year]-[month]-[day] [hour]:[minute]:[second] [offset_hour sign:mandatory]:[offset_minute]"
+        ))
+    };
+
+    formatted.unwrap_or_else(|_| "(invalid time)".to_string())
 }
 
-#[cfg(not(windows))]
-fn list_streams_for_display(_path: &Path) -> Vec<String> {
-    Vec::new()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    /// Formats seconds since the Unix epoch in UTC.
+    fn format_utc(secs: u64) -> String {
+        format_system_time(UNIX_EPOCH + Duration::from_secs(secs), true)
+    }
+
+    #[test]
+    fn formats_the_unix_epoch() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00:00 UTC");
+    }
+
+    #[test]
+    fn formats_leap_days() {
+        // 2000 is divisible by 400, so it has a February 29
+        assert_eq!(format_utc(951_782_400), "2000-02-29 00:00:00 UTC");
+        // 2100 is divisible by 100 but not 400, so February 28 is followed by March 1
+        assert_eq!(format_utc(4_107_456_000 + 86_400), "2100-03-01 00:00:00 UTC");
+    }
+
+    #[test]
+    fn formats_year_boundaries() {
+        assert_eq!(format_utc(946_684_799), "1999-12-31 23:59:59 UTC");
+        assert_eq!(format_utc(946_684_799 + 1), "2000-01-01 00:00:00 UTC");
+    }
 }
@@ -0,0 +1,72 @@
+//! Resume state written with `--state`.
+//!
+//! A plain list of the files cleaned so far, one canonical path per line,
+//! appended as each file finishes so an interrupted run still leaves an
+//! accurate record. A later run given the same file skips everything listed
+//! without checking again whether those files are still clean.
+
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Files cleaned by earlier runs, and the state file new ones are appended to.
+pub struct ResumeState {
+    path: PathBuf,
+    done: HashSet<PathBuf>,
+    /// Opened on the first record, so dry runs never create the file.
+    file: Option<File>,
+    /// Whether the last line was cut off by an interrupted write.
+    needs_newline: bool,
+}
+
+impl ResumeState {
+    /// Reads the files listed in the state at `path`; a missing file is an empty state.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            done: text.lines().filter(|line| !line.is_empty()).map(PathBuf::from).collect(),
+            file: None,
+            needs_newline: !text.is_empty() && !text.ends_with('\n'),
+        })
+    }
+
+    /// Returns true if `file` was cleaned by an earlier run.
+    pub fn contains(&self, file: &Path) -> bool {
+        self.done.contains(&canonical(file))
+    }
+
+    /// Appends a cleaned file, writing it through so an interrupt can't lose it.
+    pub fn record(&mut self, file: &Path) -> io::Result<()> {
+        let file = canonical(file);
+
+        let state = match &mut self.file {
+            Some(state) => state,
+            None => self.file.insert(OpenOptions::new().create(true).append(true).open(&self.path)?),
+        };
+
+        // One write per line, so the lines of a killed run are never interleaved
+        let mut line = String::new();
+        if std::mem::take(&mut self.needs_newline) {
+            line.push('\n');
+        }
+        line.push_str(&file.to_string_lossy());
+        line.push('\n');
+        state.write_all(line.as_bytes())?;
+
+        self.done.insert(file);
+        Ok(())
+    }
+}
+
+/// Canonicalizes a path so relative and absolute spellings of a file match,
+/// keeping it as given if it can't be resolved.
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
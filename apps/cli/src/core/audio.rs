@@ -0,0 +1,354 @@
+//! ID3 tag removal for MP3, FLAC and M4A audio.
+//!
+//! ID3v2 tags (artist, album, encoder, comments, cover art) sit in a block at
+//! the start of the file whose length is a synchsafe integer in its header;
+//! several can be stacked. MP3s may also end with a fixed 128-byte ID3v1 tag.
+//! Both are cut off, leaving the audio frames byte for byte. FLAC's own Vorbis
+//! comments and MP4 metadata atoms are not ID3 and are left alone.
+//!
+//! WAV files are RIFF containers instead: artist, software and comments live
+//! in a `LIST` chunk of type `INFO`, and broadcast WAVs add a `bext` chunk with
+//! the originator and origination date. Those chunks are dropped and the RIFF
+//! size fixed up, keeping `fmt ` and `data` as they are.
+
+use std::ops::Range;
+
+/// Audio formats whose ID3 tags can be stripped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioKind {
+    Mp3,
+    Flac,
+    M4a,
+}
+
+impl AudioKind {
+    /// Returns the audio format for a file extension, if tag stripping supports it.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "mp3" => Some(AudioKind::Mp3),
+            "flac" => Some(AudioKind::Flac),
+            "m4a" => Some(AudioKind::M4a),
+            _ => None,
+        }
+    }
+
+    /// Returns true if `data` starts the way this format's audio does once tags are removed.
+    fn starts_audio(self, data: &[u8]) -> bool {
+        match self {
+            // Frame sync: eleven set bits
+            AudioKind::Mp3 => data.len() >= 2 && data[0] == 0xFF && data[1] & 0xE0 == 0xE0,
+            AudioKind::Flac => data.starts_with(b"fLaC"),
+            AudioKind::M4a => data.get(4..8) == Some(b"ftyp"),
+        }
+    }
+}
+
+/// Size of an ID3v2 header, and of its optional footer.
+const ID3V2_HEADER_LEN: usize = 10;
+
+/// Footer-present flag in the ID3v2 header.
+const ID3V2_FLAG_FOOTER: u8 = 0x10;
+
+/// Size of the ID3v1 trailer.
+const ID3V1_LEN: usize = 128;
+
+/// Decodes a 28-bit synchsafe integer (the top bit of each byte is always zero).
+///
+/// Returns `None` if any byte has its top bit set, which means it isn't a valid tag.
+fn synchsafe(bytes: [u8; 4]) -> Option<usize> {
+    if bytes.iter().any(|b| b & 0x80 != 0) {
+        return None;
+    }
+    Some(bytes.iter().fold(0, |size, &b| (size << 7) | usize::from(b)))
+}
+
+/// Returns the `(version, length)` of the ID3v2 tags at the start of `data`,
+/// `length` covering headers, bodies and footers.
+fn id3v2_tags(data: &[u8]) -> Option<Vec<(u8, usize)>> {
+    let mut tags = Vec::new();
+    let mut offset = 0;
+
+    while data[offset..].starts_with(b"ID3") {
+        let header = data.get(offset..offset + ID3V2_HEADER_LEN)?;
+        let version = header[3];
+        let size = synchsafe(header[6..10].try_into().ok()?)?;
+        let footer = if header[5] & ID3V2_FLAG_FOOTER != 0 { ID3V2_HEADER_LEN } else { 0 };
+
+        let length = ID3V2_HEADER_LEN + size + footer;
+        if offset + length > data.len() {
+            return None;
+        }
+
+        tags.push((version, length));
+        offset += length;
+    }
+
+    Some(tags)
+}
+
+/// Returns the tag-free audio range of `data`, plus labels for the tags around it.
+///
+/// Returns `None` if a tag is malformed, or what follows the tags doesn't look
+/// like `kind` audio, so a misparse can never cut into the audio itself.
+fn audio_range(data: &[u8], kind: AudioKind) -> Option<(Range<usize>, Vec<String>)> {
+    let mut labels = Vec::new();
+
+    let mut start = 0;
+    for (version, length) in id3v2_tags(data)? {
+        labels.push(format!("ID3v2.{}", version));
+        start += length;
+    }
+
+    let mut end = data.len();
+    if kind == AudioKind::Mp3 && end >= start + ID3V1_LEN && data[end - ID3V1_LEN..].starts_with(b"TAG") {
+        labels.push("ID3v1".to_string());
+        end -= ID3V1_LEN;
+    }
+
+    if !kind.starts_audio(&data[start..end]) {
+        return None;
+    }
+
+    Some((start..end, labels))
+}
+
+/// Returns the ID3 tags in an audio file (e.g. "ID3v2.3", "ID3v1"), or `None`
+/// if it can't be parsed as `kind`.
+pub fn id3_tags(data: &[u8], kind: AudioKind) -> Option<Vec<String>> {
+    audio_range(data, kind).map(|(_, labels)| labels)
+}
+
+/// Removes ID3v2 tags (and, for MP3, the ID3v1 trailer) from an audio file.
+///
+/// Returns `None` if the file can't be parsed or carries no ID3 tag.
+pub fn strip_id3(data: &[u8], kind: AudioKind) -> Option<Vec<u8>> {
+    let (range, labels) = audio_range(data, kind)?;
+    if labels.is_empty() {
+        return None;
+    }
+    Some(data[range].to_vec())
+}
+
+/// Size of a RIFF chunk header: ID (4) and little-endian size (4).
+const RIFF_CHUNK_HEADER_LEN: usize = 8;
+
+/// A WAV chunk: its ID and its byte range including header and padding.
+struct WavChunk {
+    id: [u8; 4],
+    range: Range<usize>,
+}
+
+/// Splits a WAV file into its top-level chunks.
+///
+/// Returns `None` if it doesn't start with `RIFF`/`WAVE`, a chunk overruns
+/// the file, or `fmt ` and `data` are missing.
+fn wav_chunks(data: &[u8]) -> Option<Vec<WavChunk>> {
+    if data.get(0..4)? != b"RIFF" || data.get(8..12)? != b"WAVE" {
+        return None;
+    }
+
+    let mut chunks = Vec::new();
+    let mut offset = 12;
+    while offset + RIFF_CHUNK_HEADER_LEN <= data.len() {
+        let id: [u8; 4] = data[offset..offset + 4].try_into().ok()?;
+        let size = u32::from_le_bytes(data[offset + 4..offset + 8].try_into().ok()?) as usize;
+
+        // Chunks are padded to an even length
+        let end = offset + RIFF_CHUNK_HEADER_LEN + size + (size & 1);
+        if offset + RIFF_CHUNK_HEADER_LEN + size > data.len() {
+            return None;
+        }
+
+        chunks.push(WavChunk { id, range: offset..end.min(data.len()) });
+        offset = end;
+    }
+
+    let has = |id: &[u8; 4]| chunks.iter().any(|chunk| &chunk.id == id);
+    (has(b"fmt ") && has(b"data")).then_some(chunks)
+}
+
+/// Returns a label for a metadata chunk (e.g. "INFO (IART, ISFT)" or "bext"),
+/// or `None` for chunks that are kept.
+fn wav_metadata_label(data: &[u8], chunk: &WavChunk) -> Option<String> {
+    match &chunk.id {
+        b"bext" => Some("bext".to_string()),
+        b"LIST" => {
+            let body = &data[chunk.range.start + RIFF_CHUNK_HEADER_LEN..chunk.range.end];
+            if body.get(0..4)? != b"INFO" {
+                return None;
+            }
+
+            // INFO sub-chunks use the same header layout as top-level chunks
+            let mut fields = Vec::new();
+            let mut offset = 4;
+            while offset + RIFF_CHUNK_HEADER_LEN <= body.len() {
+                let size = u32::from_le_bytes(body[offset + 4..offset + 8].try_into().ok()?) as usize;
+                fields.push(String::from_utf8_lossy(&body[offset..offset + 4]).into_owned());
+                offset += RIFF_CHUNK_HEADER_LEN + size + (size & 1);
+            }
+
+            Some(if fields.is_empty() { "INFO".to_string() } else { format!("INFO ({})", fields.join(", ")) })
+        }
+        _ => None,
+    }
+}
+
+/// Returns labels for the `LIST`/`INFO` and `bext` chunks in a WAV file, or
+/// `None` if it isn't a parseable WAV.
+pub fn wav_metadata(data: &[u8]) -> Option<Vec<String>> {
+    let chunks = wav_chunks(data)?;
+    Some(chunks.iter().filter_map(|chunk| wav_metadata_label(data, chunk)).collect())
+}
+
+/// Removes `LIST`/`INFO` and `bext` chunks from a WAV file and updates the RIFF size.
+///
+/// Returns `None` if the file can't be parsed or carries neither chunk.
+pub fn strip_wav_metadata(data: &[u8]) -> Option<Vec<u8>> {
+    let chunks = wav_chunks(data)?;
+    if chunks.iter().all(|chunk| wav_metadata_label(data, chunk).is_none()) {
+        return None;
+    }
+
+    let mut cleaned = data[..12].to_vec();
+    for chunk in &chunks {
+        if wav_metadata_label(data, chunk).is_none() {
+            cleaned.extend_from_slice(&data[chunk.range.clone()]);
+        }
+    }
+
+    let riff_size = u32::try_from(cleaned.len() - 8).ok()?;
+    cleaned[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    Some(cleaned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds an ID3v2 tag with a `body`-sized synchsafe size and optional footer.
+    fn id3v2(version: u8, body: usize, footer: bool) -> Vec<u8> {
+        let size = [(body >> 21) as u8 & 0x7F, (body >> 14) as u8 & 0x7F, (body >> 7) as u8 & 0x7F, body as u8 & 0x7F];
+        let flags = if footer { ID3V2_FLAG_FOOTER } else { 0 };
+        let mut tag = [&b"ID3"[..], &[version, 0, flags], &size].concat();
+        tag.resize(ID3V2_HEADER_LEN + body, b'x');
+        if footer {
+            tag.extend_from_slice(&[&b"3DI"[..], &[version, 0, flags], &size].concat());
+        }
+        tag
+    }
+
+    /// Two MPEG audio frame headers' worth of audio.
+    const MP3_FRAMES: &[u8] = &[0xFF, 0xFB, 0x90, 0x00, 0x11, 0x22, 0xFF, 0xFB, 0x90, 0x00, 0x33];
+
+    /// Builds a RIFF chunk, padded to an even length.
+    fn chunk(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut chunk = [id, &(body.len() as u32).to_le_bytes()[..], body].concat();
+        if body.len() % 2 == 1 {
+            chunk.push(0);
+        }
+        chunk
+    }
+
+    /// Builds a WAV file from its chunks, with a correct RIFF size.
+    fn wav(chunks: &[&[u8]]) -> Vec<u8> {
+        let body = chunks.concat();
+        [&b"RIFF"[..], &(body.len() as u32 + 4).to_le_bytes(), b"WAVE", &body].concat()
+    }
+
+    #[test]
+    fn synchsafe_sizes_use_seven_bits_per_byte() {
+        assert_eq!(synchsafe([0, 0, 2, 1]), Some(257));
+        assert_eq!(synchsafe([0x7F, 0x7F, 0x7F, 0x7F]), Some((1 << 28) - 1));
+        assert_eq!(synchsafe([0, 0, 0x80, 0]), None);
+    }
+
+    #[test]
+    fn mp3_tags_are_cut_off_both_ends() {
+        // 200 bytes needs two synchsafe digits: 0x01, 0x48
+        let tag = id3v2(3, 200, false);
+        assert_eq!(&tag[6..10], &[0, 0, 0x01, 0x48]);
+        let mut id3v1 = b"TAG".to_vec();
+        id3v1.resize(ID3V1_LEN, 0);
+        let input = [&tag[..], MP3_FRAMES, &id3v1].concat();
+        assert_eq!(id3_tags(&input, AudioKind::Mp3).unwrap(), ["ID3v2.3", "ID3v1"]);
+
+        let output = strip_id3(&input, AudioKind::Mp3).unwrap();
+
+        assert_eq!(output, MP3_FRAMES);
+        assert_eq!(output.len(), input.len() - 210 - ID3V1_LEN);
+        assert_eq!(id3_tags(&output, AudioKind::Mp3).unwrap(), Vec::<String>::new());
+        assert_eq!(strip_id3(&output, AudioKind::Mp3), None);
+    }
+
+    #[test]
+    fn stacked_tags_with_a_footer_are_all_removed() {
+        let input = [&id3v2(4, 20, true)[..], &id3v2(3, 5, false), b"fLaC\0\0\0\x22"].concat();
+        assert_eq!(id3_tags(&input, AudioKind::Flac).unwrap(), ["ID3v2.4", "ID3v2.3"]);
+
+        assert_eq!(strip_id3(&input, AudioKind::Flac).unwrap(), b"fLaC\0\0\0\x22");
+    }
+
+    #[test]
+    fn id3v1_is_only_an_mp3_trailer() {
+        let mut trailer = b"TAG".to_vec();
+        trailer.resize(ID3V1_LEN, 0);
+        let input = [&b"fLaC"[..], &trailer].concat();
+
+        assert_eq!(id3_tags(&input, AudioKind::Flac).unwrap(), Vec::<String>::new());
+        assert_eq!(strip_id3(&input, AudioKind::Flac), None);
+    }
+
+    #[test]
+    fn malformed_tags_are_not_stripped() {
+        // A size byte with its top bit set isn't synchsafe
+        let mut invalid = [&id3v2(3, 4, false)[..], MP3_FRAMES].concat();
+        invalid[8] = 0x80;
+        assert_eq!(strip_id3(&invalid, AudioKind::Mp3), None);
+
+        // A tag running past the end of the file
+        let truncated = &id3v2(3, 50, false)[..30];
+        assert_eq!(id3_tags(truncated, AudioKind::Mp3), None);
+        assert_eq!(strip_id3(truncated, AudioKind::Mp3), None);
+
+        // A size that ends mid-frame would cut into the audio
+        let mut misparsed = [&id3v2(3, 4, false)[..], MP3_FRAMES].concat();
+        misparsed[9] = 5;
+        assert_eq!(strip_id3(&misparsed, AudioKind::Mp3), None);
+    }
+
+    #[test]
+    fn wav_info_and_bext_are_dropped_and_the_riff_size_fixed() {
+        let fmt = chunk(b"fmt ", &[1, 0, 1, 0, 0x44, 0xAC, 0, 0, 0x88, 0x58, 1, 0, 2, 0, 16, 0]);
+        // ISFT's odd size is padded inside the list
+        let info = chunk(b"LIST", &[&b"INFO"[..], &chunk(b"IART", b"Alice\0"), &chunk(b"ISFT", b"Lavf\0")].concat());
+        let bext = chunk(b"bext", b"odd");
+        // The last chunk's pad byte is often missing
+        let data = [&b"data"[..], &3u32.to_le_bytes(), b"pcm"].concat();
+        let input = wav(&[&fmt, &info, &bext, &data]);
+        assert_eq!(wav_metadata(&input).unwrap(), ["INFO (IART, ISFT)", "bext"]);
+
+        let output = strip_wav_metadata(&input).unwrap();
+
+        assert_eq!(output, wav(&[&fmt, &data]));
+        assert_eq!(output.len(), input.len() - info.len() - bext.len());
+        assert_eq!(u32::from_le_bytes(output[4..8].try_into().unwrap()) as usize, output.len() - 8);
+        assert_eq!(wav_metadata(&output).unwrap(), Vec::<String>::new());
+        assert_eq!(strip_wav_metadata(&output), None);
+    }
+
+    #[test]
+    fn malformed_wav_is_not_stripped() {
+        let fmt = chunk(b"fmt ", &[0; 16]);
+        let info = chunk(b"LIST", &[&b"INFO"[..], &chunk(b"IART", b"Alice\0")].concat());
+        let data = chunk(b"data", b"pcm!");
+
+        // A chunk running past the end of the file
+        let input = wav(&[&fmt, &info, &data]);
+        assert_eq!(wav_metadata(&input[..input.len() - 2]), None);
+        assert_eq!(strip_wav_metadata(&input[..input.len() - 2]), None);
+
+        // No data chunk
+        assert_eq!(strip_wav_metadata(&wav(&[&fmt, &info])), None);
+        assert_eq!(strip_wav_metadata(b"RIFF\0\0\0\0AVI "), None);
+    }
+}
@@ -5,598 +5,4618 @@
 
 #![allow(dead_code)]
 
+use std::collections::HashSet;
 use std::fs::{File, OpenOptions};
-use std::io::{Read, Write};
+use std::io::{BufReader, BufWriter, Cursor, Read, Seek, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::cell::Cell;
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-#[cfg(not(windows))]
-use std::time::SystemTime;
-
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use quick_xml::events::Event;
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use quick_xml::{Reader, Writer as XmlWriter};
 use walkdir::WalkDir;
 use zip::write::SimpleFileOptions;
 use zip::{ZipArchive, ZipWriter};
 
-/// Escapes special regex characters in a string.
-fn regex_escape(s: &str) -> String {
-    let special_chars = ['\\', '.', '+', '*', '?', '(', ')', '[', ']', '{', '}', '|', '^', '$', ':'];
-    let mut result = String::with_capacity(s.len() * 2);
-    for c in s.chars() {
-        if special_chars.contains(&c) {
-            result.push('\\');
-        }
-        result.push(c);
+/// Converts a `SystemTime` into a Win32 `FILETIME` (100-ns intervals since 1601-01-01 UTC).
+#[cfg(windows)]
+fn system_time_to_filetime(time: std::time::SystemTime) -> FILETIME {
+    // Seconds between 1601-01-01 and the Unix epoch
+    const EPOCH_DIFFERENCE_SECS: u64 = 11_644_473_600;
+
+    let since_unix = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let intervals = (since_unix.as_secs() + EPOCH_DIFFERENCE_SECS) * 10_000_000
+        + u64::from(since_unix.subsec_nanos() / 100);
+
+    FILETIME {
+        dwLowDateTime: (intervals & 0xFFFFFFFF) as u32,
+        dwHighDateTime: (intervals >> 32) as u32,
     }
-    result
 }
 
-use super::error::{CleanerError, CleanerResult};
-use super::types::{CleanMode, CleanOptions, CleanReport, FileResult};
-
+/// Sets all four NTFS timestamps, including the change time `SetFileTime` can't
+/// reach, through `NtSetInformationFile`.
+///
+/// This is undocumented territory for user-mode code and may be refused (e.g.
+/// by filter drivers or without the needed privilege), so callers fall back to
+/// `SetFileTime` on error.
 #[cfg(windows)]
-use std::os::windows::fs::OpenOptionsExt;
+fn set_basic_times(handle: HANDLE, time: &FILETIME) -> windows::core::Result<()> {
+    use windows::Wdk::Storage::FileSystem::{FileBasicInformation, NtSetInformationFile, FILE_BASIC_INFORMATION};
+    use windows::Win32::System::IO::IO_STATUS_BLOCK;
 
+    let time = (i64::from(time.dwHighDateTime) << 32) | i64::from(time.dwLowDateTime);
+    let info = FILE_BASIC_INFORMATION {
+        CreationTime: time,
+        LastAccessTime: time,
+        LastWriteTime: time,
+        ChangeTime: time,
+        // Zero leaves the attributes unchanged
+        FileAttributes: 0,
+    };
+    let mut status = IO_STATUS_BLOCK::default();
+
+    unsafe {
+        NtSetInformationFile(
+            handle,
+            &mut status,
+            &info as *const FILE_BASIC_INFORMATION as *const _,
+            std::mem::size_of::<FILE_BASIC_INFORMATION>() as u32,
+            FileBasicInformation,
+        )
+        .ok()
+    }
+}
+
+/// Checks that `sid` is a valid string SID such as `S-1-1-0`, as `--owner-sid` takes.
+///
+/// Uses `ConvertStringSidToSidW`, the same conversion `clear_owner` performs, so a
+/// malformed SID is rejected before any file is touched.
 #[cfg(windows)]
-use windows::core::PCWSTR;
-#[cfg(windows)]
-use windows::Win32::Foundation::{HANDLE, FILETIME, LocalFree};
+pub fn validate_sid(sid: &str) -> CleanerResult<()> {
+    use windows::Win32::Foundation::HLOCAL;
+
+    let sid_string: Vec<u16> = sid.encode_utf16().chain(std::iter::once(0)).collect();
+    let mut converted = PSID::default();
+
+    unsafe {
+        if ConvertStringSidToSidW(PCWSTR(sid_string.as_ptr()), &mut converted).is_err() {
+            return Err(CleanerError::InvalidSid(sid.to_string()));
+        }
+        let _ = LocalFree(HLOCAL(converted.0));
+    }
+
+    Ok(())
+}
+
+/// Checks that `sid` looks like a string SID (`S-1-` followed by numeric parts).
+/// Owners are only set on Windows, so this is a syntax check only.
+#[cfg(not(windows))]
+pub fn validate_sid(sid: &str) -> CleanerResult<()> {
+    let valid = sid
+        .strip_prefix("S-1-")
+        .is_some_and(|parts| parts.split('-').all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit())));
+
+    if valid { Ok(()) } else { Err(CleanerError::InvalidSid(sid.to_string())) }
+}
+
+/// Longest path Win32 accepts without the `\\?\` prefix, including the terminating NUL.
 #[cfg(windows)]
-use windows::Win32::Storage::FileSystem::{
-    DeleteFileW, FindClose, FindFirstStreamW, FindNextStreamW, SetFileTime,
-    FILE_FLAG_BACKUP_SEMANTICS, WIN32_FIND_STREAM_DATA,
-};
+const MAX_PATH: usize = 260;
+
+/// Returns the `\\?\` extended-length form of a path whose file name ends in a dot
+/// or space, or that is longer than `MAX_PATH`.
+///
+/// Win32 path normalization silently strips trailing dots and spaces, so such files
+/// (usually created by other tools or extracted from archives) can only be opened,
+/// enumerated and timestamped through the verbatim form, which skips normalization.
+/// The verbatim form also lifts the `MAX_PATH` limit for deeply nested files.
+/// Other paths are returned unchanged.
 #[cfg(windows)]
-use windows::Win32::Security::{OWNER_SECURITY_INFORMATION, PSID};
+pub fn extended_length_path(path: &Path) -> PathBuf {
+    use std::path::{Component, Prefix};
+
+    let has_pathological_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with('.') || name.ends_with(' '));
+    let too_long = path.as_os_str().len() >= MAX_PATH;
+
+    if !has_pathological_name && !too_long {
+        return path.to_path_buf();
+    }
+
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        match std::env::current_dir() {
+            Ok(cwd) => cwd.join(path),
+            Err(_) => return path.to_path_buf(),
+        }
+    };
+
+    // Resolve `.`/`..` ourselves; the verbatim form passes components through as-is
+    let mut prefix = String::new();
+    let mut parts: Vec<String> = Vec::new();
+    for component in absolute.components() {
+        match component {
+            Component::Prefix(p) => match p.kind() {
+                // Already verbatim, nothing to do
+                Prefix::Verbatim(_) | Prefix::VerbatimDisk(_) | Prefix::VerbatimUNC(_, _) => {
+                    return absolute;
+                }
+                Prefix::UNC(server, share) => {
+                    prefix = format!(r"\\?\UNC\{}\{}", server.to_string_lossy(), share.to_string_lossy());
+                }
+                Prefix::Disk(letter) => prefix = format!(r"\\?\{}:", letter as char),
+                Prefix::DeviceNS(_) => return absolute,
+            },
+            Component::RootDir | Component::CurDir => {}
+            Component::ParentDir => {
+                parts.pop();
+            }
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+        }
+    }
+
+    PathBuf::from(format!(r"{}\{}", prefix, parts.join(r"\")))
+}
+
+#[cfg(not(windows))]
+pub fn extended_length_path(path: &Path) -> PathBuf {
+    // Trailing dots and spaces are ordinary characters outside Win32
+    path.to_path_buf()
+}
+
+/// Encodes a path as a NUL-terminated wide string for Win32 calls, in its
+/// extended-length form when the plain one would be rejected.
 #[cfg(windows)]
-use windows::Win32::Security::Authorization::{
-    ConvertStringSidToSidW, SE_FILE_OBJECT, SetNamedSecurityInfoW,
-};
+fn wide_path(path: &Path) -> Vec<u16> {
+    use std::os::windows::ffi::OsStrExt;
 
-/// The main metadata cleaner that orchestrates all cleaning operations.
-#[derive(Debug, Default)]
-pub struct MetadataCleaner {
-    options: CleanOptions,
+    extended_length_path(path).as_os_str().encode_wide().chain(std::iter::once(0)).collect()
 }
 
-impl MetadataCleaner {
-    /// Creates a new `MetadataCleaner` with default options.
-    pub fn new() -> Self {
-        Self::default()
+/// Canonicalizes a path, falling back to the path as given when it exists but
+/// can't be canonicalized (e.g. over-long paths or some network shares), so
+/// that isn't reported as a missing file.
+fn resolve_path(path: &Path) -> CleanerResult<PathBuf> {
+    match path.canonicalize() {
+        Ok(resolved) => Ok(resolved),
+        Err(_) if path.symlink_metadata().is_ok() => Ok(path.to_path_buf()),
+        Err(_) => Err(CleanerError::PathNotFound(path.to_path_buf())),
     }
+}
 
-    /// Creates a new `MetadataCleaner` with the specified options.
-    pub fn with_options(options: CleanOptions) -> Self {
-        Self { options }
+/// Returns the hex SHA-256 of a file's contents, read in chunks rather than all at once.
+fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Drops repeated paths, keeping the first occurrence of each file in order.
+///
+/// Paths are compared by their canonical form, so `a/../b.txt` and `b.txt`, or a
+/// file reached through two followed symlinks, count as one; paths that can't be
+/// resolved are compared as given. The paths themselves are returned unchanged.
+pub fn dedupe_paths(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut seen = HashSet::with_capacity(paths.len());
+    paths
+        .into_iter()
+        .filter(|path| seen.insert(resolve_path(&extended_length_path(path)).unwrap_or_else(|_| path.clone())))
+        .collect()
+}
+
+/// Builds a failed result that records the steps completed so far plus the one that failed.
+fn failed_step(path: PathBuf, mut steps: Vec<CleanerStep>, name: &str, error: CleanerError) -> FileResult {
+    let message = error.to_string();
+    steps.push(CleanerStep::failed(name, message.clone()));
+
+    let mut result = FileResult::failure(path, message).with_steps(steps);
+    result.in_use = error.is_in_use();
+    result
+}
+
+/// How long to wait before retry `attempt` (1-based) of a file in use: 250ms, doubling up to 4s.
+fn retry_delay(attempt: u32) -> std::time::Duration {
+    std::time::Duration::from_millis(250 << attempt.saturating_sub(1).min(4))
+}
+
+/// Returns the bare name of a stream reported by `FindFirstStreamW` (`:name:$DATA`).
+///
+/// Exactly one leading `:` and one trailing `:$DATA` are removed, so names that
+/// themselves start or end with colons survive intact. The main stream
+/// (`::$DATA`) comes back empty; extended attribute names pass through unchanged.
+fn stream_name(raw: &str) -> &str {
+    let name = raw.strip_prefix(':').unwrap_or(raw);
+    name.strip_suffix(":$DATA").unwrap_or(name)
+}
+
+/// Builds the path that opens one stream of a file from its full `:name:$DATA`
+/// specifier, as enumerated; rebuilding it from a trimmed name breaks when the
+/// name contains colons.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn stream_path(path: &Path, raw_name: &str) -> PathBuf {
+    PathBuf::from(format!("{}{}", path.display(), raw_name))
+}
+
+/// Include/exclude globs and modification-time bounds applied while walking a directory.
+///
+/// Patterns match the path relative to the root being cleaned, and `*` crosses
+/// directory separators, so `*.docx` matches documents at any depth.
+struct PathFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    /// Files modified after this are skipped (`older_than`).
+    modified_before: Option<SystemTime>,
+    /// Files modified before this are skipped (`newer_than`).
+    modified_after: Option<SystemTime>,
+}
+
+impl PathFilter {
+    fn new(options: &CleanOptions) -> CleanerResult<Self> {
+        fn build(globs: &[String]) -> CleanerResult<GlobSet> {
+            let mut set = GlobSetBuilder::new();
+            for glob in globs {
+                set.add(Glob::new(glob)?);
+            }
+            Ok(set.build()?)
+        }
+
+        // Ages are measured from when the walk starts
+        let now = SystemTime::now();
+        let cutoff = |age: Option<Duration>| age.map(|age| now.checked_sub(age).unwrap_or(UNIX_EPOCH));
+
+        Ok(Self {
+            include: if options.include_globs.is_empty() { None } else { Some(build(&options.include_globs)?) },
+            exclude: build(&options.exclude_globs)?,
+            modified_before: cutoff(options.older_than),
+            modified_after: cutoff(options.newer_than),
+        })
     }
 
-    /// Cleans metadata from a single file.
-    pub fn clean_file(&self, path: &Path) -> CleanerResult<FileResult> {
-        let path = path.canonicalize().map_err(|_| CleanerError::PathNotFound(path.to_path_buf()))?;
+    /// Returns true if a file's modification time is within the age bounds.
+    /// Files whose time can't be read only pass when there are no bounds.
+    fn includes_modified(&self, entry: &walkdir::DirEntry) -> bool {
+        if self.modified_before.is_none() && self.modified_after.is_none() {
+            return true;
+        }
+        let Some(modified) = entry.metadata().ok().and_then(|m| m.modified().ok()) else {
+            return false;
+        };
+        self.modified_before.is_none_or(|before| modified <= before)
+            && self.modified_after.is_none_or(|after| modified >= after)
+    }
 
-        if !path.exists() {
-            return Err(CleanerError::PathNotFound(path));
+    /// Returns true if an entry (file or directory) is excluded.
+    fn excludes(&self, relative: &Path) -> bool {
+        self.exclude.is_match(relative)
+    }
+
+    /// Returns true if a file should be cleaned; exclusion wins over inclusion.
+    fn includes(&self, relative: &Path) -> bool {
+        !self.excludes(relative) && self.include.as_ref().is_none_or(|include| include.is_match(relative))
+    }
+}
+
+/// Returns where the backup of `path` goes inside `backup_dir`.
+///
+/// The file's full path is mirrored below the backup directory (the drive or
+/// share becomes the first folder), so backups from different folders never
+/// collide and each one maps straight back to its original location.
+fn backup_path(path: &Path, backup_dir: &Path) -> PathBuf {
+    use std::path::Component;
+
+    let mut backup = backup_dir.to_path_buf();
+    for component in path.components() {
+        match component {
+            Component::Prefix(prefix) => {
+                let name: String = prefix
+                    .as_os_str()
+                    .to_string_lossy()
+                    .chars()
+                    .filter(|c| c.is_alphanumeric() || *c == '\\' || *c == '.')
+                    .collect();
+                backup.extend(name.split('\\').filter(|part| !part.is_empty()));
+            }
+            Component::Normal(part) => backup.push(part),
+            Component::RootDir | Component::CurDir | Component::ParentDir => {}
         }
+    }
+    backup
+}
 
-        if path.is_dir() {
-            return Err(CleanerError::NotAFile(path));
+/// Returns where the cleaned copy of `path` goes inside `output_dir`.
+///
+/// Files below `root` keep their path relative to it; any other file mirrors its
+/// full path, as with backups.
+fn output_path(path: &Path, output_dir: &Path, root: Option<&Path>) -> PathBuf {
+    let root = root.map(|root| root.canonicalize().unwrap_or_else(|_| root.to_path_buf()));
+    match root.as_deref().and_then(|root| path.strip_prefix(root).ok()) {
+        Some(relative) => output_dir.join(relative),
+        None => backup_path(path, output_dir),
+    }
+}
+
+/// Identity fields cleared from docProps/core.xml (Dublin Core and CP namespaces),
+/// with the label Windows shows for them in the Details tab.
+const CORE_XML_FIELDS: &[(&str, &str)] = &[
+    ("dc:creator", "Author"),
+    ("cp:lastModifiedBy", "Last Modified By"),
+    ("dc:title", "Title"),
+    ("dc:subject", "Subject"),
+    ("dc:description", "Comments"),
+    ("cp:keywords", "Keywords"),
+    ("cp:category", "Category"),
+    ("cp:contentStatus", "Content Status"),
+];
+
+/// Identity fields cleared from docProps/app.xml.
+const APP_XML_FIELDS: &[(&str, &str)] = &[
+    ("Company", "Company"),
+    ("Manager", "Manager"),
+    ("HyperlinkBase", "Hyperlink Base"),
+];
+
+/// Extended attributes macOS and Linux desktops use to record where a file was
+/// downloaded from, removed like `Zone.Identifier` on Windows.
+#[cfg(not(windows))]
+const PROVENANCE_XATTRS: &[&str] = &[
+    "com.apple.quarantine",
+    "com.apple.metadata:kMDItemWhereFroms",
+    "com.apple.metadata:kMDItemDownloadedDate",
+    "com.apple.lastuseddate#PS",
+    "user.xdg.origin.url",
+    "user.xdg.referrer.url",
+];
+
+/// Named streams Windows uses for file properties (author, computer, download origin).
+const PROPERTY_STREAMS: &[&str] = &[
+    "Zone.Identifier",
+    "\x05SummaryInformation",
+    "\x05DocumentSummaryInformation",
+    "Afp_AfpInfo",
+    "encryptable",
+    "OECustomProperty",
+];
+
+/// Other streams known to hold only metadata (SmartScreen verdicts, sync client
+/// and shell property caches). Without `all_streams`, only these and
+/// [`PROPERTY_STREAMS`] are removed so application data in custom streams survives.
+const METADATA_STREAMS: &[&str] = &["SmartScreen", "com.dropbox.attrs", "com.dropbox.attributes", "ms-properties"];
+
+/// Office fields that directly identify a person or machine and weigh more in the
+/// sensitivity score than descriptive fields like Title or Keywords.
+const IDENTIFYING_OFFICE_FIELDS: &[&str] = &["Author", "Last Modified By", "Company", "Manager"];
+
+/// Hosts of personal cloud storage; a download origin pointing at one of them
+/// usually reveals the owner's account.
+const PERSONAL_CLOUD_HOSTS: &[&str] = &[
+    "drive.google.com",
+    "docs.google.com",
+    "onedrive.live.com",
+    "1drv.ms",
+    "sharepoint.com",
+    "dropbox.com",
+    "dropboxusercontent.com",
+    "icloud.com",
+    "box.com",
+    "mega.nz",
+];
+
+/// Image formats whose EXIF data can be stripped.
+#[derive(Clone, Copy)]
+pub(super) enum ImageKind {
+    Jpeg,
+    Tiff,
+}
+
+impl ImageKind {
+    /// Detects the image format from the data's signature.
+    fn detect(data: &[u8]) -> Option<Self> {
+        match data.get(..4)? {
+            [0xFF, 0xD8, ..] => Some(ImageKind::Jpeg),
+            b"II*\0" | b"MM\0*" => Some(ImageKind::Tiff),
+            _ => None,
         }
+    }
 
-        if self.options.dry_run {
-            return Ok(FileResult::success(path, 0, false));
+    /// Returns the image format for a lowercase file extension, if EXIF stripping supports it.
+    pub(super) fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "jpg" | "jpeg" => Some(ImageKind::Jpeg),
+            "tif" | "tiff" => Some(ImageKind::Tiff),
+            _ => None,
         }
+    }
+}
 
-        let mut streams_removed = 0;
-        let mut timestamps_reset = false;
+/// Returns the image format of a file from its signature, if EXIF stripping handles it.
+fn image_kind(path: &Path, signature: Option<Signature>) -> Option<ImageKind> {
+    if !ExifCleaner.handles_path(path, signature) {
+        return None;
+    }
 
-        // Remove alternate data streams
-        if self.options.clear_streams {
-            match self.remove_alternate_streams(&path) {
-                Ok(count) => streams_removed = count,
-                Err(e) => return Ok(FileResult::failure(path, e.to_string())),
-            }
+    match signature? {
+        Signature::Jpeg => Some(ImageKind::Jpeg),
+        Signature::Tiff => Some(ImageKind::Tiff),
+        _ => None,
+    }
+}
+
+/// Strips EXIF data from an image, returning `None` if it has none or isn't valid.
+fn strip_exif_data(kind: ImageKind, mut data: Vec<u8>) -> Option<Vec<u8>> {
+    match kind {
+        ImageKind::Jpeg => image::strip_jpeg_exif(&data),
+        ImageKind::Tiff => image::strip_tiff_exif(&mut data).then_some(data),
+    }
+}
+
+/// Returns the audio format of a file from its signature, if ID3 stripping supports it.
+fn audio_kind(path: &Path, signature: Option<Signature>) -> Option<AudioKind> {
+    if !AudioCleaner.handles_path(path, signature) {
+        return None;
+    }
+
+    match signature? {
+        Signature::Flac => Some(AudioKind::Flac),
+        // An ID3 tag can sit in front of FLAC audio too
+        Signature::Mp3 if path.extension().and_then(|e| e.to_str()).and_then(AudioKind::from_extension) == Some(AudioKind::Flac) => {
+            Some(AudioKind::Flac)
         }
+        Signature::Mp3 => Some(AudioKind::Mp3),
+        Signature::M4a | Signature::IsoMedia => Some(AudioKind::M4a),
+        _ => None,
+    }
+}
 
-        // Reset timestamps
-        if self.options.clear_timestamps {
-            match self.reset_timestamps(&path) {
-                Ok(_) => timestamps_reset = true,
-                Err(e) => return Ok(FileResult::failure(path, e.to_string())),
+/// Returns the given XML fields that have non-empty content, as `(label, text)` pairs.
+///
+/// Entity and character references are resolved and surrounding whitespace is
+/// trimmed; a field that holds only whitespace counts as empty.
+fn xml_field_values(content: &str, fields: &[(&str, &str)]) -> Result<Vec<(String, String)>, quick_xml::Error> {
+    let mut reader = Reader::from_str(content);
+    let mut values: Vec<(&str, String)> = Vec::new();
+    // Label of the field being read, its text so far, and how deeply nested we are inside it
+    let mut current: Option<(&str, String, usize)> = None;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => match &mut current {
+                Some((_, _, depth)) => *depth += 1,
+                None => {
+                    current = xml_field_label(start.name().as_ref(), fields).map(|label| (label, String::new(), 0))
+                }
+            },
+            Event::End(_) => match &mut current {
+                Some((_, _, depth)) if *depth > 0 => *depth -= 1,
+                Some(_) => {
+                    let (label, text, _) = current.take().unwrap_or_default();
+                    let text = text.trim();
+                    if !text.is_empty() && !values.iter().any(|(l, _)| *l == label) {
+                        values.push((label, text.to_string()));
+                    }
+                }
+                None => {}
+            },
+            Event::Text(text) => {
+                if let Some((_, value, _)) = &mut current {
+                    value.push_str(&text.decode()?);
+                }
+            }
+            Event::CData(data) => {
+                if let Some((_, value, _)) = &mut current {
+                    value.push_str(&data.decode()?);
+                }
+            }
+            Event::GeneralRef(reference) => {
+                if let Some((_, value, _)) = &mut current {
+                    if let Some(c) = reference.resolve_char_ref()? {
+                        value.push(c);
+                    } else {
+                        let name = reference.decode()?;
+                        match quick_xml::escape::resolve_predefined_entity(&name) {
+                            Some(resolved) => value.push_str(resolved),
+                            None => value.push_str(&format!("&{};", name)),
+                        }
+                    }
+                }
             }
+            Event::Eof => break,
+            _ => {}
         }
+    }
 
-        // Clear file owner (requires Administrator privileges)
-        // Only attempted when --admin flag is used
-        if self.options.clear_owner {
-            if let Err(e) = self.clear_owner(&path) {
-                return Ok(FileResult::failure(path, e.to_string()));
+    // Report in the order the fields are declared, not document order
+    Ok(fields
+        .iter()
+        .filter_map(|(_, label)| {
+            values
+                .iter()
+                .find(|(l, _)| l == label)
+                .map(|(_, value)| (label.to_string(), value.clone()))
+        })
+        .collect())
+}
+
+/// Empties the given XML fields, keeping each element (and its attributes) in place.
+///
+/// Elements are matched by qualified name, so attributes, whitespace, line breaks,
+/// CDATA and self-closing forms are all handled; everything else is copied as-is.
+fn clear_xml_fields(content: &str, fields: &[(&str, &str)]) -> Result<String, quick_xml::Error> {
+    let mut reader = Reader::from_str(content);
+    let mut writer = XmlWriter::new(Vec::with_capacity(content.len()));
+    // Nesting depth inside the field being cleared
+    let mut clearing: Option<usize> = None;
+
+    loop {
+        let event = reader.read_event()?;
+        match (&mut clearing, event) {
+            (_, Event::Eof) => break,
+            (None, Event::Start(start)) => {
+                if xml_field_label(start.name().as_ref(), fields).is_some() {
+                    clearing = Some(0);
+                }
+                writer.write_event(Event::Start(start))?;
+            }
+            (Some(depth), Event::Start(_)) => *depth += 1,
+            (Some(0), Event::End(end)) => {
+                clearing = None;
+                writer.write_event(Event::End(end))?;
             }
+            (Some(depth), Event::End(_)) => *depth -= 1,
+            // Text, CDATA, comments and nested elements inside a field are dropped
+            (Some(_), _) => {}
+            (None, event) => writer.write_event(event)?,
         }
+    }
 
-        // Clear file properties (author, computer, etc.) from NTFS streams
-        if self.options.clear_properties {
-            if let Err(e) = self.clear_properties(&path) {
-                return Ok(FileResult::failure(path, e.to_string()));
-            }
+    Ok(String::from_utf8_lossy(&writer.into_inner()).into_owned())
+}
+
+/// Returns the label of the field with the given qualified element name, if any.
+fn xml_field_label<'a>(name: &[u8], fields: &[(&str, &'a str)]) -> Option<&'a str> {
+    fields
+        .iter()
+        .find(|(tag, _)| tag.as_bytes() == name)
+        .map(|(_, label)| *label)
+}
+
+/// Stands in for the file name in errors from the in-memory `clean_*_bytes` methods.
+const IN_MEMORY: &str = "<in-memory data>";
+
+/// Suffix of the sidecar file that marks a file as being rewritten by an instance.
+const LOCK_SUFFIX: &str = ".rs-mahito-lock";
+
+/// Times a lock is retried after finding its sidecar replaced by another instance.
+const LOCK_ATTEMPTS: usize = 8;
+
+/// Suffix of temp files holding a rewritten copy before it replaces the original.
+const TEMP_SUFFIX: &str = ".rs-mahito-tmp";
+
+/// Extension used for temp files by earlier versions (may be left behind by a crash).
+const LEGACY_TEMP_EXTENSION: &str = "tmp_meta_clean";
+
+/// Returns a hidden sibling of `path` named `.<file name><tag><suffix>`.
+fn sibling_path(path: &Path, tag: &str, suffix: &str) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}{}{}", name, tag, suffix))
+}
+
+/// Returns a temp path next to `path` that is unique to this process and call,
+/// so concurrent runs over overlapping paths never write to the same temp file.
+fn unique_temp_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    sibling_path(path, &format!(".{}-{}", std::process::id(), n), TEMP_SUFFIX)
+}
+
+/// Returns true for lock and temp files created by this tool, which are never cleaned.
+fn is_own_artifact(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name.ends_with(LOCK_SUFFIX)
+        || name.ends_with(TEMP_SUFFIX)
+        || path.extension().is_some_and(|e| e == LEGACY_TEMP_EXTENSION)
+}
+
+/// Files Windows and macOS drop into folders on their own: thumbnail caches,
+/// folder customization and Finder view settings. Compared case-insensitively.
+const JUNK_FILE_NAMES: [&str; 5] = ["thumbs.db", "ehthumbs.db", "ehthumbs_vista.db", "desktop.ini", ".ds_store"];
+
+/// Returns true for OS-generated folder files that `remove_junk` deletes.
+fn is_junk_file(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| JUNK_FILE_NAMES.contains(&name.to_string_lossy().to_lowercase().as_str()))
+}
+
+/// Most entries an archive may have for its entries to be cleaned.
+const MAX_ARCHIVE_ENTRIES: usize = 10_000;
+
+/// Most bytes the cleaned entries of one archive (nested ones included) may
+/// decompress to, so a zip bomb fails instead of filling memory.
+const MAX_ARCHIVE_EXPANDED_BYTES: u64 = 1 << 30;
+
+/// Temporary directory holding archive entries while they are cleaned, removed on drop.
+struct ScratchDir {
+    path: PathBuf,
+}
+
+impl ScratchDir {
+    /// Creates a directory under the system temp directory, unique to this process and call.
+    fn create(target: &Path) -> CleanerResult<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("rs-mahito-{}-{}", std::process::id(), n));
+        std::fs::create_dir_all(&path)
+            .map_err(|e| CleanerError::file_operation(target, "Failed to create scratch directory", e))?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Counting semaphore capping how many archives are rewritten at once (`parallel_archives`).
+///
+/// Slots are per thread: cleaning an archive's entries can rewrite nested
+/// documents on the same thread, which reuses the slot it already holds
+/// instead of waiting on itself.
+#[derive(Debug, Default)]
+struct ArchiveSlots {
+    limit: Option<usize>,
+    in_use: Mutex<usize>,
+    freed: Condvar,
+}
+
+thread_local! {
+    /// Whether this thread holds an archive slot.
+    static HOLDS_ARCHIVE_SLOT: Cell<bool> = const { Cell::new(false) };
+}
+
+impl ArchiveSlots {
+    fn new(limit: Option<usize>) -> Self {
+        Self { limit, ..Self::default() }
+    }
+
+    /// Waits for a free slot, which is released when the returned guard drops.
+    fn acquire(&self) -> ArchiveSlot<'_> {
+        let Some(limit) = self.limit else {
+            return ArchiveSlot { slots: None };
+        };
+        if HOLDS_ARCHIVE_SLOT.get() {
+            return ArchiveSlot { slots: None };
         }
 
-        // Clear embedded document properties from Office Open XML files
-        // This removes Author, Company (Computer), Last Modified By, etc. from the Details tab
-        if self.options.clear_properties {
-            if let Err(e) = self.clear_office_xml_properties(&path) {
-                return Ok(FileResult::failure(path, e.to_string()));
+        let mut in_use = self.in_use.lock().unwrap_or_else(PoisonError::into_inner);
+        while *in_use >= limit {
+            in_use = self.freed.wait(in_use).unwrap_or_else(PoisonError::into_inner);
+        }
+        *in_use += 1;
+        HOLDS_ARCHIVE_SLOT.set(true);
+        ArchiveSlot { slots: Some(self) }
+    }
+}
+
+/// A slot taken from [`ArchiveSlots`]; empty when no limit applies or the
+/// thread already held one.
+struct ArchiveSlot<'a> {
+    slots: Option<&'a ArchiveSlots>,
+}
+
+impl Drop for ArchiveSlot<'_> {
+    fn drop(&mut self) {
+        if let Some(slots) = self.slots {
+            *slots.in_use.lock().unwrap_or_else(PoisonError::into_inner) -= 1;
+            HOLDS_ARCHIVE_SLOT.set(false);
+            slots.freed.notify_one();
+        }
+    }
+}
+
+/// The point past which a file cleaned under a timeout may no longer be
+/// modified, shared by the worker cleaning it and the thread waiting on it.
+///
+/// The worker checks it between steps and commits each rewrite while holding
+/// it, so once [`expire`](Self::expire) returns, nothing more is written.
+#[derive(Debug, Default)]
+struct Deadline {
+    passed: Mutex<bool>,
+}
+
+impl Deadline {
+    /// Marks the deadline as passed, waiting for a rewrite in progress to finish.
+    fn expire(&self) {
+        *self.passed.lock().unwrap_or_else(PoisonError::into_inner) = true;
+    }
+
+    fn has_passed(&self) -> bool {
+        *self.passed.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Exclusive OS-level lock held while a file is being rewritten.
+///
+/// The lock lives on a sidecar file rather than the target itself, because Windows
+/// byte-range locks would also block our own reads of the target. The OS releases
+/// the lock if the process dies, so a sidecar left behind by a crash is simply reused.
+///
+/// The holder removes the sidecar before unlocking it, so an instance that opened
+/// it just before can end up locking a file no longer at that path; the lock only
+/// counts once the locked file is still the one the path names.
+///
+/// In-place rewrites lock the target itself instead (see [`acquire_in_place`](Self::acquire_in_place)),
+/// since they mustn't need write access to its directory.
+struct RewriteLock {
+    file: File,
+    /// The sidecar, removed on release; `None` when the target itself is locked.
+    path: Option<PathBuf>,
+}
+
+impl RewriteLock {
+    /// Locks `target` for rewriting, failing if another instance already holds it.
+    fn acquire(target: &Path) -> CleanerResult<Self> {
+        let path = sibling_path(target, "", LOCK_SUFFIX);
+        for _ in 0..LOCK_ATTEMPTS {
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)
+                .map_err(|e| CleanerError::cleaning_failed(target, format!("Failed to create lock file: {}", e)))?;
+
+            match file.try_lock() {
+                Ok(()) if is_same_file(&file, &path) => return Ok(Self { file, path: Some(path) }),
+                // Locked a sidecar its holder had just removed; try the current one
+                Ok(()) => continue,
+                Err(std::fs::TryLockError::WouldBlock) => return Err(CleanerError::FileLocked(target.to_path_buf())),
+                Err(std::fs::TryLockError::Error(e)) => {
+                    return Err(CleanerError::cleaning_failed(target, format!("Failed to lock file: {}", e)));
+                }
             }
         }
+        Err(CleanerError::FileLocked(target.to_path_buf()))
+    }
 
-        Ok(FileResult::success(path, streams_removed, timestamps_reset))
+    /// Locks `target` through its own handle, failing if another instance already
+    /// holds it. Only a byte range past any real contents is locked, so reads and
+    /// writes of the file, ours included, aren't blocked on Windows.
+    fn acquire_in_place(target: &Path) -> CleanerResult<Self> {
+        let file = File::open(target)
+            .map_err(|e| CleanerError::file_operation(target, "Failed to open file", e))?;
+
+        match lock_past_contents(&file) {
+            Ok(true) => Ok(Self { file, path: None }),
+            Ok(false) => Err(CleanerError::FileLocked(target.to_path_buf())),
+            Err(e) => Err(CleanerError::cleaning_failed(target, format!("Failed to lock file: {}", e))),
+        }
     }
+}
 
-    /// Cleans metadata from all files in a directory (non-recursive).
-    pub fn clean_directory_shallow(&self, path: &Path) -> CleanerResult<CleanReport> {
-        self.clean_directory_internal(path, CleanMode::Shallow)
+/// Takes an exclusive lock on one byte far past the end of any real file,
+/// returning false if another handle holds it.
+#[cfg(windows)]
+fn lock_past_contents(file: &File) -> std::io::Result<bool> {
+    use std::os::windows::io::AsRawHandle;
+
+    let mut overlapped = OVERLAPPED::default();
+    overlapped.Anonymous.Anonymous.Offset = u32::MAX;
+    overlapped.Anonymous.Anonymous.OffsetHigh = u32::MAX - 1;
+    let locked = unsafe {
+        LockFileEx(
+            HANDLE(file.as_raw_handle() as _),
+            LOCKFILE_EXCLUSIVE_LOCK | LOCKFILE_FAIL_IMMEDIATELY,
+            0,
+            1,
+            0,
+            &mut overlapped,
+        )
+    };
+
+    match locked {
+        Ok(()) => Ok(true),
+        Err(e) if e.code() == ERROR_LOCK_VIOLATION.to_hresult() => Ok(false),
+        Err(e) => Err(std::io::Error::other(e)),
     }
+}
 
-    /// Cleans metadata from all files in a directory and subdirectories (recursive).
-    pub fn clean_directory_deep(&self, path: &Path) -> CleanerResult<CleanReport> {
-        self.clean_directory_internal(path, CleanMode::Deep)
+/// Takes an exclusive advisory lock on a file, returning false if another
+/// handle holds it. Advisory locks don't block reads or writes.
+#[cfg(not(windows))]
+fn lock_past_contents(file: &File) -> std::io::Result<bool> {
+    match file.try_lock() {
+        Ok(()) => Ok(true),
+        Err(std::fs::TryLockError::WouldBlock) => Ok(false),
+        Err(std::fs::TryLockError::Error(e)) => Err(e),
+    }
+}
+
+/// Returns true if `path` still names the open `file`.
+fn is_same_file(file: &File, path: &Path) -> bool {
+    let Ok(handle) = file.try_clone().and_then(same_file::Handle::from_file) else {
+        return false;
+    };
+    same_file::Handle::from_path(path).is_ok_and(|current| current == handle)
+}
+
+impl Drop for RewriteLock {
+    fn drop(&mut self) {
+        // Remove the sidecar while still holding the lock, then release it
+        if let Some(path) = &self.path {
+            let _ = std::fs::remove_file(path);
+        }
+        let _ = self.file.unlock();
+    }
+}
+
+/// Replaces a file's contents by writing a sibling temp file and renaming it over the original.
+fn write_replacement(path: &Path, contents: &[u8]) -> CleanerResult<()> {
+    write_replacement_with(path, contents, replace_file)
+}
+
+/// Like [`write_replacement`], moving the temp file over the original with `replace`.
+fn write_replacement_with(
+    path: &Path,
+    contents: &[u8],
+    replace: impl FnOnce(&Path, &Path) -> std::io::Result<()>,
+) -> CleanerResult<()> {
+    let temp_path = unique_temp_path(path);
+
+    std::fs::write(&temp_path, contents)
+        .map_err(|e| CleanerError::file_operation(path, "Failed to create temp file", e))?;
+
+    if let Err(e) = replace(&temp_path, path) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(CleanerError::file_operation(path, "Failed to rename temp file", e));
+    }
+
+    Ok(())
+}
+
+/// Clears a file's read-only attribute; on Unix only the owner gains write permission.
+fn make_writable(path: &Path) -> std::io::Result<()> {
+    let mut permissions = std::fs::metadata(path)?.permissions();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(permissions.mode() | 0o200);
+    }
+
+    // On Windows this only clears FILE_ATTRIBUTE_READONLY
+    #[cfg(not(unix))]
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+
+    std::fs::set_permissions(path, permissions)
+}
+
+/// Makes a file read-only again after [`make_writable`].
+fn restore_readonly(path: &Path, original: &std::fs::Permissions) -> std::io::Result<()> {
+    // Windows permissions carry every attribute, so setting the saved ones would
+    // bring back hidden/system flags cleared since; only the read-only bit is set
+    if cfg!(windows) {
+        let mut permissions = std::fs::metadata(path)?.permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(path, permissions)
+    } else {
+        std::fs::set_permissions(path, original.clone())
     }
+}
+
+/// How often a rename over a briefly locked file is retried on Windows.
+#[cfg(windows)]
+const REPLACE_RETRIES: u32 = 5;
+
+/// Moves a finished temp file over `path` in a single step.
+///
+/// The original is never removed first, so an interruption leaves either the
+/// old or the new contents at `path`, never a missing file. `ReplaceFileW` also
+/// keeps the original's ACL and file ID; when it refuses (some network shares),
+/// a rename-over is retried while indexers or scanners release the file.
+#[cfg(windows)]
+fn replace_file(temp_path: &Path, path: &Path) -> std::io::Result<()> {
+    let target = wide_path(path);
+    let replacement = wide_path(temp_path);
+
+    let replaced = unsafe {
+        ReplaceFileW(
+            PCWSTR(target.as_ptr()),
+            PCWSTR(replacement.as_ptr()),
+            PCWSTR::null(),
+            REPLACEFILE_IGNORE_MERGE_ERRORS,
+            None,
+            None,
+        )
+    };
+    if replaced.is_ok() {
+        return Ok(());
+    }
+
+    let mut attempt = 0;
+    loop {
+        match std::fs::rename(temp_path, path) {
+            Ok(()) => return Ok(()),
+            Err(_) if attempt < REPLACE_RETRIES => {
+                attempt += 1;
+                std::thread::sleep(std::time::Duration::from_millis(50 * u64::from(attempt)));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Moves a finished temp file over `path` in a single step; `rename` replaces
+/// the target atomically, so `path` is never left missing.
+#[cfg(not(windows))]
+fn replace_file(temp_path: &Path, path: &Path) -> std::io::Result<()> {
+    std::fs::rename(temp_path, path)
+}
+
+/// Overwrites a file's contents through its existing handle.
+///
+/// Unlike [`write_replacement`] the file is never recreated, so its identity
+/// (inode, NTFS file ID and object ID) is kept and only write permission on the
+/// file itself is needed. An interruption can leave it partially written.
+fn overwrite_in_place(path: &Path, contents: &[u8]) -> CleanerResult<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(|e| CleanerError::file_operation(path, "Failed to open file for writing", e))?;
+
+    file.write_all(contents)
+        .and_then(|_| file.set_len(contents.len() as u64))
+        .and_then(|_| file.sync_all())
+        .map_err(|e| CleanerError::file_operation(path, "Failed to overwrite file", e))
+}
+
+/// Like [`overwrite_in_place`], streaming the new contents from `source`.
+fn overwrite_in_place_from(path: &Path, source: &Path) -> CleanerResult<()> {
+    let mut source = File::open(source)
+        .map_err(|e| CleanerError::file_operation(path, "Failed to open temp file", e))?;
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(|e| CleanerError::file_operation(path, "Failed to open file for writing", e))?;
+
+    std::io::copy(&mut source, &mut file)
+        .and_then(|len| file.set_len(len))
+        .and_then(|_| file.sync_all())
+        .map_err(|e| CleanerError::file_operation(path, "Failed to overwrite file", e))
+}
+
+use super::audio::{self, AudioKind};
+use super::email;
+use super::epub;
+use super::image;
+use super::lnk;
+use super::pdf;
+use super::svg;
+use super::xmp;
+use super::heif;
+use super::magic::Signature;
+use super::formats::{
+    AudioCleaner, CleanOutcome, EpubCleaner, ExifCleaner, FormatCleaner, GifCleaner, HeifCleaner, LnkCleaner, OfficeXmlCleaner, PdfCleaner, PngCleaner, RawCleaner,
+    SvgCleaner, WavCleaner, XmpCleaner, ZipCleaner, FORMAT_CLEANERS,
+};
+use super::error::{CleanerError, CleanerResult};
+#[cfg(windows)]
+use super::error::is_sharing_violation;
+use super::types::{
+    Capability, CapabilityStatus, CleanMode, CleanOptions, CleanReport, CleanerStep, FileInfo,
+    FileResult, OfficeProperty, RemainingLeak, ScanSummary, SensitivityScore, SizeChange, StepStatus, StreamInfo,
+    TimestampTarget,
+};
+#[cfg(windows)]
+use super::types::DEFAULT_OWNER_SID;
+use super::xml_encoding::DecodedXml;
+
+#[cfg(windows)]
+use std::os::windows::fs::OpenOptionsExt;
+
+#[cfg(windows)]
+use windows::core::{GUID, PCWSTR};
+#[cfg(windows)]
+use windows::Win32::Foundation::{HANDLE, FILETIME, LocalFree, ERROR_FILE_NOT_FOUND, ERROR_INVALID_FUNCTION, ERROR_LOCK_VIOLATION};
+#[cfg(windows)]
+use windows::Win32::Storage::FileSystem::{
+    DeleteFileW, FindClose, FindFirstStreamW, FindNextStreamW, LockFileEx, ReplaceFileW, SetFileAttributesW,
+    SetFileTime, LOCKFILE_EXCLUSIVE_LOCK, LOCKFILE_FAIL_IMMEDIATELY, REPLACEFILE_IGNORE_MERGE_ERRORS, FILE_ATTRIBUTE_ARCHIVE, FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_NORMAL, FILE_ATTRIBUTE_SYSTEM,
+    FILE_FLAGS_AND_ATTRIBUTES, FILE_FLAG_BACKUP_SEMANTICS, WIN32_FIND_STREAM_DATA,
+};
+#[cfg(windows)]
+use windows::Win32::System::Ioctl::{FILE_OBJECTID_BUFFER, FSCTL_DELETE_OBJECT_ID, FSCTL_GET_OBJECT_ID};
+#[cfg(windows)]
+use windows::Win32::System::IO::{DeviceIoControl, OVERLAPPED};
+#[cfg(windows)]
+use windows::Win32::Security::{OWNER_SECURITY_INFORMATION, PSID};
+#[cfg(windows)]
+use windows::Win32::Security::Authorization::{
+    ConvertStringSidToSidW, SE_FILE_OBJECT, SetNamedSecurityInfoW,
+};
+
+/// The main metadata cleaner that orchestrates all cleaning operations.
+#[derive(Debug, Clone, Default)]
+pub struct MetadataCleaner {
+    options: CleanOptions,
+    /// Shared by clones, so the archive limit holds across worker threads.
+    archive_slots: Arc<ArchiveSlots>,
+    /// Set on the worker cleaning a file under a timeout.
+    deadline: Option<Arc<Deadline>>,
+}
+
+impl MetadataCleaner {
+    /// Creates a new `MetadataCleaner` with default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new `MetadataCleaner` with the specified options.
+    pub fn with_options(options: CleanOptions) -> Self {
+        let archive_slots = Arc::new(ArchiveSlots::new(options.parallel_archives));
+        Self { options, archive_slots, deadline: None }
+    }
+
+    /// Returns the options this cleaner was created with.
+    pub fn options(&self) -> &CleanOptions {
+        &self.options
+    }
+
+    /// Returns true once the run has been asked to stop (see
+    /// [`CleanOptions::with_cancel_flag`]).
+    pub fn is_cancelled(&self) -> bool {
+        self.options.cancel_flag.as_ref().is_some_and(|flag| flag.load(Ordering::SeqCst))
+    }
+
+    /// Cleans metadata from a single file.
+    ///
+    /// The cleaners work on the canonical path, but the result names the file
+    /// as `path` gives it (see [`FileResult::with_display_path`]), unless it
+    /// describes a copy in the output directory.
+    ///
+    /// With a timeout set, the file is cleaned on a worker thread; if it doesn't
+    /// finish in time, [`CleanerError::TimedOut`] is returned. The worker stops
+    /// before its next step and starts no further rewrite, so the steps it had
+    /// already finished are all that changed; one stuck in a system call stays
+    /// stuck, since a thread can't be stopped.
+    pub fn clean_file(&self, path: &Path) -> CleanerResult<FileResult> {
+        let result = match self.options.timeout {
+            Some(timeout) => self.clean_file_timed(path, timeout)?,
+            None => self.clean_file_untimed(path)?,
+        };
+
+        Ok(match self.options.output_dir {
+            Some(_) if !self.options.dry_run => result,
+            _ => result.with_display_path(path),
+        })
+    }
+
+    /// Cleans a file on a worker thread, giving up on it after `timeout`.
+    fn clean_file_timed(&self, path: &Path, timeout: Duration) -> CleanerResult<FileResult> {
+        let (sender, receiver) = mpsc::channel();
+        let deadline = Arc::new(Deadline::default());
+        let cleaner = Self { deadline: Some(Arc::clone(&deadline)), ..self.clone() };
+        let worker_path = path.to_path_buf();
+        std::thread::Builder::new()
+            .name("clean-file".to_string())
+            .spawn(move || {
+                // The receiver is gone if the file already timed out
+                let _ = sender.send(cleaner.clean_file_untimed(&worker_path));
+            })?;
+
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => {
+                deadline.expire();
+                Err(CleanerError::TimedOut { path: path.to_path_buf(), timeout })
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(CleanerError::cleaning_failed(path, "The cleaning thread panicked")),
+        }
+    }
+
+    /// Fails with [`CleanerError::TimedOut`] once the file's timeout has passed,
+    /// so the worker cleaning it stops before its next step.
+    fn check_deadline(&self, path: &Path) -> CleanerResult<()> {
+        match &self.deadline {
+            Some(deadline) if deadline.has_passed() => Err(self.timed_out(path)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Runs `write`, which rewrites `path`, unless the file's timeout has passed;
+    /// the timeout can't pass while it runs.
+    fn commit<T>(&self, path: &Path, write: impl FnOnce() -> CleanerResult<T>) -> CleanerResult<T> {
+        let Some(deadline) = &self.deadline else {
+            return write();
+        };
+        let passed = deadline.passed.lock().unwrap_or_else(PoisonError::into_inner);
+        if *passed {
+            return Err(self.timed_out(path));
+        }
+        write()
+    }
+
+    fn timed_out(&self, path: &Path) -> CleanerError {
+        CleanerError::TimedOut { path: path.to_path_buf(), timeout: self.options.timeout.unwrap_or_default() }
+    }
+
+    /// Cleans metadata from a single file on the calling thread, without a timeout.
+    fn clean_file_untimed(&self, path: &Path) -> CleanerResult<FileResult> {
+        let path = resolve_path(&extended_length_path(path))?;
+
+        if !path.exists() {
+            return Err(CleanerError::PathNotFound(path));
+        }
+
+        if path.is_dir() {
+            return Err(CleanerError::NotAFile(path));
+        }
+
+        // Dry runs only inspect; nothing is opened for writing
+        if self.options.dry_run {
+            return Ok(self.plan_file(path));
+        }
+
+        // Hash the original before anything copies or modifies it
+        let sha256_before = if self.options.hash {
+            Some(sha256_file(&path).map_err(|e| CleanerError::file_operation(&path, "Failed to hash file", e))?)
+        } else {
+            None
+        };
+
+        // With an output directory the original is only read; its copy is cleaned
+        let path = match &self.options.output_dir {
+            Some(output_dir) => self.copy_to_output(&path, output_dir)?,
+            None => path,
+        };
+
+        // Re-runs over a mostly clean tree shouldn't rewrite every file again
+        if !self.options.force && self.is_already_clean(&path) {
+            let mut result = FileResult::unchanged(path);
+            result.sha256_after.clone_from(&sha256_before);
+            result.sha256_before = sha256_before;
+            return Ok(result);
+        }
+
+        // Files held open by other processes (editors, indexers, sync clients)
+        // are often released within seconds, so try again with a backoff
+        let bytes_before = self.stored_size(&path);
+        let mut attempt = 0;
+        loop {
+            self.check_deadline(&path)?;
+            let mut result = self.clean_existing_file(path.clone())?;
+            if !result.in_use || attempt >= self.options.retries {
+                if attempt > 0 {
+                    let detail = format!("{} of {} attempts", attempt + 1, self.options.retries + 1);
+                    result.steps.insert(0, CleanerStep::ran_with("retry", detail));
+                }
+                if self.options.verify && result.success {
+                    self.verify_cleaned(&mut result);
+                }
+                result.size = Some(SizeChange::new(bytes_before, self.stored_size(&result.path)));
+                if self.options.hash {
+                    result.sha256_after = sha256_file(&result.path).ok();
+                    result.sha256_before = sha256_before;
+                }
+                return Ok(result);
+            }
+
+            attempt += 1;
+            std::thread::sleep(retry_delay(attempt));
+        }
+    }
+
+    /// Re-inspects a cleaned file with [`verify`](Self::verify), failing the
+    /// result if metadata remains.
+    ///
+    /// Leaks in categories whose cleaner was skipped, such as the properties of
+    /// a signed document kept as is, were left on purpose and don't count.
+    fn verify_cleaned(&self, result: &mut FileResult) {
+        let leaks: Vec<String> = self
+            .verify(&result.path)
+            .into_iter()
+            .filter(|leak| {
+                !result.steps.iter().any(|step| step.name == leak.category && step.status == StepStatus::Skipped)
+            })
+            .map(|leak| leak.to_string())
+            .collect();
+
+        if leaks.is_empty() {
+            result.steps.push(CleanerStep::ran("verify"));
+            return;
+        }
+
+        let detail = leaks.join(", ");
+        result.error = Some(format!("Verification failed, metadata remains: {}", detail));
+        result.steps.push(CleanerStep::failed("verify", detail));
+        result.success = false;
+    }
+
+    /// Returns the size of a file's contents plus its alternate data streams
+    /// (extended attributes elsewhere), or 0 if it can't be read.
+    fn stored_size(&self, path: &Path) -> u64 {
+        let contents = std::fs::metadata(path).map_or(0, |metadata| metadata.len());
+        let streams: u64 = self.list_streams(path).map_or(0, |streams| streams.iter().map(|s| s.size).sum());
+        contents + streams
+    }
+
+    /// Cleans a file that exists, making it writable first if it is read-only.
+    fn clean_existing_file(&self, path: PathBuf) -> CleanerResult<FileResult> {
+        let permissions = std::fs::metadata(&path)?.permissions();
+        let mut steps = Vec::new();
+
+        // Keep a copy of the original, read-only attribute included, before
+        // anything changes; without one the file is not touched at all
+        if let Some(backup_dir) = &self.options.backup_dir {
+            match self.backup_file(&path, backup_dir) {
+                Ok(Some(backup)) => steps.push(CleanerStep::ran_with("backup", backup.display().to_string())),
+                Ok(None) => steps.push(CleanerStep::skipped("backup", "kept earlier backup")),
+                Err(e) => return Ok(failed_step(path, steps, "backup", e)),
+            }
+        }
+
+        // Read-only files can't be rewritten or have their timestamps set
+        if !permissions.readonly() {
+            return self.clean_writable_file(path, steps);
+        }
+
+        if let Err(e) = make_writable(&path) {
+            let error = CleanerError::cleaning_failed(
+                &path,
+                format!("File is read-only and the attribute could not be cleared: {}", e),
+            );
+            return Ok(failed_step(path, steps, "readonly", error));
+        }
+
+        let readonly_at = steps.len();
+        let mut result = self.clean_writable_file(path, steps)?;
+
+        if !self.options.preserve_readonly {
+            result.steps.insert(readonly_at, CleanerStep::ran_with("readonly", "cleared"));
+        } else if let Err(e) = restore_readonly(&result.path, &permissions) {
+            let message = format!("Failed to restore the read-only attribute: {}", e);
+            result.steps.insert(readonly_at, CleanerStep::failed("readonly", message.clone()));
+            result.success = false;
+            result.error.get_or_insert(message);
+        } else {
+            result.steps.insert(readonly_at, CleanerStep::ran_with("readonly", "cleared, then restored"));
+        }
+
+        Ok(result)
+    }
+
+    /// Runs every enabled cleaner on a file that is known to be writable, after
+    /// the `steps` already taken (the backup).
+    fn clean_writable_file(&self, path: PathBuf, mut steps: Vec<CleanerStep>) -> CleanerResult<FileResult> {
+        let mut streams_removed = 0;
+        let mut timestamps_reset = false;
+        let mut office_size = None;
+        let mut exif_stripped = false;
+        let mut streams_removed_names = Vec::new();
+        let mut properties_cleared = Vec::new();
+        let mut owner_cleared = false;
+        let mut object_id_removed = false;
+        let mut archive_entries_cleaned = Vec::new();
+        let mut warnings = Vec::new();
+
+        // Remove alternate data streams. Each step first checks that the file
+        // hasn't timed out meanwhile (see `clean_file`)
+        self.check_deadline(&path)?;
+        if self.options.clear_streams {
+            match self.remove_alternate_streams(&path) {
+                Ok(names) => {
+                    streams_removed = names.len();
+                    let shredded = if self.options.shred_streams && !names.is_empty() { ", overwritten first" } else { "" };
+                    steps.push(CleanerStep::ran_with("streams", format!("{} removed{}", names.len(), shredded)));
+                    streams_removed_names = names;
+                }
+                Err(e) => return Ok(failed_step(path, steps, "streams", e)),
+            }
+        } else {
+            steps.push(CleanerStep::skipped("streams", "disabled"));
+        }
+
+        // Delete the NTFS object ID before any rewrite replaces the file record
+        self.check_deadline(&path)?;
+        if !self.options.clear_object_id {
+            steps.push(CleanerStep::skipped("object-id", "disabled"));
+        } else if cfg!(windows) {
+            match self.remove_object_id(&path) {
+                Ok(true) => {
+                    object_id_removed = true;
+                    steps.push(CleanerStep::ran_with("object-id", "removed"));
+                }
+                Ok(false) => steps.push(CleanerStep::skipped("object-id", "none present")),
+                Err(e) => return Ok(failed_step(path, steps, "object-id", e)),
+            }
+        } else {
+            steps.push(CleanerStep::skipped("object-id", "not supported on this platform"));
+        }
+
+        // Clear file owner (requires Administrator privileges)
+        // Only attempted with --admin (or `clear_owner` in the config file)
+        self.check_deadline(&path)?;
+        if self.options.clear_owner {
+            match self.clear_owner(&path) {
+                Ok(()) => {
+                    owner_cleared = true;
+                    steps.push(CleanerStep::ran("owner"));
+                }
+                Err(e) => return Ok(failed_step(path, steps, "owner", e)),
+            }
+        } else {
+            steps.push(CleanerStep::skipped("owner", "needs --admin"));
+        }
+
+        // Clear file properties (author, computer, etc.) from NTFS streams
+        self.check_deadline(&path)?;
+        if self.options.clear_properties {
+            match self.clear_properties(&path) {
+                Ok(names) if names.is_empty() => steps.push(CleanerStep::ran("property-streams")),
+                Ok(names) => {
+                    steps.push(CleanerStep::ran_with("property-streams", names.join(", ")));
+                    properties_cleared.extend(names);
+                }
+                Err(e) => return Ok(failed_step(path, steps, "property-streams", e)),
+            }
+        } else {
+            steps.push(CleanerStep::skipped("property-streams", "disabled"));
+        }
+
+        // Format-specific metadata: document properties, email headers, image,
+        // audio and PDF metadata, by signature and extension
+        let signature = Signature::of_file(&path);
+        for format in FORMAT_CLEANERS {
+            let name = format.name();
+            if !self.options.clear_properties {
+                steps.push(CleanerStep::skipped(name, "disabled"));
+                continue;
+            }
+            if !format.handles_path(&path, signature) {
+                steps.push(CleanerStep::skipped(name, format.not_handled()));
+                continue;
+            }
+
+            self.check_deadline(&path)?;
+            match format.clean(self, &path) {
+                Ok(CleanOutcome::Cleaned { detail, properties, size, entries, warnings: notes }) => {
+                    steps.push(CleanerStep::ran_with(name, detail));
+                    properties_cleared.extend(properties);
+                    archive_entries_cleaned.extend(entries);
+                    warnings.extend(notes.into_iter().map(|note| format!("{}: {}", name, note)));
+                    office_size = office_size.or(size);
+                    exif_stripped |= name == "exif";
+                }
+                Ok(CleanOutcome::NotApplicable(reason)) => steps.push(CleanerStep::skipped(name, reason)),
+                Ok(CleanOutcome::Skipped(reason)) => {
+                    warnings.push(format!("{}: {}", name, reason));
+                    steps.push(CleanerStep::skipped(name, reason));
+                }
+                // Named like the format but not parseable as it: left as is, with a warning
+                Err(e @ CleanerError::UnsupportedFormat { .. }) => {
+                    warnings.push(format!("{}: {}", name, e));
+                    steps.push(CleanerStep::skipped(name, e.to_string()));
+                }
+                // A file held by another process fails so it can be retried
+                Err(e) if e.is_in_use() => return Ok(failed_step(path, steps, name, e)),
+                // Streams and timestamps still get cleaned when a format can't be parsed
+                Err(e) => {
+                    steps.push(CleanerStep::failed(name, e.to_string()));
+                    warnings.push(format!("{}: {}", name, e));
+                }
+            }
+        }
+
+        // Clear hidden/system/archive flags after the rewrites, which set the archive flag again
+        self.check_deadline(&path)?;
+        if !self.options.clear_attributes {
+            steps.push(CleanerStep::skipped("attributes", "disabled"));
+        } else if cfg!(windows) {
+            match self.clear_file_attributes(&path) {
+                Ok(()) => steps.push(CleanerStep::ran("attributes")),
+                Err(e) => return Ok(failed_step(path, steps, "attributes", e)),
+            }
+        } else {
+            steps.push(CleanerStep::skipped("attributes", "not supported on this platform"));
+        }
+
+        // Reset timestamps last, so the content rewrites above don't overwrite them
+        self.check_deadline(&path)?;
+        if self.options.clear_timestamps && self.options.timestamp_target == TimestampTarget::Unchanged {
+            steps.push(CleanerStep::skipped("timestamps", "left unchanged"));
+        } else if self.options.clear_timestamps {
+            match self.reset_timestamps(&path) {
+                // A random time differs per file, so record which one this file got
+                Ok(Some(time)) if matches!(self.options.timestamp_target, TimestampTarget::Random { .. }) => {
+                    timestamps_reset = true;
+                    let time = time::OffsetDateTime::from(time).format(&time::format_description::well_known::Rfc3339);
+                    steps.push(CleanerStep::ran_with("timestamps", format!("set to {}", time.unwrap_or_default())));
+                }
+                Ok(_) => {
+                    timestamps_reset = true;
+                    steps.push(CleanerStep::ran("timestamps"));
+                }
+                Err(e) => return Ok(failed_step(path, steps, "timestamps", e)),
+            }
+        } else {
+            steps.push(CleanerStep::skipped("timestamps", "disabled"));
+        }
+
+        // Keep creation <= modification so the timestamps don't look tampered with
+        self.check_deadline(&path)?;
+        if self.options.fix_timestamp_order {
+            match self.normalize_timestamp_order(&path) {
+                Ok(true) => steps.push(CleanerStep::ran_with("timestamp-order", "creation time moved back")),
+                Ok(false) => steps.push(CleanerStep::ran_with("timestamp-order", "already ordered")),
+                Err(e) => return Ok(failed_step(path, steps, "timestamp-order", e)),
+            }
+        }
+
+        let mut result = FileResult::success(path, streams_removed, timestamps_reset).with_steps(steps);
+        result.office_size = office_size;
+        result.exif_stripped = exif_stripped;
+        result.streams_removed_names = streams_removed_names;
+        result.properties_cleared = properties_cleared;
+        result.owner_cleared = owner_cleared;
+        result.object_id_removed = object_id_removed;
+        result.archive_entries_cleaned = archive_entries_cleaned;
+        result.warnings = warnings;
+        Ok(result)
+    }
+
+    /// Cleans metadata from all files in a directory (non-recursive).
+    pub fn clean_directory_shallow(&self, path: &Path) -> CleanerResult<CleanReport> {
+        self.clean_directory_with_progress(path, CleanMode::Shallow, |_| {})
+    }
+
+    /// Cleans metadata from all files in a directory and subdirectories (recursive).
+    pub fn clean_directory_deep(&self, path: &Path) -> CleanerResult<CleanReport> {
+        self.clean_directory_with_progress(path, CleanMode::Deep, |_| {})
+    }
+
+    /// Cleans a directory tree like [`clean_directory_deep`](Self::clean_directory_deep)
+    /// without blocking the async runtime.
+    ///
+    /// Files are cleaned on tokio's blocking thread pool; each file's result is
+    /// sent on `progress` as soon as it finishes, so a GUI can show live progress.
+    /// Results stop being sent if the receiver is dropped, but cleaning continues.
+    #[cfg(feature = "async")]
+    pub async fn clean_directory_deep_async(
+        &self,
+        path: PathBuf,
+        progress: tokio::sync::mpsc::Sender<FileResult>,
+    ) -> CleanerResult<CleanReport> {
+        let cleaner = self.clone();
+        let root = path.clone();
+        tokio::task::spawn_blocking(move || {
+            cleaner.clean_directory_with_progress(&root, CleanMode::Deep, |result| {
+                let _ = progress.blocking_send(result.clone());
+            })
+        })
+        .await
+        .map_err(|e| CleanerError::cleaning_failed(path, format!("Cleaning task failed: {}", e)))?
+    }
+
+    /// Cleans a directory with the specified mode, calling `on_file` with each
+    /// file's result as soon as it finishes.
+    ///
+    /// Files are cleaned in parallel; calls to `on_file` are serialized, so it
+    /// can drive a progress bar or collect results without its own locking.
+    pub fn clean_directory_with_progress(
+        &self,
+        path: &Path,
+        mode: CleanMode,
+        on_file: impl FnMut(&FileResult) + Send,
+    ) -> CleanerResult<CleanReport> {
+        let path = resolve_path(&extended_length_path(path))?;
+
+        if !path.exists() {
+            return Err(CleanerError::PathNotFound(path));
+        }
+
+        if !path.is_dir() {
+            return Err(CleanerError::NotADirectory(path));
+        }
+
+        // Collect first so the files can be cleaned in parallel
+        let (collected, symlinks) = self.collect_files_and_symlinks(&path, mode)?;
+
+        let mut report = self.clean_files_with_progress(&collected, on_file)?;
+        for _ in &symlinks {
+            report.add_skipped();
+        }
+
+        // Junk and folders are left alone once interrupted
+        if self.is_cancelled() {
+            return Ok(report);
+        }
+
+        for junk in self.collect_junk(&path, mode)? {
+            report.add_junk_result(self.remove_junk_file(&junk));
+        }
+
+        // Folders last, as cleaning the files inside them updates their times
+        for dir in self.collect_directories(&path, mode)? {
+            report.add_directory_result(self.reset_directory_timestamps(&dir));
+        }
+
+        Ok(report)
+    }
+
+    /// Cleans a list of files in parallel, calling `on_file` with each file's
+    /// result as soon as it finishes.
+    ///
+    /// Protected files (our own executable, log or state files) are counted as
+    /// skipped and not passed to `on_file`. A file that fails to clean is
+    /// recorded as a failure rather than aborting the run. Once cancelled, files
+    /// not yet started are counted as not processed and the rest finish.
+    pub fn clean_files_with_progress(
+        &self,
+        files: &[PathBuf],
+        on_file: impl FnMut(&FileResult) + Send,
+    ) -> CleanerResult<CleanReport> {
+        enum Outcome {
+            Cleaned(Box<FileResult>),
+            Protected,
+            NotStarted,
+        }
+
+        let on_file = Mutex::new(on_file);
+
+        let outcomes: Vec<Outcome> = self.in_thread_pool(|| {
+            files
+                .par_iter()
+                .map(|file| {
+                    if self.is_cancelled() {
+                        return Outcome::NotStarted;
+                    }
+
+                    // Never touch our own executable, log or state files
+                    if self.is_protected(file) {
+                        return Outcome::Protected;
+                    }
+
+                    let result = self.clean_file(file)
+                        .unwrap_or_else(|e| FileResult::failure(file.clone(), e.to_string()));
+                    (on_file.lock().unwrap_or_else(PoisonError::into_inner))(&result);
+                    Outcome::Cleaned(Box::new(result))
+                })
+                .collect()
+        })?;
+
+        let mut report = CleanReport::new();
+        for outcome in outcomes {
+            match outcome {
+                Outcome::Cleaned(result) => report.add_result(*result),
+                Outcome::Protected => report.add_skipped(),
+                Outcome::NotStarted => report.add_not_processed(),
+            }
+        }
+        report.sort_results();
+
+        Ok(report)
+    }
+
+    /// Runs `op` on a thread pool sized by the `jobs` option.
+    ///
+    /// Parallel iterators used inside `op` are capped at that many threads.
+    pub fn in_thread_pool<R: Send>(&self, op: impl FnOnce() -> R + Send) -> CleanerResult<R> {
+        let mut builder = rayon::ThreadPoolBuilder::new();
+        if let Some(jobs) = self.options.jobs {
+            builder = builder.num_threads(jobs);
+        }
+
+        Ok(builder.build()?.install(op))
+    }
+
+    /// Copies `path` into the backup directory before it is modified.
+    ///
+    /// An existing backup is never overwritten, since after the first run it holds
+    /// the original and the file itself is already cleaned. Returns the backup's
+    /// path, or `None` if an earlier backup was kept.
+    fn backup_file(&self, path: &Path, backup_dir: &Path) -> CleanerResult<Option<PathBuf>> {
+        let backup = backup_path(path, backup_dir);
+        if backup.exists() {
+            return Ok(None);
+        }
+
+        if let Some(parent) = backup.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                CleanerError::cleaning_failed(path, format!("Failed to create backup directory: {}", e))
+            })?;
+        }
+
+        std::fs::copy(path, &backup)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to back up file", e))?;
+
+        Ok(Some(backup))
+    }
+
+    /// Pairs each backup of `target` in `backup_dir` with the file it restores,
+    /// as `(backup, original)`.
+    ///
+    /// Backups mirror the full path of the file they were taken from, so a file
+    /// target has at most one, and a directory target gets every backup below its
+    /// mirrored folder, matched back by relative path (including files since deleted).
+    pub fn find_backups(&self, backup_dir: &Path, target: &Path) -> CleanerResult<Vec<(PathBuf, PathBuf)>> {
+        let target = resolve_path(&extended_length_path(target))?;
+        let backup_dir = resolve_path(backup_dir)?;
+        let mirror = backup_path(&target, &backup_dir);
+
+        if mirror.is_file() {
+            return Ok(vec![(mirror, target)]);
+        }
+        if !mirror.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        Ok(WalkDir::new(&mirror)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|entry| entry.file_type().is_file() && !is_own_artifact(entry.path()))
+            .filter_map(|entry| {
+                let relative = entry.path().strip_prefix(&mirror).ok()?;
+                Some((entry.path().to_path_buf(), target.join(relative)))
+            })
+            .collect())
+    }
+
+    /// Copies a backup over the file it was taken from, recreating missing folders.
+    pub fn restore_backup(&self, backup: &Path, original: &Path) -> CleanerResult<()> {
+        if let Some(parent) = original.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                CleanerError::cleaning_failed(original, format!("Failed to create directory: {}", e))
+            })?;
+        }
+
+        let _lock = RewriteLock::acquire(original)?;
+
+        // The backup of a read-only file is read-only too, and so is the file once restored
+        if std::fs::metadata(original).is_ok_and(|metadata| metadata.permissions().readonly()) {
+            make_writable(original)
+                .map_err(|e| CleanerError::file_operation(original, "Failed to clear the read-only attribute", e))?;
+        }
+
+        std::fs::copy(backup, original)
+            .map_err(|e| CleanerError::file_operation(original, "Failed to restore backup", e))?;
+        Ok(())
+    }
+
+    /// Copies `path` to its place in the output directory, replacing an earlier
+    /// copy, and returns the copy's path.
+    fn copy_to_output(&self, path: &Path, output_dir: &Path) -> CleanerResult<PathBuf> {
+        let output_dir = resolve_path(output_dir).unwrap_or_else(|_| output_dir.to_path_buf());
+        let copy = output_path(path, &output_dir, self.options.output_root.as_deref());
+        if copy == path {
+            return Err(CleanerError::cleaning_failed(path, "The output directory copy would overwrite the original"));
+        }
+
+        if let Some(parent) = copy.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                CleanerError::cleaning_failed(path, format!("Failed to create output directory: {}", e))
+            })?;
+        }
+
+        std::fs::copy(path, &copy).map_err(|e| CleanerError::file_operation(path, "Failed to copy to output directory", e))?;
+        Ok(copy)
+    }
+
+    /// Returns true if the path belongs to the running tool (executable, log or
+    /// state file, or anything inside the backup or output directory).
+    pub fn is_protected(&self, path: &Path) -> bool {
+        if self.options.protected_paths.is_empty()
+            && self.options.backup_dir.is_none()
+            && self.options.output_dir.is_none()
+        {
+            return false;
+        }
+
+        let candidate = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        for dir in self.options.backup_dir.iter().chain(&self.options.output_dir) {
+            let dir = dir.canonicalize().unwrap_or_else(|_| dir.clone());
+            if candidate.starts_with(&dir) {
+                return true;
+            }
+        }
+
+        self.options.protected_paths.iter().any(|protected| {
+            let protected = protected.canonicalize().unwrap_or_else(|_| protected.clone());
+            protected == candidate
+        })
+    }
+
+    /// Returns true if a stream named in `keep_streams` or matching one of
+    /// `keep_stream_patterns` protects this one.
+    /// NTFS stream names are case-insensitive, so the name match is too.
+    fn is_kept_stream(&self, name: &str) -> bool {
+        self.options.keep_streams.iter().any(|kept| kept.eq_ignore_ascii_case(name))
+            || self.options.keep_stream_patterns.iter().any(|pattern| pattern.is_match(name))
+    }
+
+    /// Returns true if cleaning should remove the named stream: known metadata,
+    /// or any stream with `all_streams`, unless it is kept by name or pattern.
+    fn is_removable_stream(&self, name: &str) -> bool {
+        if self.is_kept_stream(name) {
+            return false;
+        }
+
+        // Extended attributes are already limited to provenance ones when listed
+        self.options.all_streams
+            || cfg!(not(windows))
+            || PROPERTY_STREAMS.iter().chain(METADATA_STREAMS).any(|known| known.eq_ignore_ascii_case(name))
+    }
+
+    /// Lists the streams of a file that cleaning would remove, as enumerated.
+    /// The main `::$DATA` stream is never included.
+    fn removable_streams(&self, path: &Path) -> CleanerResult<Vec<String>> {
+        Ok(self
+            .enumerate_streams(path)?
+            .into_iter()
+            .filter(|raw| {
+                let name = stream_name(raw);
+                !name.is_empty() && self.is_removable_stream(name)
+            })
+            .collect())
+    }
+
+    /// Like [`removable_streams`](Self::removable_streams), with each stream's size.
+    fn removable_stream_sizes(&self, path: &Path) -> CleanerResult<Vec<(String, u64)>> {
+        Ok(self
+            .enumerate_stream_sizes(path)?
+            .into_iter()
+            .filter(|(raw, _)| {
+                let name = stream_name(raw);
+                !name.is_empty() && self.is_removable_stream(name)
+            })
+            .collect())
+    }
+
+    /// Removes metadata alternate data streams from a file (every named stream with `all_streams`).
+    /// Returns the names of the streams removed.
+    ///
+    /// A stream that can't be deleted doesn't stop the others from being tried,
+    /// but fails the whole step afterwards so it never passes for removed.
+    #[cfg(windows)]
+    fn remove_alternate_streams(&self, path: &Path) -> CleanerResult<Vec<String>> {
+        let mut removed = Vec::new();
+        let mut failures = Vec::new();
+
+        for (raw_name, size) in self.removable_stream_sizes(path)? {
+            if self.options.shred_streams {
+                self.shred_stream(path, &raw_name, size)?;
+            }
+
+            match self.delete_stream(path, &raw_name) {
+                Ok(()) => removed.push(stream_name(&raw_name).to_string()),
+                Err(e @ CleanerError::FileInUse(_)) => return Err(e),
+                Err(e) => failures.push(format!("{}: {}", stream_name(&raw_name), e)),
+            }
+        }
+
+        if !failures.is_empty() {
+            return Err(CleanerError::cleaning_failed(
+                path,
+                format!("Failed to remove {} of {} streams ({})", failures.len(), failures.len() + removed.len(), failures.join("; ")),
+            ));
+        }
+
+        Ok(removed)
+    }
+
+    /// Removes download-provenance extended attributes, the Unix analogue of
+    /// `Zone.Identifier` (e.g. `com.apple.quarantine`, `user.xdg.origin.url`).
+    /// Returns the names of the attributes removed.
+    #[cfg(not(windows))]
+    fn remove_alternate_streams(&self, path: &Path) -> CleanerResult<Vec<String>> {
+        let mut removed = Vec::new();
+
+        for (name, size) in self.removable_stream_sizes(path)? {
+            if self.options.shred_streams {
+                self.shred_stream(path, &name, size)?;
+            }
+            xattr::remove(path, &name)
+                .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to remove {}: {}", name, e)))?;
+            removed.push(name);
+        }
+
+        Ok(removed)
+    }
+
+    /// Enumerates all alternate data streams for a file.
+    #[cfg(windows)]
+    fn enumerate_streams(&self, path: &Path) -> CleanerResult<Vec<String>> {
+        Ok(self.enumerate_stream_sizes(path)?.into_iter().map(|(name, _)| name).collect())
+    }
+
+    /// Enumerates all streams of a file as `(:name:$DATA, size)` pairs, including `::$DATA`.
+    #[cfg(windows)]
+    fn enumerate_stream_sizes(&self, path: &Path) -> CleanerResult<Vec<(String, u64)>> {
+        let wide_path = wide_path(path);
+
+        let mut streams = Vec::new();
+        let mut find_data = WIN32_FIND_STREAM_DATA::default();
+
+        unsafe {
+            let handle = FindFirstStreamW(
+                PCWSTR(wide_path.as_ptr()),
+                windows::Win32::Storage::FileSystem::FindStreamInfoStandard,
+                &mut find_data as *mut _ as *mut _,
+                0,
+            );
+
+            match handle {
+                Ok(h) if !h.is_invalid() => {
+                    loop {
+                        let stream_name = String::from_utf16_lossy(
+                            &find_data.cStreamName[..find_data.cStreamName.iter().position(|&c| c == 0).unwrap_or(find_data.cStreamName.len())]
+                        );
+
+                        if !stream_name.is_empty() {
+                            streams.push((stream_name, find_data.StreamSize as u64));
+                        }
+
+                        if FindNextStreamW(h, &mut find_data as *mut _ as *mut _).is_err() {
+                            break;
+                        }
+                    }
+                    let _ = FindClose(h);
+                }
+                _ => {
+                    // No streams found or error - this is not necessarily an error condition
+                }
+            }
+        }
+
+        Ok(streams)
+    }
+
+    /// Lists the provenance extended attributes present on a file. Other attributes
+    /// (ACLs, SELinux labels, Finder info) are left alone.
+    #[cfg(not(windows))]
+    fn enumerate_streams(&self, path: &Path) -> CleanerResult<Vec<String>> {
+        if !xattr::SUPPORTED_PLATFORM {
+            return Ok(Vec::new());
+        }
+
+        let names = xattr::list(path).map_err(|_| CleanerError::StreamEnumerationFailed(path.to_path_buf()))?;
+
+        Ok(names
+            .filter_map(|name| name.into_string().ok())
+            .filter(|name| PROVENANCE_XATTRS.contains(&name.as_str()))
+            .collect())
+    }
+
+    /// Lists the provenance extended attributes of a file with the size of their values.
+    #[cfg(not(windows))]
+    fn enumerate_stream_sizes(&self, path: &Path) -> CleanerResult<Vec<(String, u64)>> {
+        Ok(self
+            .enumerate_streams(path)?
+            .into_iter()
+            .map(|name| {
+                let size = xattr::get(path, &name).ok().flatten().map_or(0, |value| value.len() as u64);
+                (name, size)
+            })
+            .collect())
+    }
+
+    /// Resets file timestamps to the configured target, by default January 1, 2000
+    /// (a neutral date that doesn't reveal when the file was actually created).
+    #[cfg(windows)]
+    fn reset_timestamps(&self, path: &Path) -> CleanerResult<Option<SystemTime>> {
+        use std::os::windows::io::AsRawHandle;
+
+        let Some(target) = self.options.timestamp_target.resolve(path) else {
+            return Ok(None);
+        };
+
+        // Open the file with write access for setting times
+        let file = OpenOptions::new()
+            .write(true)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS.0)
+            .open(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to open file", e))?;
+
+        let target_time = system_time_to_filetime(target);
+        let handle = HANDLE(file.as_raw_handle() as _);
+
+        // SetFileTime can't set the change time, so try ntdll first and fall back
+        // to the three standard timestamps if it refuses
+        if self.options.set_change_time && set_basic_times(handle, &target_time).is_ok() {
+            return Ok(Some(target));
+        }
+
+        unsafe {
+            SetFileTime(
+                handle,
+                Some(&target_time), // Creation time
+                Some(&target_time), // Last access time
+                Some(&target_time), // Last write time
+            )
+            .map_err(|e| CleanerError::windows_api_error(path, e.to_string()))?;
+        }
+
+        Ok(Some(target))
+    }
+
+    /// Resets the access and modification times to the configured target, as on Windows.
+    ///
+    /// The creation (birth) time, where the filesystem keeps one, can't be set
+    /// through any portable call, so it keeps showing when the file was created.
+    #[cfg(not(windows))]
+    fn reset_timestamps(&self, path: &Path) -> CleanerResult<Option<SystemTime>> {
+        let Some(target) = self.options.timestamp_target.resolve(path) else {
+            return Ok(None);
+        };
+
+        // Works on directories too, which can't be opened for writing
+        let time = filetime::FileTime::from_system_time(target);
+        filetime::set_file_times(path, time, time)
+            .map_err(|e| CleanerError::cleaning_failed(path, e.to_string()))?;
+
+        Ok(Some(target))
+    }
+
+    /// Returns true if the file claims to have been created after it was last modified.
+    ///
+    /// This happens when files are copied (the copy gets a fresh creation time) or when
+    /// timestamps have been edited, and is itself a forensic tell.
+    pub fn has_inverted_timestamps(&self, path: &Path) -> bool {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return false;
+        };
+
+        match (metadata.created(), metadata.modified()) {
+            (Ok(created), Ok(modified)) => created > modified,
+            _ => false,
+        }
+    }
+
+    /// Moves the creation time back to the modification time when it is later.
+    /// Returns true if the file was adjusted.
+    #[cfg(windows)]
+    fn normalize_timestamp_order(&self, path: &Path) -> CleanerResult<bool> {
+        use std::os::windows::io::AsRawHandle;
+
+        if !self.has_inverted_timestamps(path) {
+            return Ok(false);
+        }
+
+        let modified = std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .map_err(|e| CleanerError::cleaning_failed(path, e.to_string()))?;
+
+        let file = OpenOptions::new()
+            .write(true)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS.0)
+            .open(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to open file", e))?;
+
+        let creation_time = system_time_to_filetime(modified);
+
+        unsafe {
+            let handle = HANDLE(file.as_raw_handle() as _);
+            // Only the creation time changes; access and write times are left alone
+            SetFileTime(handle, Some(&creation_time), None, None)
+                .map_err(|e| CleanerError::windows_api_error(path, e.to_string()))?;
+        }
+
+        Ok(true)
+    }
+
+    #[cfg(not(windows))]
+    fn normalize_timestamp_order(&self, _path: &Path) -> CleanerResult<bool> {
+        // Creation time can't be set on most non-Windows filesystems
+        Ok(false)
+    }
+
+    /// Clears the file owner by setting it to the configured well-known SID
+    /// (Everyone by default), so the file no longer names the user who made it.
+    #[cfg(windows)]
+    fn clear_owner(&self, path: &Path) -> CleanerResult<()> {
+        use windows::Win32::Foundation::HLOCAL;
+
+        let owner_sid = self.options.owner_sid.as_deref().unwrap_or(DEFAULT_OWNER_SID);
+        let sid_string: Vec<u16> = owner_sid.encode_utf16().chain(std::iter::once(0)).collect();
+        let wide_path = wide_path(path);
+
+        unsafe {
+            let mut sid: PSID = PSID::default();
+
+            // Convert the string SID to a binary SID
+            let result = ConvertStringSidToSidW(
+                PCWSTR(sid_string.as_ptr()),
+                &mut sid,
+            );
+
+            if result.is_err() {
+                return Err(CleanerError::InvalidSid(owner_sid.to_string()));
+            }
+
+            // Set the owner on the file using SetNamedSecurityInfoW
+            let result = SetNamedSecurityInfoW(
+                PCWSTR(wide_path.as_ptr()),
+                SE_FILE_OBJECT,
+                OWNER_SECURITY_INFORMATION,
+                sid,
+                PSID::default(),
+                None,
+                None,
+            );
+
+            // Free the SID memory allocated by ConvertStringSidToSidW
+            let _ = LocalFree(HLOCAL(sid.0));
+
+            // SetNamedSecurityInfoW returns WIN32_ERROR, ERROR_SUCCESS (0) means success
+            if result.0 != 0 {
+                return Err(CleanerError::windows_api_error(path, format!("Failed to set owner (error {}). Run as Administrator.", result.0)));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    fn clear_owner(&self, _path: &Path) -> CleanerResult<()> {
+        // Owner clearing is Windows-specific
+        Ok(())
+    }
+
+    /// Returns the file's NTFS object ID as a GUID, or `None` if it has none or the
+    /// filesystem doesn't support object IDs.
+    #[cfg(windows)]
+    fn object_id(&self, path: &Path) -> CleanerResult<Option<String>> {
+        use std::os::windows::io::AsRawHandle;
+
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS.0)
+            .open(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to open file", e))?;
+
+        let mut buffer = FILE_OBJECTID_BUFFER::default();
+        let mut returned = 0u32;
+        let result = unsafe {
+            DeviceIoControl(
+                HANDLE(file.as_raw_handle() as _),
+                FSCTL_GET_OBJECT_ID,
+                None,
+                0,
+                Some(&mut buffer as *mut FILE_OBJECTID_BUFFER as *mut _),
+                std::mem::size_of::<FILE_OBJECTID_BUFFER>() as u32,
+                Some(&mut returned),
+                None,
+            )
+        };
+
+        match result {
+            Ok(()) => {
+                let id = buffer.ObjectId;
+                let guid = GUID::from_values(
+                    u32::from_le_bytes([id[0], id[1], id[2], id[3]]),
+                    u16::from_le_bytes([id[4], id[5]]),
+                    u16::from_le_bytes([id[6], id[7]]),
+                    [id[8], id[9], id[10], id[11], id[12], id[13], id[14], id[15]],
+                );
+                Ok(Some(format!("{:?}", guid)))
+            }
+            // No object ID, or a filesystem without them (FAT, exFAT, most network shares)
+            Err(e) if e.code() == ERROR_FILE_NOT_FOUND.to_hresult() || e.code() == ERROR_INVALID_FUNCTION.to_hresult() => {
+                Ok(None)
+            }
+            Err(e) => Err(CleanerError::windows_api_error(path, e.to_string())),
+        }
+    }
+
+    #[cfg(not(windows))]
+    fn object_id(&self, _path: &Path) -> CleanerResult<Option<String>> {
+        // Object IDs are NTFS-specific
+        Ok(None)
+    }
+
+    /// Deletes the file's NTFS object ID, so link tracking can no longer match it
+    /// to shortcuts made elsewhere. Returns false if it had none.
+    #[cfg(windows)]
+    fn remove_object_id(&self, path: &Path) -> CleanerResult<bool> {
+        use std::os::windows::io::AsRawHandle;
+
+        if self.object_id(path)?.is_none() {
+            return Ok(false);
+        }
+
+        let file = OpenOptions::new()
+            .write(true)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS.0)
+            .open(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to open file", e))?;
+
+        let mut returned = 0u32;
+        unsafe {
+            DeviceIoControl(
+                HANDLE(file.as_raw_handle() as _),
+                FSCTL_DELETE_OBJECT_ID,
+                None,
+                0,
+                None,
+                0,
+                Some(&mut returned),
+                None,
+            )
+            .map_err(|e| CleanerError::windows_api_error(path, e.to_string()))?;
+        }
+
+        Ok(true)
+    }
+
+    #[cfg(not(windows))]
+    fn remove_object_id(&self, _path: &Path) -> CleanerResult<bool> {
+        // Object IDs are NTFS-specific
+        Ok(false)
+    }
+
+    /// Resets the hidden, system and archive attributes, leaving a plain normal file.
+    /// Other attributes (read-only, compressed, encrypted) are kept.
+    #[cfg(windows)]
+    fn clear_file_attributes(&self, path: &Path) -> CleanerResult<()> {
+        use std::os::windows::fs::MetadataExt;
+
+        let attributes = std::fs::metadata(path)?.file_attributes();
+        let cleared = attributes & !(FILE_ATTRIBUTE_HIDDEN.0 | FILE_ATTRIBUTE_SYSTEM.0 | FILE_ATTRIBUTE_ARCHIVE.0);
+        if cleared == attributes {
+            return Ok(());
+        }
+
+        // An empty attribute set must be written as FILE_ATTRIBUTE_NORMAL
+        let attributes = if cleared == 0 { FILE_ATTRIBUTE_NORMAL } else { FILE_FLAGS_AND_ATTRIBUTES(cleared) };
+        let wide_path = wide_path(path);
+
+        unsafe {
+            SetFileAttributesW(PCWSTR(wide_path.as_ptr()), attributes)
+                .map_err(|e| CleanerError::windows_api_error(path, e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    fn clear_file_attributes(&self, _path: &Path) -> CleanerResult<()> {
+        // Hidden/system/archive attributes are Windows-specific
+        Ok(())
+    }
+
+    /// Clears file properties stored in NTFS extended attributes and various streams.
+    /// This removes author, computer name, and other metadata from the Details tab.
+    /// Returns the names of the property streams that were present and removed.
+    #[cfg(windows)]
+    fn clear_properties(&self, path: &Path) -> CleanerResult<Vec<String>> {
+        // Windows stores various metadata in alternate data streams:
+        // - Zone.Identifier: Downloaded file info (includes URL, computer info)
+        // - SummaryInformation: OLE document properties
+        // - DocumentSummaryInformation: Extended document properties
+        // - Afp_AfpInfo, encryptable, OECustomProperty, etc.
+
+        let mut removed = Vec::new();
+
+        for stream_name in PROPERTY_STREAMS {
+            if self.is_kept_stream(stream_name) {
+                continue;
+            }
+
+            let stream_path = format!("{}:{}", path.display(), stream_name);
+            let wide_path = wide_path(Path::new(&stream_path));
+
+            unsafe {
+                // Attempt to delete the stream - ignore errors as the stream may not exist
+                if DeleteFileW(PCWSTR(wide_path.as_ptr())).is_ok() {
+                    removed.push(stream_name.replace('\x05', ""));
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+
+    #[cfg(not(windows))]
+    fn clear_properties(&self, _path: &Path) -> CleanerResult<Vec<String>> {
+        // Properties clearing is Windows-specific
+        Ok(Vec::new())
+    }
+
+    /// Clears embedded document properties from Office Open XML files (.docx, .xlsx, .pptx, etc.).
+    /// These files are ZIP archives containing XML metadata in docProps/core.xml and docProps/app.xml.
+    /// This function removes the "Owner", "Computer", "Author", "Last Modified By", etc. fields
+    /// that appear in Windows File Properties → Details tab.
+    ///
+    /// Returns the archive size before and after the rewrite with the labels of the
+    /// fields that were populated, or `None`, leaving the file untouched, if it
+    /// carries none, and
+    /// [`CleanerError::UnsupportedFormat`] if the file isn't a valid ZIP archive.
+    pub(super) fn clear_office_xml_properties(&self, path: &Path) -> CleanerResult<Option<(SizeChange, Vec<String>)>> {
+        // Keep other instances from rewriting the same document concurrently
+        let _lock = self.lock_for_rewrite(path)?;
+        let _slot = self.archive_slots.acquire();
+
+        let file = File::open(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to open file", e))?;
+        let size_before = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+        let Ok(mut archive) = ZipArchive::new(BufReader::new(file)) else {
+            return Err(CleanerError::unsupported_format(path, "Office Open XML"));
+        };
+
+        let cleared: Vec<String> = self
+            .populated_office_properties(path, &mut archive)?
+            .into_iter()
+            .map(|property| property.field)
+            .collect();
+        if cleared.is_empty() {
+            return Ok(None);
+        }
+
+        // Entries are streamed into a temp file rather than buffered, so memory
+        // stays flat however large the document is; the original stays readable
+        // until the cleaned archive replaces it
+        let temp_path = self.rewrite_temp_path(path);
+        let written = File::create(&temp_path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to create temp file", e))
+            .and_then(|temp| self.rewrite_office_archive(path, &mut archive, BufWriter::new(temp)))
+            .and_then(|writer| {
+                writer.into_inner().map_err(|e| CleanerError::file_operation(path, "Failed to write temp file", e.into_error()))
+            })
+            .and_then(|temp| temp.metadata().map_err(|e| CleanerError::file_operation(path, "Failed to write temp file", e)));
+        drop(archive);
+
+        let size_after = match written.and_then(|metadata| self.replace_with_file(path, &temp_path).map(|()| metadata.len())) {
+            Ok(size) => size,
+            Err(e) => {
+                let _ = std::fs::remove_file(&temp_path);
+                return Err(e);
+            }
+        };
+
+        Ok(Some((SizeChange::new(size_before, size_after), cleared)))
+    }
+
+    /// Clears the docProps fields of an Office Open XML document held in memory,
+    /// as [`clean_file`](Self::clean_file) does for .docx, .xlsx and .pptx files.
+    ///
+    /// Fails with [`CleanerError::InvalidInput`] if the data isn't a ZIP archive.
+    pub fn clean_docx_bytes(&self, input: &[u8]) -> CleanerResult<Vec<u8>> {
+        let mut archive = ZipArchive::new(Cursor::new(input))
+            .map_err(|e| CleanerError::InvalidInput(format!("not an Office document: {}", e)))?;
+
+        Ok(self.rewrite_office_archive(Path::new(IN_MEMORY), &mut archive, Cursor::new(Vec::new()))?.into_inner())
+    }
+
+    /// Copies every entry of an Office archive into `output`, cleaning the property parts.
+    fn rewrite_office_archive<R: Read + Seek, W: Write + Seek>(
+        &self,
+        path: &Path,
+        archive: &mut ZipArchive<R>,
+        output: W,
+    ) -> CleanerResult<W> {
+        let mut zip_writer = ZipWriter::new(output);
+        // Process each file in the archive
+        for i in 0..archive.len() {
+            // In metadata-only mode every entry but the property parts is copied
+            // verbatim, keeping its compressed bytes, CRC and timestamps
+            let is_property_part = matches!(archive.name_for_index(i), Some("docProps/core.xml" | "docProps/app.xml"));
+            if self.options.metadata_only && !is_property_part {
+                let entry = archive.by_index_raw(i)
+                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to read archive entry: {}", e)))?;
+                zip_writer.raw_copy_file(entry)
+                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to write to archive: {}", e)))?;
+                continue;
+            }
+
+            let mut entry = archive.by_index(i)
+                .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to read archive entry: {}", e)))?;
+
+            let entry_name = entry.name().to_string();
+
+            // Keep each entry's original method (Stored vs Deflated) so strict readers
+            // and byte-level comparisons see the same structure; anything we can't write
+            // falls back to Deflated
+            let method = match entry.compression() {
+                zip::CompressionMethod::Stored => zip::CompressionMethod::Stored,
+                _ => zip::CompressionMethod::Deflated,
+            };
+            let options = SimpleFileOptions::default().compression_method(method);
+
+            // Handle docProps/core.xml - contains Author, Last Modified By, etc.
+            if entry_name == "docProps/core.xml" {
+                let mut raw = Vec::new();
+                entry.read_to_end(&mut raw)
+                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to read core.xml: {}", e)))?;
+
+                // Decode using the part's declared encoding so non-ASCII names are matched
+                let content = DecodedXml::decode(&raw, self.xml_fallback_encoding());
+                let cleaned_content = self.clean_core_xml(&content.text)
+                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to parse core.xml: {}", e)))?;
+
+                zip_writer.start_file(&entry_name, options)
+                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to write to archive: {}", e)))?;
+                zip_writer.write_all(&content.encode(&cleaned_content))
+                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to write content: {}", e)))?;
+            }
+            // Handle docProps/app.xml - contains Application, Company (Computer), etc.
+            else if entry_name == "docProps/app.xml" {
+                let mut raw = Vec::new();
+                entry.read_to_end(&mut raw)
+                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to read app.xml: {}", e)))?;
+
+                // Decode using the part's declared encoding so non-ASCII names are matched
+                let content = DecodedXml::decode(&raw, self.xml_fallback_encoding());
+                let cleaned_content = self.clean_app_xml(&content.text)
+                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to parse app.xml: {}", e)))?;
+
+                zip_writer.start_file(&entry_name, options)
+                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to write to archive: {}", e)))?;
+                zip_writer.write_all(&content.encode(&cleaned_content))
+                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to write content: {}", e)))?;
+            }
+            // Copy all other files unchanged, through io::copy's fixed-size buffer
+            else {
+                zip_writer.start_file(&entry_name, options)
+                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to write to archive: {}", e)))?;
+                std::io::copy(&mut entry, &mut zip_writer)
+                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to copy entry: {}", e)))?;
+            }
+        }
+
+        // Finalize the ZIP
+        zip_writer.finish()
+            .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to finalize archive: {}", e)))
+    }
+
+    /// Empties the creator, publisher, date and rights fields of an EPUB's package
+    /// document and drops its calibre entries, copying every other entry byte for byte.
+    ///
+    /// Returns the labels of what was removed, or `None`, leaving the file untouched,
+    /// if it has nothing to remove, and [`CleanerError::UnsupportedFormat`] if
+    /// it isn't a valid EPUB.
+    pub(super) fn clean_epub(&self, path: &Path) -> CleanerResult<Option<Vec<String>>> {
+        let _lock = self.lock_for_rewrite(path)?;
+        let _slot = self.archive_slots.acquire();
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read EPUB", e))?;
+        let Ok(mut archive) = ZipArchive::new(Cursor::new(data.as_slice())) else {
+            return Err(CleanerError::unsupported_format(path, "EPUB"));
+        };
+        let Some((package_name, package)) = self.read_epub_package(path, &mut archive)? else {
+            return Err(CleanerError::unsupported_format(path, "EPUB"));
+        };
+        if epub::package_metadata(&package.text).is_none() {
+            return Err(CleanerError::unsupported_format(path, "EPUB"));
+        }
+        let Some((cleaned, removed)) = epub::strip_package_metadata(&package.text) else {
+            return Ok(None);
+        };
+
+        let archive_error = |e: &dyn std::fmt::Display| CleanerError::cleaning_failed(path, format!("Failed to rewrite EPUB: {}", e));
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for i in 0..archive.len() {
+            // The mimetype entry must stay first and stored, so everything else is copied raw
+            if archive.name_for_index(i) != Some(package_name.as_str()) {
+                let entry = archive.by_index_raw(i).map_err(|e| archive_error(&e))?;
+                writer.raw_copy_file(entry).map_err(|e| archive_error(&e))?;
+                continue;
+            }
+
+            let entry = archive.by_index_raw(i).map_err(|e| archive_error(&e))?;
+            let method = match entry.compression() {
+                zip::CompressionMethod::Stored => zip::CompressionMethod::Stored,
+                _ => zip::CompressionMethod::Deflated,
+            };
+            drop(entry);
+            writer.start_file(&package_name, SimpleFileOptions::default().compression_method(method))
+                .map_err(|e| archive_error(&e))?;
+            writer.write_all(&package.encode(&cleaned)).map_err(|e| archive_error(&e))?;
+        }
+        let output = writer.finish().map_err(|e| archive_error(&e))?.into_inner();
+
+        self.replace_contents(path, &output)?;
+        Ok(Some(removed))
+    }
+
+    /// Finds and decodes the package document of an EPUB via `META-INF/container.xml`.
+    ///
+    /// Returns `None` if the archive doesn't declare the EPUB mimetype or the
+    /// package document can't be found.
+    fn read_epub_package<R: Read + Seek>(
+        &self,
+        path: &Path,
+        archive: &mut ZipArchive<R>,
+    ) -> CleanerResult<Option<(String, DecodedXml)>> {
+        let mut read_entry = |name: &str| -> CleanerResult<Option<Vec<u8>>> {
+            let Ok(mut entry) = archive.by_name(name) else {
+                return Ok(None);
+            };
+            let mut raw = Vec::new();
+            entry.read_to_end(&mut raw)
+                .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to read {}: {}", name, e)))?;
+            Ok(Some(raw))
+        };
+
+        if read_entry("mimetype")?.is_none_or(|mimetype| mimetype.trim_ascii() != b"application/epub+zip") {
+            return Ok(None);
+        }
+        let Some(container) = read_entry("META-INF/container.xml")? else {
+            return Ok(None);
+        };
+        let container = DecodedXml::decode(&container, self.xml_fallback_encoding());
+        let Some(package_name) = epub::package_path(&container.text) else {
+            return Ok(None);
+        };
+        let Some(package) = read_entry(&package_name)? else {
+            return Ok(None);
+        };
+
+        Ok(Some((package_name, DecodedXml::decode(&package, self.xml_fallback_encoding()))))
+    }
+
+    /// Lists the identity fields and calibre entries an EPUB carries without modifying it.
+    /// Returns `None` if the file isn't an EPUB or its package document is invalid.
+    pub(super) fn inspect_epub(&self, path: &Path, signature: Option<Signature>) -> CleanerResult<Option<Vec<String>>> {
+        if !EpubCleaner.handles_path(path, signature) {
+            return Ok(None);
+        }
+
+        let file = File::open(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to open file", e))?;
+        let Ok(mut archive) = ZipArchive::new(file) else {
+            return Ok(None);
+        };
+
+        Ok(self
+            .read_epub_package(path, &mut archive)?
+            .and_then(|(_, package)| epub::package_metadata(&package.text)))
+    }
+
+    /// Strips identifying headers from `.eml` and `.msg` files.
+    /// Returns the number of headers removed, or `None` if the file isn't an email.
+    pub(super) fn clean_email(&self, path: &Path) -> CleanerResult<Option<usize>> {
+        let extension = path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        let classes = &self.options.email_header_classes;
+
+        match extension.as_deref() {
+            Some("eml") => {
+                let _lock = self.lock_for_rewrite(path)?;
+
+                let message = std::fs::read(path)
+                    .map_err(|e| CleanerError::file_operation(path, "Failed to read email", e))?;
+
+                let (cleaned, removed) = email::strip_headers(&message, classes);
+                if removed > 0 {
+                    self.replace_contents(path, &cleaned)?;
+                }
+
+                Ok(Some(removed))
+            }
+            Some("msg") => {
+                let _lock = self.lock_for_rewrite(path)?;
+
+                let message = std::fs::read(path)
+                    .map_err(|e| CleanerError::file_operation(path, "Failed to read message", e))?;
+
+                // Edited in memory and written back whole, so an interruption can't leave half a message
+                let stripped = email::strip_msg_headers(message, classes)
+                    .map_err(|e| CleanerError::file_operation(path, "Failed to rewrite message", e))?;
+                match stripped {
+                    Some((cleaned, removed)) => {
+                        if removed > 0 {
+                            self.replace_contents(path, &cleaned)?;
+                        }
+                        Ok(Some(removed))
+                    }
+                    None => Ok(None),
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Removes EXIF data from JPEG and TIFF images, keeping the image data.
+    /// Returns `Ok(false)` if the image carries no EXIF, and
+    /// [`CleanerError::UnsupportedFormat`] if its contents aren't such an image.
+    pub(super) fn strip_exif(&self, path: &Path) -> CleanerResult<bool> {
+        let _lock = self.lock_for_rewrite(path)?;
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read image", e))?;
+        let Some(kind) = ImageKind::detect(&data) else {
+            return Err(CleanerError::unsupported_format(path, "JPEG or TIFF"));
+        };
+
+        match strip_exif_data(kind, data) {
+            Some(cleaned) => {
+                self.replace_contents(path, &cleaned)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Strips EXIF data from a JPEG or TIFF image held in memory, returning it
+    /// unchanged if it has none.
+    ///
+    /// The format is detected from the data. Fails with [`CleanerError::InvalidInput`]
+    /// if it is neither a JPEG nor a TIFF image.
+    pub fn clean_exif_bytes(&self, input: &[u8]) -> CleanerResult<Vec<u8>> {
+        let kind = ImageKind::detect(input)
+            .ok_or_else(|| CleanerError::InvalidInput("not a JPEG or TIFF image".to_string()))?;
+
+        Ok(strip_exif_data(kind, input.to_vec()).unwrap_or_else(|| input.to_vec()))
+    }
+
+    /// Removes `tEXt`, `zTXt`, `iTXt` and `tIME` chunks from PNG images.
+    /// Returns `Ok(false)`, leaving the file untouched, if it has none, and
+    /// [`CleanerError::UnsupportedFormat`] if it isn't a valid PNG.
+    pub(super) fn clean_png(&self, path: &Path) -> CleanerResult<bool> {
+        let _lock = self.lock_for_rewrite(path)?;
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read PNG", e))?;
+        if image::png_metadata_chunks(&data).is_none() {
+            return Err(CleanerError::unsupported_format(path, "PNG"));
+        }
+
+        match image::strip_png_metadata(&data) {
+            Some(cleaned) => {
+                self.replace_contents(path, &cleaned)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Removes text and time chunks from a PNG image held in memory, returning it
+    /// unchanged if it has none.
+    ///
+    /// Fails with [`CleanerError::InvalidInput`] if the data isn't a PNG image.
+    pub fn clean_png_bytes(&self, input: &[u8]) -> CleanerResult<Vec<u8>> {
+        if image::png_metadata_chunks(input).is_none() {
+            return Err(CleanerError::InvalidInput("not a PNG image".to_string()));
+        }
+
+        Ok(image::strip_png_metadata(input).unwrap_or_else(|| input.to_vec()))
+    }
+
+    /// Removes comment and application extension blocks from GIF images, keeping
+    /// the animation loop count. Returns `Ok(false)`, leaving the file untouched,
+    /// if it has none, and [`CleanerError::UnsupportedFormat`] if it isn't a valid GIF.
+    pub(super) fn clean_gif(&self, path: &Path) -> CleanerResult<bool> {
+        let _lock = self.lock_for_rewrite(path)?;
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read GIF", e))?;
+        if image::gif_metadata(&data).is_none() {
+            return Err(CleanerError::unsupported_format(path, "GIF"));
+        }
+
+        match image::strip_gif_metadata(&data) {
+            Some(cleaned) => {
+                self.replace_contents(path, &cleaned)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Removes the GPS IFD and blanks the date tags of camera RAW files, keeping the
+    /// maker notes and image data. Returns `Ok(false)`, leaving the file untouched,
+    /// if there is nothing to remove, and [`CleanerError::UnsupportedFormat`] if
+    /// its TIFF structure isn't as expected.
+    pub(super) fn clean_raw(&self, path: &Path) -> CleanerResult<bool> {
+        let _lock = self.lock_for_rewrite(path)?;
+
+        let mut data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read RAW file", e))?;
+        if image::raw_metadata(&data).is_none() {
+            return Err(CleanerError::unsupported_format(path, "camera RAW"));
+        }
+
+        if !image::strip_raw_metadata(&mut data) {
+            return Ok(false);
+        }
+
+        self.replace_contents(path, &data)?;
+        Ok(true)
+    }
+
+    /// Returns true if the file is a camera RAW image, whose cleaning is experimental.
+    pub fn is_camera_raw(&self, path: &Path) -> bool {
+        RawCleaner.handles_path(path, Signature::of_file(path))
+    }
+
+    /// Removes the `<metadata>` element, Inkscape/Sodipodi editor data and comments from SVGs.
+    /// Returns `Ok(false)`, leaving the file untouched, if it has none, and
+    /// [`CleanerError::UnsupportedFormat`] if it isn't valid XML.
+    pub(super) fn clean_svg(&self, path: &Path) -> CleanerResult<bool> {
+        let _lock = self.lock_for_rewrite(path)?;
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read SVG", e))?;
+
+        let content = DecodedXml::decode(&data, self.xml_fallback_encoding());
+        if svg::svg_metadata(&content.text).is_none() {
+            return Err(CleanerError::unsupported_format(path, "SVG"));
+        }
+
+        match svg::strip_svg_metadata(&content.text) {
+            Some(cleaned) => {
+                self.replace_contents(path, &content.encode(&cleaned))?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Removes `dc:creator` and the Photoshop, IPTC and media-management properties
+    /// from XMP sidecars, keeping the develop settings.
+    /// Returns `Ok(false)`, leaving the file untouched, if it has none, and
+    /// [`CleanerError::UnsupportedFormat`] if it isn't valid XMP.
+    pub(super) fn clean_xmp(&self, path: &Path) -> CleanerResult<bool> {
+        let _lock = self.lock_for_rewrite(path)?;
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read XMP sidecar", e))?;
+
+        let content = DecodedXml::decode(&data, self.xml_fallback_encoding());
+        if xmp::xmp_metadata(&content.text).is_none() {
+            return Err(CleanerError::unsupported_format(path, "XMP"));
+        }
+
+        match xmp::strip_xmp_metadata(&content.text) {
+            Some(cleaned) => {
+                self.replace_contents(path, &content.encode(&cleaned))?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Returns the XMP sidecars next to an image that still carry identity
+    /// properties: `photo.xmp` (Lightroom, Bridge) or `photo.jpg.xmp` (darktable).
+    ///
+    /// Empty unless properties are cleared and `image` is a format with sidecars
+    /// (JPEG, TIFF, PNG, GIF, HEIF or camera RAW).
+    pub fn xmp_sidecars(&self, image: &Path) -> Vec<PathBuf> {
+        let signature = Signature::of_file(image);
+        let is_image = [&ExifCleaner as &dyn FormatCleaner, &PngCleaner, &GifCleaner, &HeifCleaner, &RawCleaner]
+            .iter()
+            .any(|format| format.handles_path(image, signature));
+        if !self.options.clear_properties || !is_image {
+            return Vec::new();
+        }
+
+        let mut appended = image.as_os_str().to_os_string();
+        appended.push(".xmp");
+
+        [image.with_extension("xmp"), PathBuf::from(appended)]
+            .into_iter()
+            .filter(|sidecar| sidecar.is_file())
+            .filter(|sidecar| self.inspect_xmp(sidecar, Signature::of_file(sidecar)).is_ok_and(|found| found.is_some_and(|found| !found.is_empty())))
+            .collect()
+    }
+
+    /// Zeroes the EXIF and XMP items of HEIC/HEIF images, keeping the image items intact.
+    /// Returns `Ok(false)`, leaving the file untouched, if it has no such items,
+    /// and [`CleanerError::UnsupportedFormat`] if the container isn't recognized.
+    pub(super) fn clean_heif(&self, path: &Path) -> CleanerResult<bool> {
+        let _lock = self.lock_for_rewrite(path)?;
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read HEIF image", e))?;
+        if heif::heif_metadata(&data).is_none() {
+            return Err(CleanerError::unsupported_format(path, "HEIF"));
+        }
+
+        match heif::strip_heif_metadata(&data) {
+            Some(cleaned) => {
+                self.replace_contents(path, &cleaned)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Removes ID3v2 tags, and the ID3v1 trailer of MP3s, from audio files.
+    /// Returns `Ok(false)`, leaving the file untouched, if it carries no ID3 tag,
+    /// and [`CleanerError::UnsupportedFormat`] if it isn't a parseable MP3, FLAC or M4A file.
+    pub(super) fn clean_audio(&self, path: &Path) -> CleanerResult<bool> {
+        let _lock = self.lock_for_rewrite(path)?;
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read audio file", e))?;
+        let Some(kind) = audio_kind(path, Signature::detect(&data)).filter(|&kind| audio::id3_tags(&data, kind).is_some()) else {
+            return Err(CleanerError::unsupported_format(path, "MP3, FLAC or M4A"));
+        };
+
+        match audio::strip_id3(&data, kind) {
+            Some(cleaned) => {
+                self.replace_contents(path, &cleaned)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Removes `LIST`/`INFO` and broadcast-wave `bext` chunks from WAV files,
+    /// keeping `fmt ` and `data`. Returns `Ok(false)`, leaving the file untouched,
+    /// if it has neither chunk, and [`CleanerError::UnsupportedFormat`] if it
+    /// isn't a valid RIFF/WAVE file.
+    pub(super) fn clean_wav(&self, path: &Path) -> CleanerResult<bool> {
+        let _lock = self.lock_for_rewrite(path)?;
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read WAV file", e))?;
+        if audio::wav_metadata(&data).is_none() {
+            return Err(CleanerError::unsupported_format(path, "WAV"));
+        }
+
+        match audio::strip_wav_metadata(&data) {
+            Some(cleaned) => {
+                self.replace_contents(path, &cleaned)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Removes the document Info entries (author, producer, dates) and XMP metadata from PDFs.
+    /// Returns the labels of the removed fields, or `Ok(None)`, leaving the file untouched,
+    /// if it has nothing to remove. Invalid and encrypted PDFs fail with
+    /// [`CleanerError::UnsupportedFormat`].
+    pub(super) fn clean_pdf(&self, path: &Path) -> CleanerResult<Option<Vec<String>>> {
+        let _lock = self.lock_for_rewrite(path)?;
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read PDF", e))?;
+        if pdf::pdf_metadata_fields(&data).is_none() {
+            return Err(CleanerError::unsupported_format(path, "unencrypted PDF"));
+        }
+
+        match pdf::strip_pdf_metadata(&data) {
+            Some(cleaned) => {
+                let fields = pdf::pdf_metadata_fields(&data).unwrap_or_default();
+                self.replace_contents(path, &cleaned)?;
+                Ok(Some(fields))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Drops the tracker block (machine name and MAC address) from Windows shortcuts
+    /// and zeroes the source volume's serial number and label, keeping the target
+    /// path and ID list so the link still resolves. Returns the labels of what was
+    /// removed, or `Ok(None)`, leaving the file untouched, if it has nothing to
+    /// remove, and [`CleanerError::UnsupportedFormat`] if it isn't a valid Shell Link.
+    pub(super) fn clean_lnk(&self, path: &Path) -> CleanerResult<Option<Vec<String>>> {
+        let _lock = self.lock_for_rewrite(path)?;
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read shortcut", e))?;
+        if lnk::lnk_metadata(&data).is_none() {
+            return Err(CleanerError::unsupported_format(path, "Shell Link"));
+        }
+
+        match lnk::strip_lnk_metadata(&data) {
+            Some((cleaned, removed)) => {
+                self.replace_contents(path, &cleaned)?;
+                Ok(Some(removed))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Cleans the entries of a ZIP archive with the format cleaners, opening nested
+    /// archives up to `max_archive_depth` levels deep, and rewrites it.
+    ///
+    /// Returns the cleaned entries prefixed with the archive name, or `Ok(None)`,
+    /// leaving the file untouched, if no entry changed, and
+    /// [`CleanerError::UnsupportedFormat`] if it isn't a valid ZIP.
+    pub(super) fn clean_zip_entries(&self, path: &Path) -> CleanerResult<Option<Vec<String>>> {
+        let _lock = self.lock_for_rewrite(path)?;
+        let _slot = self.archive_slots.acquire();
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read archive", e))?;
+        if ZipArchive::new(Cursor::new(data.as_slice())).is_err() {
+            return Err(CleanerError::unsupported_format(path, "ZIP"));
+        }
+
+        let scratch = ScratchDir::create(path)?;
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let mut budget = MAX_ARCHIVE_EXPANDED_BYTES;
+
+        match self.clean_archive_bytes(path, &data, &name, 1, &scratch, &mut budget)? {
+            Some((cleaned, entries)) => {
+                self.replace_contents(path, &cleaned)?;
+                Ok(Some(entries))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Cleans the entries of an in-memory ZIP archive, labelling them under `label`.
+    ///
+    /// Entries no format cleaner handles are copied as they are, without being
+    /// decompressed. The others are cleaned through a file in `scratch`, and
+    /// `budget` caps how many bytes they may decompress to in total.
+    fn clean_archive_bytes(
+        &self,
+        path: &Path,
+        data: &[u8],
+        label: &str,
+        depth: usize,
+        scratch: &ScratchDir,
+        budget: &mut u64,
+    ) -> CleanerResult<Option<(Vec<u8>, Vec<String>)>> {
+        let Ok(mut archive) = ZipArchive::new(Cursor::new(data)) else {
+            return Ok(None);
+        };
+        if archive.len() > MAX_ARCHIVE_ENTRIES {
+            return Err(CleanerError::cleaning_failed(
+                path,
+                format!("{} has {} entries, more than the {} allowed", label, archive.len(), MAX_ARCHIVE_ENTRIES),
+            ));
+        }
+
+        let archive_error = |e: &dyn std::fmt::Display| CleanerError::cleaning_failed(path, format!("{}: {}", label, e));
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let mut cleaned_entries = Vec::new();
+
+        for i in 0..archive.len() {
+            let name = archive.name_for_index(i).unwrap_or_default().to_string();
+            let extension = Path::new(&name)
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_lowercase())
+                .unwrap_or_default();
+            let nested = ZipCleaner.handles_extension(&extension) && depth < self.options.max_archive_depth;
+            // Entries are picked by name; once extracted, their contents decide the format
+            let candidate = FORMAT_CLEANERS
+                .iter()
+                .any(|format| format.handles_extension(&extension) && format.name() != ZipCleaner.name());
+
+            let mut replacement = None;
+            if !name.ends_with('/') && (nested || candidate) {
+                let mut entry = archive.by_index(i).map_err(|e| archive_error(&e))?;
+                let mut contents = Vec::new();
+                (&mut entry).take(*budget + 1).read_to_end(&mut contents).map_err(|e| archive_error(&e))?;
+                if contents.len() as u64 > *budget {
+                    return Err(archive_error(&format!(
+                        "entries expand to more than {} bytes, possibly a zip bomb",
+                        MAX_ARCHIVE_EXPANDED_BYTES
+                    )));
+                }
+                *budget -= contents.len() as u64;
+
+                let method = match entry.compression() {
+                    zip::CompressionMethod::Stored => zip::CompressionMethod::Stored,
+                    _ => zip::CompressionMethod::Deflated,
+                };
+                let entry_label = format!("{}/{}", label, name);
+
+                if nested {
+                    if let Some((inner, entries)) =
+                        self.clean_archive_bytes(path, &contents, &entry_label, depth + 1, scratch, budget)?
+                    {
+                        cleaned_entries.extend(entries);
+                        replacement = Some((inner, method));
+                    }
+                } else {
+                    let file = scratch.path.join(format!("{}.{}", i, extension));
+                    std::fs::write(&file, &contents)
+                        .map_err(|e| CleanerError::file_operation(path, "Failed to write archive entry", e))?;
+
+                    let mut ran = Vec::new();
+                    let signature = Signature::detect(&contents);
+                    let formats = FORMAT_CLEANERS
+                        .iter()
+                        .filter(|format| format.name() != ZipCleaner.name() && format.handles_path(&file, signature));
+                    for format in formats {
+                        // An entry that only looks like its format is copied as it is
+                        match format.clean(self, &file) {
+                            Ok(CleanOutcome::Cleaned { .. }) => ran.push(format.name()),
+                            Ok(_) | Err(CleanerError::UnsupportedFormat { .. }) => {}
+                            Err(e) => return Err(archive_error(&e)),
+                        }
+                    }
+
+                    if !ran.is_empty() {
+                        let cleaned = std::fs::read(&file)
+                            .map_err(|e| CleanerError::file_operation(path, "Failed to read archive entry", e))?;
+                        cleaned_entries.push(format!("{} ({})", entry_label, ran.join(", ")));
+                        replacement = Some((cleaned, method));
+                    }
+                }
+            }
+
+            match replacement {
+                Some((contents, method)) => {
+                    writer.start_file(&name, SimpleFileOptions::default().compression_method(method))
+                        .map_err(|e| archive_error(&e))?;
+                    writer.write_all(&contents).map_err(|e| archive_error(&e))?;
+                }
+                None => {
+                    let entry = archive.by_index_raw(i).map_err(|e| archive_error(&e))?;
+                    writer.raw_copy_file(entry).map_err(|e| archive_error(&e))?;
+                }
+            }
+        }
+
+        if cleaned_entries.is_empty() {
+            return Ok(None);
+        }
+
+        let output = writer.finish().map_err(|e| archive_error(&e))?;
+        Ok(Some((output.into_inner(), cleaned_entries)))
+    }
+
+    /// Locks `path` for a rewrite: through a sidecar, or, in place, through the
+    /// file itself, so no file is created next to it.
+    fn lock_for_rewrite(&self, path: &Path) -> CleanerResult<RewriteLock> {
+        if self.options.in_place {
+            RewriteLock::acquire_in_place(path)
+        } else {
+            RewriteLock::acquire(path)
+        }
+    }
+
+    /// Returns the temp file a rewrite of `path` is streamed into: next to it, to
+    /// be renamed over it, or, in place, where it is only copied from, under the
+    /// system temp directory, which needs no write access to the file's directory.
+    fn rewrite_temp_path(&self, path: &Path) -> PathBuf {
+        let temp_path = unique_temp_path(path);
+        if !self.options.in_place {
+            return temp_path;
+        }
+        std::env::temp_dir().join(temp_path.file_name().unwrap_or_default())
+    }
+
+    /// Writes cleaned contents back to `path`, in place or via a temp file per the options.
+    fn replace_contents(&self, path: &Path, contents: &[u8]) -> CleanerResult<()> {
+        self.commit(path, || {
+            if self.options.in_place {
+                overwrite_in_place(path, contents)
+            } else {
+                write_replacement(path, contents)
+            }
+        })
+    }
+
+    /// Like [`replace_contents`](Self::replace_contents) for contents already
+    /// written to `temp_path`, which is moved over `path` or, in place, copied
+    /// into it and removed.
+    fn replace_with_file(&self, path: &Path, temp_path: &Path) -> CleanerResult<()> {
+        self.commit(path, || {
+            if self.options.in_place {
+                overwrite_in_place_from(path, temp_path)?;
+                let _ = std::fs::remove_file(temp_path);
+                Ok(())
+            } else {
+                replace_file(temp_path, path).map_err(|e| CleanerError::file_operation(path, "Failed to rename temp file", e))
+            }
+        })
+    }
+
+    /// Encoding assumed for XML parts that carry no BOM or declaration.
+    fn xml_fallback_encoding(&self) -> &'static encoding_rs::Encoding {
+        self.options.xml_fallback_encoding.unwrap_or(encoding_rs::UTF_8)
+    }
+
+    /// Cleans the docProps/core.xml file, removing author, last modified by, etc.
+    fn clean_core_xml(&self, content: &str) -> Result<String, quick_xml::Error> {
+        clear_xml_fields(content, CORE_XML_FIELDS)
+    }
+
+    /// Cleans the docProps/app.xml file, removing company (computer), manager, etc.
+    fn clean_app_xml(&self, content: &str) -> Result<String, quick_xml::Error> {
+        clear_xml_fields(content, APP_XML_FIELDS)
+    }
+
+    /// Reads an Office document's docProps parts without modifying the file and returns
+    /// the labels of identifying fields that are populated, or `None` if it isn't an Office document.
+    pub(super) fn inspect_office_properties(&self, path: &Path, signature: Option<Signature>) -> CleanerResult<Option<Vec<String>>> {
+        Ok(self
+            .office_properties(path, signature)?
+            .map(|properties| properties.into_iter().map(|property| property.field).collect()))
+    }
+
+    /// Reads the populated docProps fields of an Office document with their values.
+    /// Returns `None` if the file isn't an Office document.
+    pub fn read_office_properties(&self, path: &Path) -> CleanerResult<Option<Vec<OfficeProperty>>> {
+        self.office_properties(path, Signature::of_file(path))
+    }
+
+    /// Reads the populated docProps fields of a file already sniffed as `signature`.
+    fn office_properties(&self, path: &Path, signature: Option<Signature>) -> CleanerResult<Option<Vec<OfficeProperty>>> {
+        if !OfficeXmlCleaner.handles_path(path, signature) {
+            return Ok(None);
+        }
+
+        let file = File::open(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to open file", e))?;
+
+        let mut archive = match ZipArchive::new(file) {
+            Ok(a) => a,
+            Err(_) => return Ok(None),
+        };
+
+        self.populated_office_properties(path, &mut archive).map(Some)
+    }
+
+    /// Returns true if an Office document carries a digital signature, which
+    /// any rewrite of the package invalidates.
+    ///
+    /// Office keeps signatures under `_xmlsignatures/`, linked from the package
+    /// relationships by a `digital-signature` relationship; either one counts.
+    pub fn is_signed_office_document(&self, path: &Path) -> CleanerResult<bool> {
+        let file = File::open(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to open file", e))?;
+        let Ok(mut archive) = ZipArchive::new(BufReader::new(file)) else {
+            return Ok(false);
+        };
+
+        if archive.file_names().any(|name| name.starts_with("_xmlsignatures/")) {
+            return Ok(true);
+        }
+
+        let mut rels = String::new();
+        let has_signature_relationship = archive
+            .by_name("_rels/.rels")
+            .is_ok_and(|mut entry| entry.read_to_string(&mut rels).is_ok() && rels.contains("/digital-signature/"));
+        Ok(has_signature_relationship)
+    }
+
+    /// Reads the populated identifying fields from an open Office archive's docProps parts.
+    fn populated_office_properties<R: Read + Seek>(
+        &self,
+        path: &Path,
+        archive: &mut ZipArchive<R>,
+    ) -> CleanerResult<Vec<OfficeProperty>> {
+        let mut populated = Vec::new();
+        for (part, fields) in [("docProps/core.xml", CORE_XML_FIELDS), ("docProps/app.xml", APP_XML_FIELDS)] {
+            let Ok(mut entry) = archive.by_name(part) else {
+                continue;
+            };
+
+            let mut raw = Vec::new();
+            entry.read_to_end(&mut raw)
+                .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to read {}: {}", part, e)))?;
+
+            let content = DecodedXml::decode(&raw, self.xml_fallback_encoding());
+            let values = xml_field_values(&content.text, fields)
+                .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to parse {}: {}", part, e)))?;
+            populated.extend(values.into_iter().map(|(field, value)| OfficeProperty { field, value }));
+        }
+
+        Ok(populated)
+    }
+
+    /// Counts the identifying headers an email would lose, without modifying it.
+    /// Returns `None` if the file isn't an email.
+    pub(super) fn inspect_email(&self, path: &Path) -> CleanerResult<Option<usize>> {
+        let extension = path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        let classes = &self.options.email_header_classes;
+
+        match extension.as_deref() {
+            Some("eml") => {
+                let message = std::fs::read(path)
+                    .map_err(|e| CleanerError::file_operation(path, "Failed to read email", e))?;
+                Ok(Some(email::strip_headers(&message, classes).1))
+            }
+            Some("msg") => email::count_msg_headers(path, classes)
+                .map_err(|e| CleanerError::file_operation(path, "Failed to read message", e)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Checks an image for EXIF data without modifying it.
+    /// Returns `None` if the file isn't a JPEG or TIFF image.
+    pub(super) fn inspect_exif(&self, path: &Path, signature: Option<Signature>) -> CleanerResult<Option<bool>> {
+        let Some(kind) = image_kind(path, signature) else {
+            return Ok(None);
+        };
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read image", e))?;
+
+        Ok(Some(match kind {
+            ImageKind::Jpeg => image::jpeg_has_exif(&data),
+            ImageKind::Tiff => image::tiff_has_exif(&data),
+        }))
+    }
+
+    /// Lists the text and time chunks a PNG carries without modifying it.
+    /// Returns `None` if the file isn't a PNG or can't be parsed.
+    pub(super) fn inspect_png(&self, path: &Path, signature: Option<Signature>) -> CleanerResult<Option<Vec<String>>> {
+        if !PngCleaner.handles_path(path, signature) {
+            return Ok(None);
+        }
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read PNG", e))?;
+
+        Ok(image::png_metadata_chunks(&data))
+    }
+
+    /// Lists the comment and application extensions a GIF carries without modifying it.
+    /// Returns `None` if the file isn't a GIF or can't be parsed.
+    pub(super) fn inspect_gif(&self, path: &Path, signature: Option<Signature>) -> CleanerResult<Option<Vec<String>>> {
+        if !GifCleaner.handles_path(path, signature) {
+            return Ok(None);
+        }
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read GIF", e))?;
+
+        Ok(image::gif_metadata(&data))
+    }
+
+    /// Lists the GPS IFD and date tags a camera RAW file carries without modifying it.
+    /// Returns `None` if the file isn't a RAW file or its TIFF structure is unexpected.
+    pub(super) fn inspect_raw(&self, path: &Path, signature: Option<Signature>) -> CleanerResult<Option<Vec<String>>> {
+        if !RawCleaner.handles_path(path, signature) {
+            return Ok(None);
+        }
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read RAW file", e))?;
+
+        Ok(image::raw_metadata(&data))
+    }
+
+    /// Lists the metadata an SVG carries without modifying it.
+    /// Returns `None` if the file isn't an SVG or isn't valid XML.
+    pub(super) fn inspect_svg(&self, path: &Path, signature: Option<Signature>) -> CleanerResult<Option<Vec<String>>> {
+        if !SvgCleaner.handles_path(path, signature) {
+            return Ok(None);
+        }
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read SVG", e))?;
+
+        Ok(svg::svg_metadata(&DecodedXml::decode(&data, self.xml_fallback_encoding()).text))
+    }
+
+    /// Lists the identity properties an XMP sidecar carries without modifying it.
+    /// Returns `None` if the file isn't an XMP sidecar or isn't valid XMP.
+    pub(super) fn inspect_xmp(&self, path: &Path, signature: Option<Signature>) -> CleanerResult<Option<Vec<String>>> {
+        if !XmpCleaner.handles_path(path, signature) {
+            return Ok(None);
+        }
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read XMP sidecar", e))?;
+
+        Ok(xmp::xmp_metadata(&DecodedXml::decode(&data, self.xml_fallback_encoding()).text))
+    }
+
+    /// Lists the EXIF and XMP items a HEIF image carries without modifying it.
+    /// Returns `None` if the file isn't a HEIF image or can't be parsed.
+    pub(super) fn inspect_heif(&self, path: &Path, signature: Option<Signature>) -> CleanerResult<Option<Vec<String>>> {
+        if !HeifCleaner.handles_path(path, signature) {
+            return Ok(None);
+        }
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read HEIF image", e))?;
+
+        Ok(heif::heif_metadata(&data))
+    }
+
+    /// Lists the ID3 tags an audio file carries without modifying it.
+    /// Returns `None` if the file isn't a supported audio format or can't be parsed.
+    pub(super) fn inspect_audio(&self, path: &Path, signature: Option<Signature>) -> CleanerResult<Option<Vec<String>>> {
+        let Some(kind) = audio_kind(path, signature) else {
+            return Ok(None);
+        };
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read audio file", e))?;
+
+        Ok(audio::id3_tags(&data, kind))
+    }
+
+    /// Lists the INFO and bext chunks a WAV file carries without modifying it.
+    /// Returns `None` if the file isn't a WAV or can't be parsed.
+    pub(super) fn inspect_wav(&self, path: &Path, signature: Option<Signature>) -> CleanerResult<Option<Vec<String>>> {
+        if !WavCleaner.handles_path(path, signature) {
+            return Ok(None);
+        }
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read WAV file", e))?;
+
+        Ok(audio::wav_metadata(&data))
+    }
+
+    /// Lists the metadata a PDF carries without modifying it.
+    /// Returns `None` if the file isn't a PDF, can't be parsed, or is encrypted.
+    pub(super) fn inspect_pdf(&self, path: &Path, signature: Option<Signature>) -> CleanerResult<Option<Vec<String>>> {
+        if !PdfCleaner.handles_path(path, signature) {
+            return Ok(None);
+        }
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read PDF", e))?;
+
+        Ok(pdf::pdf_metadata_fields(&data))
+    }
+
+    /// Lists the tracker block and volume fields a shortcut carries without modifying it.
+    /// Returns `None` if the file isn't a shortcut or isn't a valid Shell Link.
+    pub(super) fn inspect_lnk(&self, path: &Path, signature: Option<Signature>) -> CleanerResult<Option<Vec<String>>> {
+        if !LnkCleaner.handles_path(path, signature) {
+            return Ok(None);
+        }
+
+        let data = std::fs::read(path)
+            .map_err(|e| CleanerError::file_operation(path, "Failed to read shortcut", e))?;
+
+        Ok(lnk::lnk_metadata(&data))
+    }
+
+    /// Returns true if the file's modification (and, on Windows, creation) time
+    /// already matches the configured target: within two seconds of it, or
+    /// anywhere in the range of a random target.
+    fn has_target_timestamps(&self, path: &Path) -> bool {
+        // `Now` moves on every run, so it can never already be reached
+        if self.options.timestamp_target == TimestampTarget::Now {
+            return false;
+        }
+        let target = self.options.timestamp_target;
+        if target == TimestampTarget::Unchanged {
+            return true;
+        }
+
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return false;
+        };
+
+        let modified_at_target = metadata.modified().is_ok_and(|t| target.matches(path, t));
+        // Only Windows lets us set the creation time, so elsewhere it can't count against the file
+        let created_at_target = !cfg!(windows) || metadata.created().map(|t| target.matches(path, t)).unwrap_or(true);
+
+        modified_at_target && created_at_target
+    }
+
+    /// Reads the `Zone.Identifier` stream (download origin) of a file, if present.
+    #[cfg(windows)]
+    fn read_zone_identifier(&self, path: &Path) -> Option<String> {
+        std::fs::read_to_string(format!("{}:Zone.Identifier", path.display())).ok()
+    }
+
+    #[cfg(not(windows))]
+    fn read_zone_identifier(&self, _path: &Path) -> Option<String> {
+        None
+    }
+
+    /// Scores how likely a file is to carry sensitive metadata, using only read-only checks.
+    ///
+    /// Identifying Office and PDF fields (author, company) weigh 3, descriptive ones 1;
+    /// a download origin weighs 2, or 5 when it points at personal cloud storage;
+    /// email routing/client headers and image EXIF data weigh 3; any other
+    /// alternate stream weighs 1.
+    pub fn sensitivity(&self, path: &Path) -> SensitivityScore {
+        let mut score = SensitivityScore::default();
+        let signature = Signature::of_file(path);
+
+        if let Ok(Some(fields)) = self.inspect_office_properties(path, signature) {
+            for field in fields {
+                let points = if IDENTIFYING_OFFICE_FIELDS.contains(&field.as_str()) { 3 } else { 1 };
+                score.add(points, field);
+            }
+        }
+
+        if let Ok(Some(fields)) = self.inspect_epub(path, signature) {
+            for field in fields {
+                let points = if matches!(field.as_str(), "Creator" | "Contributor") { 3 } else { 1 };
+                score.add(points, format!("EPUB {}", field));
+            }
+        }
+
+        if let Ok(Some(fields)) = self.inspect_pdf(path, signature) {
+            for field in fields {
+                let points = if field == "Author" { 3 } else { 1 };
+                score.add(points, format!("PDF {}", field));
+            }
+        }
+
+        if let Ok(Some(found)) = self.inspect_lnk(path, signature) {
+            // The tracker block names the machine and usually its MAC address
+            for item in found {
+                let points = if item.starts_with("tracker") { 3 } else { 1 };
+                score.add(points, format!("shortcut {}", item));
+            }
+        }
+
+        if let Ok(Some(chunks)) = self.inspect_png(path, signature) {
+            if !chunks.is_empty() {
+                score.add(1, format!("{} PNG text chunks", chunks.len()));
+            }
+        }
+
+        if let Ok(Some(blocks)) = self.inspect_gif(path, signature) {
+            if !blocks.is_empty() {
+                score.add(1, format!("GIF {}", blocks.join(", ")));
+            }
+        }
+
+        if let Ok(Some(found)) = self.inspect_svg(path, signature) {
+            if !found.is_empty() {
+                score.add(1, format!("SVG {}", found.join(", ")));
+            }
+        }
+
+        if let Ok(Some(found)) = self.inspect_xmp(path, signature) {
+            // Creator and IPTC contact details name a person; the rest is edit history
+            for property in found {
+                let points = if property == "dc:creator" || property.starts_with("Iptc4xmpCore:") { 2 } else { 1 };
+                score.add(points, format!("XMP {}", property));
+            }
+        }
+
+        if let Ok(Some(items)) = self.inspect_heif(path, signature) {
+            // HEIF EXIF from phones nearly always carries a GPS position
+            if items.iter().any(|item| item == "Exif") {
+                score.add(3, "HEIF EXIF");
+            }
+            if items.iter().any(|item| item == "XMP") {
+                score.add(1, "HEIF XMP");
+            }
+        }
+
+        if let Ok(Some(found)) = self.inspect_raw(path, signature) {
+            if found.iter().any(|item| item == "GPS") {
+                score.add(3, "RAW GPS position");
+            }
+            if found.iter().any(|item| item != "GPS") {
+                score.add(1, "RAW capture dates");
+            }
+        }
+
+        if let Ok(Some(tags)) = self.inspect_audio(path, signature) {
+            if !tags.is_empty() {
+                score.add(1, format!("{} ID3 tags", tags.len()));
+            }
+        }
+
+        if let Ok(Some(chunks)) = self.inspect_wav(path, signature) {
+            if !chunks.is_empty() {
+                score.add(1, format!("WAV {}", chunks.join(", ")));
+            }
+        }
+
+        if let Some(zone) = self.read_zone_identifier(path) {
+            let zone = zone.to_lowercase();
+            if PERSONAL_CLOUD_HOSTS.iter().any(|host| zone.contains(host)) {
+                score.add(5, "downloaded from personal cloud storage");
+            } else {
+                score.add(2, "download origin");
+            }
+        }
+
+        if let Ok(Some(headers)) = self.inspect_email(path) {
+            if headers > 0 {
+                score.add(3, format!("{} identifying email headers", headers));
+            }
+        }
+
+        if let Ok(Some(true)) = self.inspect_exif(path, signature) {
+            score.add(3, "EXIF data");
+        }
+
+        if let Ok(Some(_)) = self.object_id(path) {
+            score.add(1, "NTFS object ID");
+        }
+
+        let other_streams = self.enumerate_streams(path)
+            .unwrap_or_default()
+            .iter()
+            .map(|s| stream_name(s).to_string())
+            .filter(|name| !name.is_empty() && name != "Zone.Identifier")
+            .count();
+        if other_streams > 0 {
+            score.add(1, format!("{} other alternate streams", other_streams));
+        }
+
+        score
+    }
+
+    /// Gathers everything `info` shows about a file: timestamps, streams, Office
+    /// properties, its sensitivity score and what the enabled cleaners would remove.
+    pub fn file_info(&self, path: &Path) -> CleanerResult<FileInfo> {
+        let metadata = std::fs::metadata(path).map_err(|_| CleanerError::PathNotFound(path.to_path_buf()))?;
+        if metadata.is_dir() {
+            return Err(CleanerError::NotAFile(path.to_path_buf()));
+        }
+
+        let streams = self.list_streams(path).unwrap_or_default();
+
+        Ok(FileInfo {
+            path: path.to_path_buf(),
+            size: metadata.len(),
+            readonly: metadata.permissions().readonly(),
+            created: metadata.created().ok().map(time::OffsetDateTime::from),
+            modified: metadata.modified().ok().map(time::OffsetDateTime::from),
+            accessed: metadata.accessed().ok().map(time::OffsetDateTime::from),
+            inverted_timestamps: self.has_inverted_timestamps(path),
+            streams,
+            object_id: self.object_id(path).ok().flatten(),
+            office_properties: self.read_office_properties(path).ok().flatten(),
+            sensitivity: self.sensitivity(path),
+            remaining_metadata: self.verify(path),
+        })
+    }
+
+    /// Lists a file's alternate data streams (extended attributes elsewhere) with their sizes.
+    /// The main `::$DATA` stream is not included.
+    pub fn list_streams(&self, path: &Path) -> CleanerResult<Vec<StreamInfo>> {
+        Ok(self
+            .enumerate_stream_sizes(path)?
+            .into_iter()
+            .filter(|(name, _)| !stream_name(name).is_empty())
+            .map(|(name, size)| StreamInfo { name: stream_name(&name).to_string(), size })
+            .collect())
+    }
+
+    /// Removes the one stream with the given name, leaving every other stream,
+    /// the file's contents and its timestamps alone.
+    ///
+    /// Fails if the file has no such stream. The main `::$DATA` stream can't be removed.
+    pub fn remove_stream_by_name(&self, path: &Path, name: &str) -> CleanerResult<()> {
+        // NTFS stream names are case-insensitive; extended attribute names are not
+        let (raw_name, size) = self
+            .enumerate_stream_sizes(path)?
+            .into_iter()
+            .find(|(raw, _)| {
+                let candidate = stream_name(raw);
+                !candidate.is_empty()
+                    && if cfg!(windows) { candidate.eq_ignore_ascii_case(name) } else { candidate == name }
+            })
+            .ok_or_else(|| CleanerError::StreamNotFound { path: path.to_path_buf(), name: name.to_string() })?;
+
+        if self.options.shred_streams {
+            self.shred_stream(path, &raw_name, size)?;
+        }
+        self.delete_stream(path, &raw_name)
+    }
+
+    /// Overwrites a stream's `size` bytes (as `FindFirstStreamW` reports them)
+    /// with zeros and flushes them to disk, so deleting it doesn't leave the
+    /// content in free clusters or, for small streams, in the file's MFT record.
+    #[cfg(windows)]
+    fn shred_stream(&self, path: &Path, raw_name: &str, size: u64) -> CleanerResult<()> {
+        let context = format!("Failed to overwrite stream {}", stream_name(raw_name));
+        let stream_path = stream_path(path, raw_name);
+
+        let shred = || -> std::io::Result<()> {
+            let mut stream = OpenOptions::new().write(true).open(&stream_path)?;
+            let zeros = [0u8; 64 * 1024];
+            let mut remaining = size;
+            while remaining > 0 {
+                let chunk = remaining.min(zeros.len() as u64) as usize;
+                stream.write_all(&zeros[..chunk])?;
+                remaining -= chunk as u64;
+            }
+            stream.sync_all()
+        };
+        shred().map_err(|e| CleanerError::file_operation(path, &context, e))
+    }
+
+    /// Overwrites an extended attribute's value with zeros of the same length,
+    /// which filesystems storing it inside the inode rewrite in place.
+    #[cfg(not(windows))]
+    fn shred_stream(&self, path: &Path, name: &str, size: u64) -> CleanerResult<()> {
+        xattr::set(path, name, &vec![0u8; size as usize])
+            .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to overwrite {}: {}", name, e)))
+    }
+
+    /// Deletes one stream, addressed by its `:name:$DATA` specifier as enumerated.
+    #[cfg(windows)]
+    fn delete_stream(&self, path: &Path, raw_name: &str) -> CleanerResult<()> {
+        let wide_path = wide_path(&stream_path(path, raw_name));
+
+        unsafe {
+            DeleteFileW(PCWSTR(wide_path.as_ptr())).map_err(|e| {
+                if is_sharing_violation(&std::io::Error::last_os_error()) {
+                    CleanerError::FileInUse(path.to_path_buf())
+                } else {
+                    CleanerError::windows_api_error(path, e.to_string())
+                }
+            })
+        }
+    }
+
+    /// Deletes one extended attribute.
+    #[cfg(not(windows))]
+    fn delete_stream(&self, path: &Path, name: &str) -> CleanerResult<()> {
+        xattr::remove(path, name)
+            .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to remove {}: {}", name, e)))
+    }
+
+    /// Re-inspects a file and returns any metadata the enabled cleaners should have removed.
+    ///
+    /// Each format cleaner has its own verifier (see [`FormatCleaner::verify`]),
+    /// so the check is as thorough as the cleaner it mirrors. Read-only; usable both after cleaning and standalone.
+    pub fn verify(&self, path: &Path) -> Vec<RemainingLeak> {
+        let mut leaks = Vec::new();
+
+        if self.options.clear_streams {
+            leaks.extend(self.verify_streams(path));
+        }
+
+        if self.options.clear_timestamps {
+            leaks.extend(self.verify_timestamps(path));
+        }
+
+        if self.options.clear_object_id {
+            leaks.extend(self.verify_object_id(path));
+        }
+
+        if self.options.clear_attributes {
+            leaks.extend(self.verify_attributes(path));
+        }
+
+        // Creation times can only be moved back on Windows
+        if self.options.fix_timestamp_order && cfg!(windows) && self.has_inverted_timestamps(path) {
+            leaks.push(RemainingLeak::new("timestamp-order", "created after last modified"));
+        }
+
+        if self.options.clear_properties {
+            let signature = Signature::of_file(path);
+            for format in FORMAT_CLEANERS.iter().filter(|format| format.is_verified()) {
+                leaks.extend(format.verify(self, path, signature));
+            }
+        }
+
+        leaks
+    }
+
+    /// Counts which kinds of metadata `files` carry, without modifying them.
+    ///
+    /// Runs the read-only [`verify`](Self::verify) checks in parallel and rewrites
+    /// nothing, so it is quick enough to show before asking whether to clean.
+    /// Protected files are left out, as cleaning skips them too.
+    pub fn preview_scan(&self, files: &[PathBuf]) -> CleanerResult<ScanSummary> {
+        let leaks: Vec<Vec<RemainingLeak>> = self.in_thread_pool(|| {
+            files
+                .par_iter()
+                .filter(|file| !self.is_protected(file))
+                .map(|file| self.verify(file))
+                .collect()
+        })?;
+
+        let mut summary = ScanSummary::default();
+        for file_leaks in &leaks {
+            summary.add_file(file_leaks);
+        }
+        Ok(summary)
+    }
+
+    /// Returns true if cleaning would change nothing: `verify` finds no metadata,
+    /// attributes or inverted timestamps, and timestamps already match a fixed target.
+    ///
+    /// Always false when the owner is cleared, since ownership isn't verified,
+    /// or when timestamps are set to the current time.
+    pub fn is_already_clean(&self, path: &Path) -> bool {
+        if self.options.clear_owner {
+            return false;
+        }
+        if self.options.clear_timestamps && self.options.timestamp_target == TimestampTarget::Now {
+            return false;
+        }
+        self.verify(path).is_empty()
+    }
+
+    /// Returns the categories `verify` checks with the current options, in report order.
+    pub fn verified_categories(&self) -> Vec<&'static str> {
+        let mut categories = Vec::new();
+        if self.options.clear_streams {
+            categories.push("streams");
+        }
+        if self.options.clear_timestamps {
+            categories.push("timestamps");
+        }
+        if self.options.clear_object_id && cfg!(windows) {
+            categories.push("object-id");
+        }
+        if self.options.clear_attributes && cfg!(windows) {
+            categories.push("attributes");
+        }
+        if self.options.fix_timestamp_order && cfg!(windows) {
+            categories.push("timestamp-order");
+        }
+        if self.options.clear_properties {
+            categories.extend(FORMAT_CLEANERS.iter().filter(|format| format.is_verified()).map(|format| format.name()));
+        }
+        categories
+    }
+
+    /// Reports timestamps that differ from the configured target, or for a
+    /// random target, fall outside its range.
+    ///
+    /// Skipped for `TimestampTarget::Now`, which no earlier clean can match.
+    fn verify_timestamps(&self, path: &Path) -> Vec<RemainingLeak> {
+        if self.options.timestamp_target == TimestampTarget::Now || self.has_target_timestamps(path) {
+            return Vec::new();
+        }
+        vec![RemainingLeak::new("timestamps", "not set to the target time")]
+    }
+
+    /// Reports an NTFS object ID that cleaning would have deleted.
+    fn verify_object_id(&self, path: &Path) -> Vec<RemainingLeak> {
+        match self.object_id(path) {
+            Ok(Some(id)) => vec![RemainingLeak::new("object-id", id)],
+            Ok(None) => Vec::new(),
+            Err(e) => vec![RemainingLeak::new("object-id", format!("could not inspect: {}", e))],
+        }
+    }
+
+    /// Reports hidden, system and archive attributes that cleaning would have cleared.
+    #[cfg(windows)]
+    fn verify_attributes(&self, path: &Path) -> Vec<RemainingLeak> {
+        use std::os::windows::fs::MetadataExt;
+
+        let Ok(attributes) = std::fs::metadata(path).map(|m| m.file_attributes()) else {
+            return Vec::new();
+        };
+        let set: Vec<&str> = [(FILE_ATTRIBUTE_HIDDEN, "hidden"), (FILE_ATTRIBUTE_SYSTEM, "system"), (FILE_ATTRIBUTE_ARCHIVE, "archive")]
+            .into_iter()
+            .filter(|(flag, _)| attributes & flag.0 != 0)
+            .map(|(_, name)| name)
+            .collect();
+
+        if set.is_empty() {
+            Vec::new()
+        } else {
+            vec![RemainingLeak::new("attributes", set.join(", "))]
+        }
+    }
+
+    #[cfg(not(windows))]
+    fn verify_attributes(&self, _path: &Path) -> Vec<RemainingLeak> {
+        // Hidden/system/archive attributes are Windows-specific
+        Vec::new()
+    }
+
+    /// Reports alternate data streams that cleaning would have removed.
+    fn verify_streams(&self, path: &Path) -> Vec<RemainingLeak> {
+        match self.removable_streams(path) {
+            Ok(streams) => streams
+                .iter()
+                .map(|s| RemainingLeak::new("streams", stream_name(s)))
+                .collect(),
+            Err(e) => vec![RemainingLeak::new("streams", format!("could not enumerate: {}", e))],
+        }
+    }
+
+    /// Reports identifying docProps fields that are still populated.
+    pub(super) fn verify_office_xml(&self, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        match self.inspect_office_properties(path, signature) {
+            Ok(Some(fields)) => fields
+                .into_iter()
+                .map(|field| RemainingLeak::new("office-xml", field))
+                .collect(),
+            Ok(None) => Vec::new(),
+            Err(e) => vec![RemainingLeak::new("office-xml", format!("could not inspect: {}", e))],
+        }
+    }
+
+    /// Reports identifying email headers that are still present.
+    pub(super) fn verify_email(&self, path: &Path) -> Vec<RemainingLeak> {
+        match self.inspect_email(path) {
+            Ok(Some(0)) | Ok(None) => Vec::new(),
+            Ok(Some(count)) => vec![RemainingLeak::new("email", format!("{} identifying headers", count))],
+            Err(e) => vec![RemainingLeak::new("email", format!("could not inspect: {}", e))],
+        }
+    }
+
+    /// Reports EXIF data still present in a JPEG or TIFF image.
+    pub(super) fn verify_exif(&self, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        match self.inspect_exif(path, signature) {
+            Ok(Some(true)) => vec![RemainingLeak::new("exif", "EXIF data present")],
+            Ok(Some(false)) | Ok(None) => Vec::new(),
+            Err(e) => vec![RemainingLeak::new("exif", format!("could not inspect: {}", e))],
+        }
+    }
+
+    /// Reports text and time chunks still present in a PNG.
+    pub(super) fn verify_png(&self, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        match self.inspect_png(path, signature) {
+            Ok(Some(chunks)) => chunks
+                .into_iter()
+                .map(|chunk| RemainingLeak::new("png", format!("{} chunk", chunk)))
+                .collect(),
+            Ok(None) => Vec::new(),
+            Err(e) => vec![RemainingLeak::new("png", format!("could not inspect: {}", e))],
+        }
+    }
+
+    /// Reports identity fields and calibre entries still present in an EPUB.
+    pub(super) fn verify_epub(&self, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        match self.inspect_epub(path, signature) {
+            Ok(Some(fields)) => fields.into_iter().map(|field| RemainingLeak::new("epub", field)).collect(),
+            Ok(None) => Vec::new(),
+            Err(e) => vec![RemainingLeak::new("epub", format!("could not inspect: {}", e))],
+        }
+    }
+
+    /// Reports comment and application extensions still present in a GIF.
+    pub(super) fn verify_gif(&self, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        match self.inspect_gif(path, signature) {
+            Ok(Some(blocks)) => blocks.into_iter().map(|block| RemainingLeak::new("gif", block)).collect(),
+            Ok(None) => Vec::new(),
+            Err(e) => vec![RemainingLeak::new("gif", format!("could not inspect: {}", e))],
+        }
+    }
+
+    /// Reports the GPS IFD and date tags still present in a camera RAW file.
+    pub(super) fn verify_raw(&self, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        match self.inspect_raw(path, signature) {
+            Ok(Some(found)) => found.into_iter().map(|item| RemainingLeak::new("raw", item)).collect(),
+            Ok(None) => Vec::new(),
+            Err(e) => vec![RemainingLeak::new("raw", format!("could not inspect: {}", e))],
+        }
+    }
+
+    /// Reports metadata, editor data and comments still present in an SVG.
+    pub(super) fn verify_svg(&self, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        match self.inspect_svg(path, signature) {
+            Ok(Some(found)) => found.into_iter().map(|item| RemainingLeak::new("svg", item)).collect(),
+            Ok(None) => Vec::new(),
+            Err(e) => vec![RemainingLeak::new("svg", format!("could not inspect: {}", e))],
+        }
+    }
+
+    /// Reports identity properties still present in an XMP sidecar.
+    pub(super) fn verify_xmp(&self, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        match self.inspect_xmp(path, signature) {
+            Ok(Some(found)) => found.into_iter().map(|property| RemainingLeak::new("xmp", property)).collect(),
+            Ok(None) => Vec::new(),
+            Err(e) => vec![RemainingLeak::new("xmp", format!("could not inspect: {}", e))],
+        }
+    }
+
+    /// Reports EXIF and XMP items still holding data in a HEIF image.
+    pub(super) fn verify_heif(&self, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        match self.inspect_heif(path, signature) {
+            Ok(Some(items)) => items
+                .into_iter()
+                .map(|item| RemainingLeak::new("heif", format!("{} item", item)))
+                .collect(),
+            Ok(None) => Vec::new(),
+            Err(e) => vec![RemainingLeak::new("heif", format!("could not inspect: {}", e))],
+        }
+    }
+
+    /// Reports ID3 tags still present in an audio file.
+    pub(super) fn verify_audio(&self, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        match self.inspect_audio(path, signature) {
+            Ok(Some(tags)) => tags
+                .into_iter()
+                .map(|tag| RemainingLeak::new("audio", tag))
+                .collect(),
+            Ok(None) => Vec::new(),
+            Err(e) => vec![RemainingLeak::new("audio", format!("could not inspect: {}", e))],
+        }
+    }
+
+    /// Reports INFO and bext chunks still present in a WAV file.
+    pub(super) fn verify_wav(&self, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        match self.inspect_wav(path, signature) {
+            Ok(Some(chunks)) => chunks
+                .into_iter()
+                .map(|chunk| RemainingLeak::new("wav", format!("{} chunk", chunk)))
+                .collect(),
+            Ok(None) => Vec::new(),
+            Err(e) => vec![RemainingLeak::new("wav", format!("could not inspect: {}", e))],
+        }
+    }
+
+    /// Reports document info entries and XMP metadata still present in a PDF.
+    pub(super) fn verify_pdf(&self, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        match self.inspect_pdf(path, signature) {
+            Ok(Some(fields)) => fields
+                .into_iter()
+                .map(|field| RemainingLeak::new("pdf", field))
+                .collect(),
+            Ok(None) => Vec::new(),
+            Err(e) => vec![RemainingLeak::new("pdf", format!("could not inspect: {}", e))],
+        }
+    }
+
+    /// Reports tracker blocks and volume fields still present in a shortcut.
+    pub(super) fn verify_lnk(&self, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        match self.inspect_lnk(path, signature) {
+            Ok(Some(found)) => found
+                .into_iter()
+                .map(|item| RemainingLeak::new("lnk", item))
+                .collect(),
+            Ok(None) => Vec::new(),
+            Err(e) => vec![RemainingLeak::new("lnk", format!("could not inspect: {}", e))],
+        }
+    }
+
+    /// Inspects a file without modifying it and reports what a real run would do.
+    ///
+    /// Nothing is opened for writing: streams are enumerated, Office properties and
+    /// email headers are read, and timestamps are compared against the neutral date.
+    /// A step that fails to inspect the file becomes a warning, as in a real run.
+    fn plan_file(&self, path: PathBuf) -> FileResult {
+        let mut steps = Vec::new();
+        let mut actions = Vec::new();
+
+        if std::fs::metadata(&path).is_ok_and(|m| m.permissions().readonly()) {
+            let detail = if self.options.preserve_readonly { "would be cleared, then restored" } else { "would be cleared" };
+            steps.push(CleanerStep::planned("readonly", detail));
+            actions.push("would clear the read-only attribute".to_string());
+        }
+
+        let streams = self.enumerate_streams(&path);
+
+        if self.options.clear_streams {
+            match &streams {
+                Ok(streams) => {
+                    let count = streams
+                        .iter()
+                        .map(|s| stream_name(s))
+                        .filter(|name| !name.is_empty() && self.is_removable_stream(name))
+                        .count();
+                    if count == 0 {
+                        steps.push(CleanerStep::skipped("streams", "none present"));
+                    } else {
+                        steps.push(CleanerStep::planned("streams", format!("{} to remove", count)));
+                        actions.push(format!("would remove {} stream{}", count, if count == 1 { "" } else { "s" }));
+                    }
+                }
+                Err(e) => steps.push(CleanerStep::failed("streams", e.to_string())),
+            }
+        } else {
+            steps.push(CleanerStep::skipped("streams", "disabled"));
+        }
+
+        if !self.options.clear_object_id {
+            steps.push(CleanerStep::skipped("object-id", "disabled"));
+        } else if cfg!(windows) {
+            match self.object_id(&path) {
+                Ok(Some(id)) => {
+                    steps.push(CleanerStep::planned("object-id", id));
+                    actions.push("would delete the NTFS object ID".to_string());
+                }
+                Ok(None) => steps.push(CleanerStep::skipped("object-id", "none present")),
+                Err(e) => steps.push(CleanerStep::failed("object-id", e.to_string())),
+            }
+        } else {
+            steps.push(CleanerStep::skipped("object-id", "not supported on this platform"));
+        }
+
+        if self.options.clear_owner {
+            steps.push(CleanerStep::planned("owner", "would be replaced"));
+            actions.push("would replace the owner".to_string());
+        } else {
+            steps.push(CleanerStep::skipped("owner", "needs --admin"));
+        }
+
+        if self.options.clear_properties {
+            let present: Vec<&str> = streams
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|s| {
+                    let name = stream_name(s);
+                    PROPERTY_STREAMS.iter().copied().find(|p| *p == name && !self.is_kept_stream(p))
+                })
+                .collect();
+            if present.is_empty() {
+                steps.push(CleanerStep::skipped("property-streams", "none present"));
+            } else {
+                let names = present.join(", ").replace('\x05', "");
+                actions.push(format!("would remove property streams ({})", names));
+                steps.push(CleanerStep::planned("property-streams", names));
+            }
+        } else {
+            steps.push(CleanerStep::skipped("property-streams", "disabled"));
+        }
+
+        let signature = Signature::of_file(&path);
+        for format in FORMAT_CLEANERS {
+            if !self.options.clear_properties {
+                steps.push(CleanerStep::skipped(format.name(), "disabled"));
+            } else if !format.handles_path(&path, signature) {
+                steps.push(CleanerStep::skipped(format.name(), format.not_handled()));
+            } else {
+                let (step, action) = format.plan(self, &path, signature);
+                steps.push(step);
+                actions.extend(action);
+            }
+        }
+
+        if !self.options.clear_attributes {
+            steps.push(CleanerStep::skipped("attributes", "disabled"));
+        } else if cfg!(windows) {
+            steps.push(CleanerStep::planned("attributes", "hidden, system and archive flags"));
+            actions.push("would clear hidden, system and archive flags".to_string());
+        } else {
+            steps.push(CleanerStep::skipped("attributes", "not supported on this platform"));
+        }
+
+        if !self.options.clear_timestamps {
+            steps.push(CleanerStep::skipped("timestamps", "disabled"));
+        } else if self.options.timestamp_target == TimestampTarget::Unchanged {
+            steps.push(CleanerStep::skipped("timestamps", "left unchanged"));
+        } else if self.has_target_timestamps(&path) {
+            steps.push(CleanerStep::skipped("timestamps", "already at target"));
+        } else {
+            steps.push(CleanerStep::planned("timestamps", "not at target"));
+            actions.push("would reset timestamps".to_string());
+        }
+
+        if self.options.fix_timestamp_order {
+            if self.has_inverted_timestamps(&path) {
+                steps.push(CleanerStep::planned("timestamp-order", "creation time would move back"));
+                actions.push("would move the creation time back".to_string());
+            } else {
+                steps.push(CleanerStep::skipped("timestamp-order", "already ordered"));
+            }
+        }
+
+        // A step that couldn't inspect the file doesn't stop the rest of the plan
+        let warnings = steps
+            .iter()
+            .filter(|step| step.status == StepStatus::Failed)
+            .map(|step| format!("{}: {}", step.name, step.detail.as_deref().unwrap_or("inspection failed")))
+            .collect();
+
+        let mut result = FileResult::success(path, 0, false).with_steps(steps).with_planned_actions(actions);
+        result.warnings = warnings;
+        result
+    }
+
+    /// Collects all files that would be processed.
+    pub fn collect_files(&self, path: &Path, mode: CleanMode) -> CleanerResult<Vec<PathBuf>> {
+        Ok(self.collect_files_and_symlinks(path, mode)?.0)
+    }
+
+    /// Collects the files that would be processed, plus the symlinks skipped on the way.
+    ///
+    /// Unless `follow_symlinks` is set, symlinked files and directories are not
+    /// followed, so cleaning never escapes the tree or loops; they are returned
+    /// separately so callers can report them. When following, link cycles are
+    /// detected and skipped.
+    pub fn collect_files_and_symlinks(&self, path: &Path, mode: CleanMode) -> CleanerResult<(Vec<PathBuf>, Vec<PathBuf>)> {
+        let path = resolve_path(&extended_length_path(path))?;
+
+        match mode {
+            CleanMode::SingleFile => {
+                if path.is_file() {
+                    Ok((vec![path], Vec::new()))
+                } else {
+                    Err(CleanerError::NotAFile(path))
+                }
+            }
+            CleanMode::Shallow | CleanMode::Deep => {
+                if !path.is_dir() {
+                    return Err(CleanerError::NotADirectory(path));
+                }
+
+                let filter = PathFilter::new(&self.options)?;
+                let max_depth = match mode {
+                    CleanMode::Shallow => 1,
+                    _ => self.options.max_depth.unwrap_or(usize::MAX),
+                };
+                let relative = |entry: &walkdir::DirEntry| entry.path().strip_prefix(&path).unwrap_or(entry.path()).to_path_buf();
+
+                let mut files = Vec::new();
+                let mut symlinks = Vec::new();
+
+                // Excluded directories are pruned here, so their subtrees are never read
+                let walker = WalkDir::new(&path)
+                    .min_depth(1)
+                    .max_depth(max_depth)
+                    .follow_links(self.options.follow_symlinks)
+                    .into_iter()
+                    .filter_entry(|e| !filter.excludes(&relative(e)));
+
+                for entry in walker.filter_map(|e| e.ok()) {
+                    if entry.path_is_symlink() && !self.options.follow_symlinks {
+                        if filter.includes(&relative(&entry)) {
+                            symlinks.push(entry.path().to_path_buf());
+                        }
+                        continue;
+                    }
+
+                    if entry.file_type().is_file()
+                        && !is_own_artifact(entry.path())
+                        && !(self.options.remove_junk && is_junk_file(entry.path()))
+                        && filter.includes(&relative(&entry))
+                        && filter.includes_modified(&entry)
+                    {
+                        files.push(entry.path().to_path_buf());
+                    }
+                }
+
+                // Followed symlinks can reach the same file more than once
+                let files = if self.options.follow_symlinks { dedupe_paths(files) } else { files };
+
+                Ok((files, symlinks))
+            }
+        }
+    }
+
+    /// Collects the junk files (`Thumbs.db`, `desktop.ini`, `.DS_Store`) that
+    /// `remove_junk` deletes.
+    ///
+    /// The walk is the same as for the files to clean, but include globs and
+    /// age bounds don't apply: they pick documents, and junk goes wherever it is.
+    /// Returns nothing unless `remove_junk` is on, or when cleaned copies go to
+    /// an output folder and the source folders must stay as they are.
+    pub fn collect_junk(&self, path: &Path, mode: CleanMode) -> CleanerResult<Vec<PathBuf>> {
+        if !self.options.remove_junk || self.options.output_dir.is_some() || mode == CleanMode::SingleFile {
+            return Ok(Vec::new());
+        }
+
+        let path = resolve_path(&extended_length_path(path))?;
+        if !path.is_dir() {
+            return Err(CleanerError::NotADirectory(path));
+        }
+
+        let filter = PathFilter::new(&self.options)?;
+        let max_depth = match mode {
+            CleanMode::Shallow => 1,
+            _ => self.options.max_depth.unwrap_or(usize::MAX),
+        };
+        let relative = |entry: &walkdir::DirEntry| entry.path().strip_prefix(&path).unwrap_or(entry.path()).to_path_buf();
+
+        let walker = WalkDir::new(&path)
+            .min_depth(1)
+            .max_depth(max_depth)
+            .follow_links(self.options.follow_symlinks)
+            .into_iter()
+            .filter_entry(|e| !filter.excludes(&relative(e)));
+
+        let junk = walker
+            .filter_map(|e| e.ok())
+            .filter(|entry| entry.file_type().is_file() && !entry.path_is_symlink() && is_junk_file(entry.path()))
+            .map(|entry| entry.path().to_path_buf())
+            .collect();
+
+        // Followed symlinks can reach the same file more than once
+        Ok(if self.options.follow_symlinks { dedupe_paths(junk) } else { junk })
+    }
+
+    /// Deletes one junk file, backing it up first if a backup folder is set.
+    ///
+    /// In a dry run nothing changes and the result records the planned step.
+    pub fn remove_junk_file(&self, path: &Path) -> FileResult {
+        if self.options.dry_run {
+            return FileResult::success(path.to_path_buf(), 0, false)
+                .with_steps(vec![CleanerStep::planned("junk", "would delete")])
+                .with_planned_actions(vec!["would delete junk file".to_string()]);
+        }
+
+        let mut steps = Vec::new();
+        if let Some(backup_dir) = &self.options.backup_dir {
+            match self.backup_file(path, backup_dir) {
+                Ok(Some(backup)) => steps.push(CleanerStep::ran_with("backup", backup.display().to_string())),
+                Ok(None) => steps.push(CleanerStep::skipped("backup", "kept earlier backup")),
+                Err(e) => return failed_step(path.to_path_buf(), steps, "backup", e),
+            }
+        }
+
+        // desktop.ini is often read-only, which Windows won't delete
+        let removed = std::fs::remove_file(path).or_else(|e| {
+            if e.kind() != std::io::ErrorKind::PermissionDenied {
+                return Err(e);
+            }
+            make_writable(path)?;
+            std::fs::remove_file(path)
+        });
 
-    /// Internal method to clean a directory with the specified mode.
-    fn clean_directory_internal(&self, path: &Path, mode: CleanMode) -> CleanerResult<CleanReport> {
-        let path = path.canonicalize().map_err(|_| CleanerError::PathNotFound(path.to_path_buf()))?;
+        match removed {
+            Ok(()) => {
+                steps.push(CleanerStep::ran("junk"));
+                FileResult::success(path.to_path_buf(), 0, false).with_steps(steps)
+            }
+            Err(e) => failed_step(
+                path.to_path_buf(),
+                steps,
+                "junk",
+                CleanerError::file_operation(path, "Failed to delete junk file", e),
+            ),
+        }
+    }
 
-        if !path.exists() {
-            return Err(CleanerError::PathNotFound(path));
+    /// Collects the folders whose timestamps `clean_dirs` resets, deepest first.
+    ///
+    /// These are the root and every folder whose contents the walk reads, so
+    /// excluded folders, folders below `max_depth` and (unless following)
+    /// symlinked folders are left alone. Returns nothing unless `clean_dirs` and
+    /// timestamp clearing are on, or when cleaned copies go to an output folder
+    /// and the source folders never change.
+    pub fn collect_directories(&self, path: &Path, mode: CleanMode) -> CleanerResult<Vec<PathBuf>> {
+        if !self.options.clean_dirs
+            || !self.options.clear_timestamps
+            || self.options.output_dir.is_some()
+            || mode == CleanMode::SingleFile
+        {
+            return Ok(Vec::new());
         }
 
+        let path = resolve_path(&extended_length_path(path))?;
         if !path.is_dir() {
             return Err(CleanerError::NotADirectory(path));
         }
 
-        let mut report = CleanReport::new();
-
-        let walker = match mode {
-            CleanMode::Shallow => WalkDir::new(&path).min_depth(1).max_depth(1),
-            CleanMode::Deep => WalkDir::new(&path).min_depth(1),
-            CleanMode::SingleFile => unreachable!(),
+        let filter = PathFilter::new(&self.options)?;
+        let max_depth = match mode {
+            CleanMode::Shallow => 1,
+            _ => self.options.max_depth.unwrap_or(usize::MAX),
         };
+        let relative = |entry: &walkdir::DirEntry| entry.path().strip_prefix(&path).unwrap_or(entry.path()).to_path_buf();
 
-        for entry in walker.into_iter().filter_map(|e| e.ok()) {
-            let entry_path = entry.path();
+        // Folders at the depth limit are listed but not entered, so they stay as they are
+        let walker = WalkDir::new(&path)
+            .max_depth(max_depth.saturating_sub(1))
+            .follow_links(self.options.follow_symlinks)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !filter.excludes(&relative(e)));
 
-            // Skip directories
-            if entry_path.is_dir() {
-                continue;
-            }
+        let dirs: Vec<PathBuf> = walker
+            .filter_map(|e| e.ok())
+            .filter(|entry| entry.file_type().is_dir() && (self.options.follow_symlinks || !entry.path_is_symlink()))
+            .map(|entry| entry.path().to_path_buf())
+            .collect();
 
-            match self.clean_file(entry_path) {
-                Ok(result) => report.add_result(result),
-                Err(e) => {
-                    report.add_result(FileResult::failure(entry_path.to_path_buf(), e.to_string()));
-                }
-            }
+        // Followed symlinks can reach the same folder more than once
+        let mut dirs = if self.options.follow_symlinks { dedupe_paths(dirs) } else { dirs };
+
+        // The walk lists each folder before its subfolders; reversed, children come first
+        dirs.reverse();
+        Ok(dirs)
+    }
+
+    /// Resets one folder's timestamps to the configured target.
+    ///
+    /// In a dry run nothing changes and the result records the planned step.
+    pub fn reset_directory_timestamps(&self, dir: &Path) -> FileResult {
+        if self.options.dry_run {
+            return FileResult::success(dir.to_path_buf(), 0, false)
+                .with_steps(vec![CleanerStep::planned("timestamps", "folder")])
+                .with_planned_actions(vec!["would reset folder timestamps".to_string()]);
         }
 
-        Ok(report)
+        match self.reset_timestamps(dir) {
+            Ok(_) => FileResult::success(dir.to_path_buf(), 0, false).with_steps(vec![CleanerStep::ran("timestamps")]),
+            Err(e) => FileResult::failure(dir.to_path_buf(), e.to_string()),
+        }
     }
 
-    /// Removes alternate data streams from a file.
-    #[cfg(windows)]
-    fn remove_alternate_streams(&self, path: &Path) -> CleanerResult<usize> {
-        let streams = self.enumerate_streams(path)?;
-        let mut removed_count = 0;
+    /// Exercises each cleaning operation on scratch files created in `dir` and reports
+    /// which capabilities work on this system and filesystem.
+    pub fn diagnose(&self, dir: &Path) -> CleanerResult<Vec<Capability>> {
+        let scratch = dir.join(format!(".rs-mahito-doctor-{}", std::process::id()));
+        std::fs::create_dir_all(&scratch)?;
 
-        for stream_name in streams {
-            // Skip the main data stream (::$DATA)
-            if stream_name == "::$DATA" || stream_name.is_empty() {
-                continue;
-            }
+        let capabilities = vec![
+            Capability::new("streams", self.probe_streams(&scratch)),
+            Capability::new("timestamps", self.probe_timestamps(&scratch)),
+            Capability::new("owner", self.probe_owner(&scratch)),
+            Capability::new("office-xml", self.probe_office_xml(&scratch)),
+            Capability::new("email", self.probe_email(&scratch)),
+        ];
 
-            // Build the full stream path
-            let stream_path = format!("{}:{}", path.display(), stream_name.trim_start_matches(':').trim_end_matches(":$DATA"));
+        let _ = std::fs::remove_dir_all(&scratch);
+        Ok(capabilities)
+    }
 
-            let wide_path: Vec<u16> = stream_path.encode_utf16().chain(std::iter::once(0)).collect();
+    /// Creates a scratch file with the given name and contents.
+    fn scratch_file(dir: &Path, name: &str, contents: &[u8]) -> Result<PathBuf, CapabilityStatus> {
+        let path = dir.join(name);
+        std::fs::write(&path, contents)
+            .map_err(|e| CapabilityStatus::Failed(format!("cannot create scratch file: {}", e)))?;
+        Ok(path)
+    }
 
-            unsafe {
-                let result = DeleteFileW(PCWSTR(wide_path.as_ptr()));
-                if result.is_ok() {
-                    removed_count += 1;
-                }
-            }
+    /// Checks that an alternate data stream can be created and removed.
+    #[cfg(windows)]
+    fn probe_streams(&self, dir: &Path) -> CapabilityStatus {
+        let path = match Self::scratch_file(dir, "streams.txt", b"probe") {
+            Ok(path) => path,
+            Err(status) => return status,
+        };
+
+        // Zone.Identifier is removed under any stream allowlist
+        let stream_path = format!("{}:Zone.Identifier", path.display());
+        if std::fs::write(&stream_path, b"probe").is_err() {
+            return CapabilityStatus::Unsupported("filesystem has no alternate data streams".into());
         }
 
-        Ok(removed_count)
+        if let Err(e) = self.remove_alternate_streams(&path) {
+            return CapabilityStatus::Failed(e.to_string());
+        }
+
+        match self.enumerate_streams(&path) {
+            Ok(streams) if streams.iter().all(|s| s == "::$DATA") => CapabilityStatus::Available,
+            Ok(_) => CapabilityStatus::Failed("stream still present after removal".into()),
+            Err(e) => CapabilityStatus::Failed(e.to_string()),
+        }
     }
 
     #[cfg(not(windows))]
-    fn remove_alternate_streams(&self, _path: &Path) -> CleanerResult<usize> {
-        // Non-Windows systems don't have NTFS alternate data streams
-        Ok(0)
-    }
+    fn probe_streams(&self, dir: &Path) -> CapabilityStatus {
+        // The attribute the platform's browsers set on downloads
+        let probe_attribute = if cfg!(target_os = "macos") { "com.apple.quarantine" } else { "user.xdg.origin.url" };
 
-    /// Enumerates all alternate data streams for a file.
-    #[cfg(windows)]
-    fn enumerate_streams(&self, path: &Path) -> CleanerResult<Vec<String>> {
-        let wide_path: Vec<u16> = path.as_os_str()
-            .to_string_lossy()
-            .encode_utf16()
-            .chain(std::iter::once(0))
-            .collect();
+        let path = match Self::scratch_file(dir, "streams.txt", b"probe") {
+            Ok(path) => path,
+            Err(status) => return status,
+        };
 
-        let mut streams = Vec::new();
-        let mut find_data = WIN32_FIND_STREAM_DATA::default();
+        if xattr::set(&path, probe_attribute, b"rs-mahito-probe").is_err() {
+            return CapabilityStatus::Unsupported("filesystem has no extended attributes".into());
+        }
 
-        unsafe {
-            let handle = FindFirstStreamW(
-                PCWSTR(wide_path.as_ptr()),
-                windows::Win32::Storage::FileSystem::FindStreamInfoStandard,
-                &mut find_data as *mut _ as *mut _,
-                0,
-            );
+        if let Err(e) = self.remove_alternate_streams(&path) {
+            return CapabilityStatus::Failed(e.to_string());
+        }
 
-            match handle {
-                Ok(h) if !h.is_invalid() => {
-                    loop {
-                        let stream_name = String::from_utf16_lossy(
-                            &find_data.cStreamName[..find_data.cStreamName.iter().position(|&c| c == 0).unwrap_or(find_data.cStreamName.len())]
-                        );
+        match self.enumerate_streams(&path) {
+            Ok(attributes) if attributes.is_empty() => CapabilityStatus::Available,
+            Ok(_) => CapabilityStatus::Failed("attribute still present after removal".into()),
+            Err(e) => CapabilityStatus::Failed(e.to_string()),
+        }
+    }
 
-                        if !stream_name.is_empty() {
-                            streams.push(stream_name);
-                        }
+    /// Checks that timestamps can be reset.
+    fn probe_timestamps(&self, dir: &Path) -> CapabilityStatus {
+        let path = match Self::scratch_file(dir, "timestamps.txt", b"probe") {
+            Ok(path) => path,
+            Err(status) => return status,
+        };
 
-                        if FindNextStreamW(h, &mut find_data as *mut _ as *mut _).is_err() {
-                            break;
-                        }
-                    }
-                    let _ = FindClose(h);
-                }
-                _ => {
-                    // No streams found or error - this is not necessarily an error condition
-                }
-            }
+        if let Err(e) = self.reset_timestamps(&path) {
+            return CapabilityStatus::Failed(e.to_string());
         }
 
-        Ok(streams)
+        if cfg!(windows) {
+            CapabilityStatus::Available
+        } else {
+            CapabilityStatus::Limited("only the modification time can be changed".into())
+        }
     }
 
-    #[cfg(not(windows))]
-    fn enumerate_streams(&self, _path: &Path) -> CleanerResult<Vec<String>> {
-        Ok(Vec::new())
-    }
+    /// Checks whether the file owner can be changed (requires Administrator).
+    fn probe_owner(&self, dir: &Path) -> CapabilityStatus {
+        if !cfg!(windows) {
+            return CapabilityStatus::Unsupported("NTFS ownership is Windows-only".into());
+        }
 
-    /// Resets file timestamps to January 1, 2000 (a neutral, anonymous date).
-    #[cfg(windows)]
-    fn reset_timestamps(&self, path: &Path) -> CleanerResult<()> {
-        use std::os::windows::io::AsRawHandle;
+        let path = match Self::scratch_file(dir, "owner.txt", b"probe") {
+            Ok(path) => path,
+            Err(status) => return status,
+        };
 
-        // Open the file with write access for setting times
-        let file = OpenOptions::new()
-            .write(true)
-            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS.0)
-            .open(path)
-            .map_err(|e| CleanerError::cleaning_failed(path, e.to_string()))?;
+        match self.clear_owner(&path) {
+            Ok(()) => CapabilityStatus::Available,
+            Err(_) => CapabilityStatus::NeedsAdmin,
+        }
+    }
 
-        // FILETIME is in 100-nanosecond intervals since January 1, 1601 (UTC)
-        // January 1, 2000 00:00:00 UTC = 125911584000000000 (100-ns intervals since 1601)
-        // This is calculated as: days from 1601 to 2000 * 24 * 60 * 60 * 10_000_000
-        // Using a neutral date that doesn't reveal when the file was actually created
-        const FILETIME_JAN_1_2000: u64 = 125911584000000000;
+    /// Checks that Office document properties are cleared from a generated .docx.
+    fn probe_office_xml(&self, dir: &Path) -> CapabilityStatus {
+        const MARKER: &str = "rs-mahito-doctor";
 
-        let epoch_time = FILETIME {
-            dwLowDateTime: (FILETIME_JAN_1_2000 & 0xFFFFFFFF) as u32,
-            dwHighDateTime: (FILETIME_JAN_1_2000 >> 32) as u32,
+        let path = dir.join("probe.docx");
+        let build = || -> zip::result::ZipResult<()> {
+            let mut writer = ZipWriter::new(File::create(&path)?);
+            writer.start_file("docProps/core.xml", SimpleFileOptions::default())?;
+            writer.write_all(format!("<cp:coreProperties><dc:creator>{}</dc:creator></cp:coreProperties>", MARKER).as_bytes())?;
+            writer.finish()?;
+            Ok(())
         };
+        if let Err(e) = build() {
+            return CapabilityStatus::Failed(format!("cannot create scratch document: {}", e));
+        }
 
-        unsafe {
-            let handle = HANDLE(file.as_raw_handle() as _);
-            SetFileTime(
-                handle,
-                Some(&epoch_time), // Creation time
-                Some(&epoch_time), // Last access time
-                Some(&epoch_time), // Last write time
-            )
-            .map_err(|e| CleanerError::windows_api_error(path, e.to_string()))?;
+        if let Err(e) = self.clear_office_xml_properties(&path) {
+            return CapabilityStatus::Failed(e.to_string());
         }
 
-        Ok(())
+        let cleaned = File::open(&path).ok()
+            .and_then(|file| ZipArchive::new(file).ok())
+            .and_then(|mut archive| {
+                let mut content = String::new();
+                archive.by_name("docProps/core.xml").ok()?.read_to_string(&mut content).ok()?;
+                Some(!content.contains(MARKER))
+            });
+
+        match cleaned {
+            Some(true) => CapabilityStatus::Available,
+            Some(false) => CapabilityStatus::Failed("author still present after cleaning".into()),
+            None => CapabilityStatus::Failed("cleaned document could not be read back".into()),
+        }
     }
 
-    #[cfg(not(windows))]
-    fn reset_timestamps(&self, path: &Path) -> CleanerResult<()> {
-        // On non-Windows systems, use filetime crate or similar
-        // For now, just touch the file
-        let now = SystemTime::now();
-        let file = OpenOptions::new()
-            .write(true)
-            .open(path)
-            .map_err(|e| CleanerError::cleaning_failed(path, e.to_string()))?;
+    /// Checks that identifying headers are removed from a generated .eml.
+    fn probe_email(&self, dir: &Path) -> CapabilityStatus {
+        let message = b"Received: from probe (192.0.2.1)\r\nFrom: probe@example.com\r\n\r\nbody\r\n";
+        let path = match Self::scratch_file(dir, "probe.eml", message) {
+            Ok(path) => path,
+            Err(status) => return status,
+        };
 
-        file.set_modified(now)
-            .map_err(|e| CleanerError::cleaning_failed(path, e.to_string()))?;
+        if let Err(e) = self.clean_email(&path) {
+            return CapabilityStatus::Failed(e.to_string());
+        }
 
-        Ok(())
+        match std::fs::read_to_string(&path) {
+            Ok(content) if !content.contains("192.0.2.1") => CapabilityStatus::Available,
+            Ok(_) => CapabilityStatus::Failed("Received header still present after cleaning".into()),
+            Err(e) => CapabilityStatus::Failed(e.to_string()),
+        }
     }
+}
 
-    /// Clears the file owner by setting it to the "Everyone" well-known SID.
-    /// This effectively anonymizes the file ownership.
-    #[cfg(windows)]
-    fn clear_owner(&self, path: &Path) -> CleanerResult<()> {
-        use windows::Win32::Foundation::HLOCAL;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        // Use "S-1-5-32-544" which is the "BUILTIN\Administrators" well-known SID
-        // This is a generic admin SID that doesn't identify any specific user
-        let sid_string: Vec<u16> = "S-1-5-32-544\0".encode_utf16().collect();
-        let wide_path: Vec<u16> = path.as_os_str()
-            .to_string_lossy()
-            .encode_utf16()
-            .chain(std::iter::once(0))
+    /// Creates an empty folder for one test under the system temp folder.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rs-mahito-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Lists the names of the files in a folder, sorted.
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
             .collect();
+        names.sort();
+        names
+    }
 
-        unsafe {
-            let mut sid: PSID = PSID::default();
+    #[test]
+    fn failed_replace_keeps_the_original_and_removes_the_temp_file() {
+        let dir = scratch_dir("failed-replace");
+        let file = dir.join("report.txt");
+        std::fs::write(&file, b"original").unwrap();
+        // A temp file left behind by an earlier, interrupted run (no process has ID 0)
+        let stale = sibling_path(&file, ".0-0", TEMP_SUFFIX);
+        std::fs::write(&stale, b"stale").unwrap();
 
-            // Convert the string SID to a binary SID
-            let result = ConvertStringSidToSidW(
-                PCWSTR(sid_string.as_ptr()),
-                &mut sid,
-            );
+        let result = write_replacement_with(&file, b"cleaned", |_, _| {
+            Err(std::io::Error::other("simulated rename failure"))
+        });
 
-            if result.is_err() {
-                return Err(CleanerError::windows_api_error(path, "Failed to convert SID string"));
-            }
+        assert!(result.is_err());
+        assert_eq!(std::fs::read(&file).unwrap(), b"original");
+        assert_eq!(std::fs::read(&stale).unwrap(), b"stale");
+        assert_eq!(file_names(&dir), [".report.txt.0-0.rs-mahito-tmp", "report.txt"]);
 
-            // Set the owner on the file using SetNamedSecurityInfoW
-            let result = SetNamedSecurityInfoW(
-                PCWSTR(wide_path.as_ptr()),
-                SE_FILE_OBJECT,
-                OWNER_SECURITY_INFORMATION,
-                sid,
-                PSID::default(),
-                None,
-                None,
-            );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 
-            // Free the SID memory allocated by ConvertStringSidToSidW
-            let _ = LocalFree(HLOCAL(sid.0));
+    #[test]
+    fn replace_ignores_a_stale_temp_file() {
+        let dir = scratch_dir("stale-temp");
+        let file = dir.join("report.txt");
+        std::fs::write(&file, b"original").unwrap();
+        // No process has ID 0, so this run can't pick the same name
+        let stale = sibling_path(&file, ".0-0", TEMP_SUFFIX);
+        std::fs::write(&stale, b"stale").unwrap();
 
-            // SetNamedSecurityInfoW returns WIN32_ERROR, ERROR_SUCCESS (0) means success
-            if result.0 != 0 {
-                return Err(CleanerError::windows_api_error(path, format!("Failed to set owner (error {}). Run as Administrator.", result.0)));
-            }
-        }
+        write_replacement(&file, b"cleaned").unwrap();
 
-        Ok(())
-    }
+        assert_eq!(std::fs::read(&file).unwrap(), b"cleaned");
+        assert_eq!(std::fs::read(&stale).unwrap(), b"stale");
 
-    #[cfg(not(windows))]
-    fn clear_owner(&self, _path: &Path) -> CleanerResult<()> {
-        // Owner clearing is Windows-specific
-        Ok(())
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 
-    /// Clears file properties stored in NTFS extended attributes and various streams.
-    /// This removes author, computer name, and other metadata from the Details tab.
-    #[cfg(windows)]
-    fn clear_properties(&self, path: &Path) -> CleanerResult<()> {
-        // Windows stores various metadata in alternate data streams:
-        // - Zone.Identifier: Downloaded file info (includes URL, computer info)
-        // - SummaryInformation: OLE document properties
-        // - DocumentSummaryInformation: Extended document properties
-        // - Afp_AfpInfo, encryptable, OECustomProperty, etc.
-
-        let streams_to_remove = [
-            "Zone.Identifier",
-            "\x05SummaryInformation",
-            "\x05DocumentSummaryInformation",
-            "Afp_AfpInfo",
-            "encryptable",
-            "OECustomProperty",
-        ];
+    #[test]
+    fn no_rewrite_starts_after_the_timeout() {
+        let dir = scratch_dir("deadline");
+        let file = dir.join("report.txt");
+        std::fs::write(&file, b"original").unwrap();
 
-        for stream_name in streams_to_remove {
-            let stream_path = format!("{}:{}", path.display(), stream_name);
-            let wide_path: Vec<u16> = stream_path.encode_utf16().chain(std::iter::once(0)).collect();
+        let deadline = Arc::new(Deadline::default());
+        let cleaner = MetadataCleaner { deadline: Some(Arc::clone(&deadline)), ..MetadataCleaner::new() };
+        cleaner.replace_contents(&file, b"first").unwrap();
+        deadline.expire();
 
-            unsafe {
-                // Attempt to delete the stream - ignore errors as the stream may not exist
-                let _ = DeleteFileW(PCWSTR(wide_path.as_ptr()));
-            }
-        }
+        assert!(matches!(cleaner.replace_contents(&file, b"second"), Err(CleanerError::TimedOut { .. })));
+        assert!(matches!(cleaner.check_deadline(&file), Err(CleanerError::TimedOut { .. })));
+        assert_eq!(std::fs::read(&file).unwrap(), b"first");
+        assert_eq!(file_names(&dir), ["report.txt"]);
 
-        Ok(())
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 
-    #[cfg(not(windows))]
-    fn clear_properties(&self, _path: &Path) -> CleanerResult<()> {
-        // Properties clearing is Windows-specific
-        Ok(())
+    #[test]
+    fn rewrite_lock_is_exclusive_and_removed_on_release() {
+        let dir = scratch_dir("rewrite-lock");
+        let file = dir.join("report.txt");
+        std::fs::write(&file, b"original").unwrap();
+
+        let lock = RewriteLock::acquire(&file).unwrap();
+        assert!(matches!(RewriteLock::acquire(&file), Err(CleanerError::FileLocked(_))));
+        drop(lock);
+
+        assert_eq!(file_names(&dir), ["report.txt"]);
+        assert!(RewriteLock::acquire(&file).is_ok());
+
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 
-    /// Clears embedded document properties from Office Open XML files (.docx, .xlsx, .pptx, etc.).
-    /// These files are ZIP archives containing XML metadata in docProps/core.xml and docProps/app.xml.
-    /// This function removes the "Owner", "Computer", "Author", "Last Modified By", etc. fields
-    /// that appear in Windows File Properties → Details tab.
-    fn clear_office_xml_properties(&self, path: &Path) -> CleanerResult<bool> {
-        // Check if this is an Office Open XML file by extension
-        let extension = path.extension()
-            .and_then(|e| e.to_str())
-            .map(|e| e.to_lowercase());
+    #[test]
+    fn removed_sidecar_is_not_the_same_file() {
+        let dir = scratch_dir("same-file");
+        let sidecar = dir.join("report.txt.lock");
+        let orphan = File::create(&sidecar).unwrap();
+        assert!(is_same_file(&orphan, &sidecar));
 
-        let is_office_xml = matches!(
-            extension.as_deref(),
-            Some("docx") | Some("xlsx") | Some("pptx") |
-            Some("docm") | Some("xlsm") | Some("pptm") |
-            Some("dotx") | Some("xltx") | Some("potx")
-        );
+        // Another instance released the lock and a third created a fresh sidecar
+        std::fs::remove_file(&sidecar).unwrap();
+        assert!(!is_same_file(&orphan, &sidecar));
+        let _fresh = File::create(&sidecar).unwrap();
+        assert!(!is_same_file(&orphan, &sidecar));
 
-        if !is_office_xml {
-            return Ok(false);
-        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 
-        // Try to open as a ZIP archive
-        let file = File::open(path)
-            .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to open file: {}", e)))?;
+    #[test]
+    fn in_place_rewrite_creates_nothing_next_to_the_file() {
+        let dir = scratch_dir("in-place");
+        let file = dir.join("report.docx");
+        office_document(&file, "Alice");
+        let before = same_file::Handle::from_path(&file).unwrap();
 
-        let mut archive = match ZipArchive::new(file) {
-            Ok(a) => a,
-            Err(_) => return Ok(false), // Not a valid ZIP/Office file
-        };
+        let cleaner = MetadataCleaner::with_options(CleanOptions::default().with_in_place(true));
+        let lock = cleaner.lock_for_rewrite(&file).unwrap();
+        assert!(matches!(cleaner.lock_for_rewrite(&file), Err(CleanerError::FileLocked(_))));
+        assert_eq!(file_names(&dir), ["report.docx"]);
+        drop(lock);
 
-        // Create a temporary file for the modified archive
-        let temp_path = path.with_extension("tmp_meta_clean");
-        let temp_file = File::create(&temp_path)
-            .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to create temp file: {}", e)))?;
+        assert!(cleaner.clear_office_xml_properties(&file).unwrap().is_some());
+        assert_eq!(file_names(&dir), ["report.docx"]);
+        assert!(same_file::Handle::from_path(&file).unwrap() == before);
 
-        let mut zip_writer = ZipWriter::new(temp_file);
-        let options = SimpleFileOptions::default()
-            .compression_method(zip::CompressionMethod::Deflated);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 
-        // Process each file in the archive
-        for i in 0..archive.len() {
-            let mut entry = archive.by_index(i)
-                .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to read archive entry: {}", e)))?;
+    /// Writes a minimal Office document whose core.xml names `creator`.
+    fn office_document(path: &Path, creator: &str) {
+        let mut writer = ZipWriter::new(File::create(path).unwrap());
+        writer.start_file("[Content_Types].xml", SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"<Types/>").unwrap();
+        writer.start_file("docProps/core.xml", SimpleFileOptions::default()).unwrap();
+        let core = format!("<cp:coreProperties><dc:creator>{}</dc:creator></cp:coreProperties>", creator);
+        writer.write_all(core.as_bytes()).unwrap();
+        writer.finish().unwrap();
+    }
 
-            let entry_name = entry.name().to_string();
+    #[test]
+    fn office_document_without_properties_is_left_untouched() {
+        let dir = scratch_dir("office-clean");
+        let file = dir.join("report.docx");
+        office_document(&file, "");
+        let before = std::fs::read(&file).unwrap();
 
-            // Handle docProps/core.xml - contains Author, Last Modified By, etc.
-            if entry_name == "docProps/core.xml" {
-                let mut content = String::new();
-                entry.read_to_string(&mut content)
-                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to read core.xml: {}", e)))?;
+        let cleared = MetadataCleaner::new().clear_office_xml_properties(&file).unwrap();
 
-                let cleaned_content = self.clean_core_xml(&content);
+        assert!(cleared.is_none());
+        assert_eq!(std::fs::read(&file).unwrap(), before);
+        assert_eq!(file_names(&dir), ["report.docx"]);
 
-                zip_writer.start_file(&entry_name, options)
-                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to write to archive: {}", e)))?;
-                zip_writer.write_all(cleaned_content.as_bytes())
-                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to write content: {}", e)))?;
-            }
-            // Handle docProps/app.xml - contains Application, Company (Computer), etc.
-            else if entry_name == "docProps/app.xml" {
-                let mut content = String::new();
-                entry.read_to_string(&mut content)
-                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to read app.xml: {}", e)))?;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 
-                let cleaned_content = self.clean_app_xml(&content);
+    #[test]
+    fn office_document_properties_are_cleared() {
+        let dir = scratch_dir("office-populated");
+        let file = dir.join("report.docx");
+        office_document(&file, "Alice");
 
-                zip_writer.start_file(&entry_name, options)
-                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to write to archive: {}", e)))?;
-                zip_writer.write_all(cleaned_content.as_bytes())
-                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to write content: {}", e)))?;
-            }
-            // Copy all other files unchanged
-            else {
-                let mut buffer = Vec::new();
-                entry.read_to_end(&mut buffer)
-                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to read entry: {}", e)))?;
+        let cleaner = MetadataCleaner::new();
+        let (_, cleared) = cleaner.clear_office_xml_properties(&file).unwrap().unwrap();
 
-                zip_writer.start_file(&entry_name, options)
-                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to write to archive: {}", e)))?;
-                zip_writer.write_all(&buffer)
-                    .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to write content: {}", e)))?;
-            }
-        }
+        assert_eq!(cleared, ["Author"]);
+        assert!(cleaner.clear_office_xml_properties(&file).unwrap().is_none());
 
-        // Finalize the ZIP
-        zip_writer.finish()
-            .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to finalize archive: {}", e)))?;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 
-        // Replace the original file with the cleaned version
-        std::fs::remove_file(path)
-            .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to remove original file: {}", e)))?;
-        std::fs::rename(&temp_path, path)
-            .map_err(|e| CleanerError::cleaning_failed(path, format!("Failed to rename temp file: {}", e)))?;
+    #[test]
+    fn stream_name_trims_one_prefix_and_suffix() {
+        assert_eq!(stream_name(":Zone.Identifier:$DATA"), "Zone.Identifier");
+        assert_eq!(stream_name(":a:b:$DATA"), "a:b");
+        assert_eq!(stream_name("::b::$DATA"), ":b:");
+        assert_eq!(stream_name("::$DATA"), "");
+        assert_eq!(stream_name("user.xdg.origin.url"), "user.xdg.origin.url");
+    }
 
-        Ok(true)
+    #[test]
+    fn stream_path_keeps_the_enumerated_specifier() {
+        let file = Path::new(r"C:\docs\report.docx");
+        assert_eq!(stream_path(file, ":a:b:$DATA"), PathBuf::from(r"C:\docs\report.docx:a:b:$DATA"));
+        assert_eq!(stream_path(file, "::$DATA"), PathBuf::from(r"C:\docs\report.docx::$DATA"));
     }
 
-    /// Cleans the docProps/core.xml file, removing author, last modified by, etc.
-    fn clean_core_xml(&self, content: &str) -> String {
-        let mut result = content.to_string();
-
-        // Elements to clear in core.xml (Dublin Core and CP namespaces)
-        let elements_to_clear = [
-            ("dc:creator", ""),           // Author
-            ("cp:lastModifiedBy", ""),    // Last Modified By
-            ("dc:title", ""),             // Title
-            ("dc:subject", ""),           // Subject
-            ("dc:description", ""),       // Comments
-            ("cp:keywords", ""),          // Keywords/Tags
-            ("cp:category", ""),          // Category
-            ("cp:contentStatus", ""),     // Content Status
-        ];
+    #[test]
+    fn unreadable_document_is_a_warning_in_a_dry_run() {
+        let dir = scratch_dir("plan-warning");
+        let file = dir.join("broken.docx");
+        // The unclosed element leaves docProps/core.xml unparseable
+        office_document(&file, "<unclosed>");
 
-        for (tag, replacement) in elements_to_clear {
-            // Match <tag>content</tag> or <tag attr="...">content</tag>
-            let pattern = format!(r"<{}[^>]*>.*?</{}>", regex_escape(tag), regex_escape(tag));
-            if let Ok(re) = regex_lite::Regex::new(&pattern) {
-                let new_tag = format!("<{}>{}</{}>", tag, replacement, tag);
-                result = re.replace_all(&result, new_tag.as_str()).to_string();
-            }
-        }
+        let cleaner = MetadataCleaner::with_options(CleanOptions::all().with_dry_run(true));
+        let result = cleaner.clean_file(&file).unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert!(result.warnings.iter().any(|w| w.starts_with("office-xml: ")), "{:?}", result.warnings);
+        assert!(result.planned_actions.iter().any(|a| a == "would reset timestamps"));
 
-        result
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 
-    /// Cleans the docProps/app.xml file, removing company (computer), manager, etc.
-    fn clean_app_xml(&self, content: &str) -> String {
-        let mut result = content.to_string();
-
-        // Elements to clear in app.xml
-        let elements_to_clear = [
-            ("Company", ""),              // Company/Computer name
-            ("Manager", ""),              // Manager
-            ("HyperlinkBase", ""),        // Hyperlink base
-        ];
+    #[test]
+    fn read_only_file_is_backed_up_before_it_is_made_writable() {
+        let dir = scratch_dir("readonly-backup");
+        let file = dir.join("report.txt");
+        std::fs::write(&file, b"original").unwrap();
+        let mut permissions = std::fs::metadata(&file).unwrap().permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&file, permissions).unwrap();
 
-        for (tag, replacement) in elements_to_clear {
-            // Match <tag>content</tag>
-            let pattern = format!(r"<{}[^>]*>.*?</{}>", tag, tag);
-            if let Ok(re) = regex_lite::Regex::new(&pattern) {
-                let new_tag = format!("<{}>{}</{}>", tag, replacement, tag);
-                result = re.replace_all(&result, new_tag.as_str()).to_string();
-            }
-        }
+        let backups = dir.join("backups");
+        let options = CleanOptions::all()
+            .with_clear_streams(false)
+            .with_backup_dir(Some(backups.clone()))
+            .with_preserve_readonly(true);
+        let cleaner = MetadataCleaner::with_options(options);
+        let result = cleaner.clean_file(&file).unwrap();
 
-        result
-    }
+        assert!(result.success, "{:?}", result.error);
+        let first_steps: Vec<&str> = result.steps.iter().take(2).map(|step| step.name.as_str()).collect();
+        assert_eq!(first_steps, ["backup", "readonly"]);
+        let (backup, original) = cleaner.find_backups(&backups, &file).unwrap().remove(0);
+        assert!(std::fs::metadata(&backup).unwrap().permissions().readonly());
 
-    /// Collects all files that would be processed.
-    pub fn collect_files(&self, path: &Path, mode: CleanMode) -> CleanerResult<Vec<PathBuf>> {
-        let path = path.canonicalize().map_err(|_| CleanerError::PathNotFound(path.to_path_buf()))?;
+        // Restoring onto the still read-only file puts the original back as it was
+        cleaner.restore_backup(&backup, &original).unwrap();
+        assert_eq!(std::fs::read(&file).unwrap(), b"original");
+        assert!(std::fs::metadata(&file).unwrap().permissions().readonly());
 
-        match mode {
-            CleanMode::SingleFile => {
-                if path.is_file() {
-                    Ok(vec![path])
-                } else {
-                    Err(CleanerError::NotAFile(path))
-                }
-            }
-            CleanMode::Shallow => {
-                if !path.is_dir() {
-                    return Err(CleanerError::NotADirectory(path));
-                }
-                Ok(WalkDir::new(&path)
-                    .min_depth(1)
-                    .max_depth(1)
-                    .into_iter()
-                    .filter_map(|e| e.ok())
-                    .filter(|e| e.path().is_file())
-                    .map(|e| e.path().to_path_buf())
-                    .collect())
-            }
-            CleanMode::Deep => {
-                if !path.is_dir() {
-                    return Err(CleanerError::NotADirectory(path));
-                }
-                Ok(WalkDir::new(&path)
-                    .min_depth(1)
-                    .into_iter()
-                    .filter_map(|e| e.ok())
-                    .filter(|e| e.path().is_file())
-                    .map(|e| e.path().to_path_buf())
-                    .collect())
-            }
-        }
+        make_writable(&file).unwrap();
+        make_writable(&backup).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,316 @@
+//! Identifying-header removal for saved emails.
+//!
+//! `.eml` files are plain RFC 822 messages whose header block carries `Received`
+//! chains with IP addresses, the sending client, and provider-specific tracking
+//! headers. `.msg` files are OLE compound files that store the same header block
+//! in the `PR_TRANSPORT_MESSAGE_HEADERS` property, so both share one filter.
+
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+
+use super::types::EmailHeaderClass;
+
+/// Headers describing the delivery path (IP addresses, relays, recipients' mailboxes).
+const ROUTING_HEADERS: &[&str] = &[
+    "received",
+    "x-received",
+    "return-path",
+    "delivered-to",
+    "x-originating-ip",
+    "x-sender-ip",
+    "x-forwarded-for",
+    "x-forwarded-to",
+    "x-apparently-to",
+    "received-spf",
+    "authentication-results",
+    "arc-seal",
+    "arc-message-signature",
+    "arc-authentication-results",
+    "dkim-signature",
+    "x-google-smtp-source",
+];
+
+/// Headers identifying the software (and often OS) that composed the message.
+const CLIENT_HEADERS: &[&str] = &[
+    "user-agent",
+    "x-mailer",
+    "x-mimeole",
+    "x-newsreader",
+    "x-originating-client",
+];
+
+/// Non-`X-` vendor headers that carry tracking identifiers.
+const VENDOR_HEADERS: &[&str] = &["thread-index"];
+
+/// Property ID + type of `PR_TRANSPORT_MESSAGE_HEADERS` (Unicode and ANSI variants).
+const MSG_HEADERS_UNICODE: &str = "__substg1.0_007D001F";
+const MSG_HEADERS_ANSI: &str = "__substg1.0_007D001E";
+
+/// Property stream of the top-level message, which records each stream's size.
+const MSG_PROPERTIES: &str = "__properties_version1.0";
+
+/// Size of the top-level message's property stream header.
+const MSG_PROPERTIES_HEADER_LEN: usize = 32;
+
+/// Returns the class a header belongs to, or `None` if it must be kept.
+fn classify(name: &str) -> Option<EmailHeaderClass> {
+    let name = name.trim().to_ascii_lowercase();
+
+    if ROUTING_HEADERS.contains(&name.as_str()) {
+        Some(EmailHeaderClass::Routing)
+    } else if CLIENT_HEADERS.contains(&name.as_str()) {
+        Some(EmailHeaderClass::Client)
+    } else if VENDOR_HEADERS.contains(&name.as_str()) || name.starts_with("x-") {
+        Some(EmailHeaderClass::Vendor)
+    } else {
+        None
+    }
+}
+
+/// Removes headers of the given classes from an RFC 822 message.
+///
+/// Only the top-level header block is touched; the body (including any MIME parts)
+/// is copied byte for byte. Returns the rewritten message and the number of
+/// headers removed.
+pub fn strip_headers(message: &[u8], classes: &[EmailHeaderClass]) -> (Vec<u8>, usize) {
+    let mut output = Vec::with_capacity(message.len());
+    let mut removed = 0;
+    let mut offset = 0;
+    let mut dropping = false;
+
+    while offset < message.len() {
+        let line_end = message[offset..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map(|i| offset + i + 1)
+            .unwrap_or(message.len());
+        let line = &message[offset..line_end];
+
+        // A blank line ends the header block; everything after is the body
+        if line == b"\r\n" || line == b"\n" {
+            break;
+        }
+
+        let is_continuation = matches!(line.first(), Some(b' ') | Some(b'\t'));
+
+        if !is_continuation {
+            let name = line
+                .iter()
+                .position(|&b| b == b':')
+                .map(|colon| String::from_utf8_lossy(&line[..colon]).into_owned());
+
+            dropping = name
+                .and_then(|name| classify(&name))
+                .is_some_and(|class| classes.contains(&class));
+
+            if dropping {
+                removed += 1;
+            }
+        }
+
+        // Folded continuation lines follow the fate of their header
+        if !dropping {
+            output.extend_from_slice(line);
+        }
+
+        offset = line_end;
+    }
+
+    output.extend_from_slice(&message[offset..]);
+    (output, removed)
+}
+
+/// Transport headers read from a `.msg` file.
+struct MsgHeaders {
+    /// Stream holding the headers (Unicode or ANSI variant).
+    stream_name: &'static str,
+    /// Whether the stream is UTF-16LE.
+    unicode: bool,
+    /// Raw stream bytes.
+    raw: Vec<u8>,
+    /// Headers converted to UTF-8 (or left as-is for ANSI).
+    text: Vec<u8>,
+}
+
+/// Reads `PR_TRANSPORT_MESSAGE_HEADERS` from an open compound file, if present.
+fn read_msg_headers<F: Read + std::io::Seek>(
+    compound: &mut cfb::CompoundFile<F>,
+) -> std::io::Result<Option<MsgHeaders>> {
+    let (stream_name, unicode) = if compound.is_stream(format!("/{}", MSG_HEADERS_UNICODE)) {
+        (MSG_HEADERS_UNICODE, true)
+    } else if compound.is_stream(format!("/{}", MSG_HEADERS_ANSI)) {
+        (MSG_HEADERS_ANSI, false)
+    } else {
+        return Ok(None);
+    };
+
+    let mut raw = Vec::new();
+    compound.open_stream(format!("/{}", stream_name))?.read_to_end(&mut raw)?;
+
+    let text = if unicode {
+        let units: Vec<u16> = raw
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units).into_bytes()
+    } else {
+        raw.clone()
+    };
+
+    Ok(Some(MsgHeaders {
+        stream_name,
+        unicode,
+        raw,
+        text,
+    }))
+}
+
+/// Counts the headers [`strip_msg_headers`] would remove, without opening the file for writing.
+pub fn count_msg_headers(path: &Path, classes: &[EmailHeaderClass]) -> std::io::Result<Option<usize>> {
+    let mut compound = match cfb::open(path) {
+        Ok(compound) => compound,
+        Err(_) => return Ok(None),
+    };
+
+    Ok(read_msg_headers(&mut compound)?.map(|headers| strip_headers(&headers.text, classes).1))
+}
+
+/// Filters the transport headers stored inside an Outlook `.msg` file held in memory.
+///
+/// The compound file is edited in memory, so the caller can write it back
+/// atomically. Returns `Ok(None)` if it isn't a compound file or has no stored
+/// headers, otherwise the message, rewritten if anything was removed, and the
+/// number of headers removed.
+pub fn strip_msg_headers(message: Vec<u8>, classes: &[EmailHeaderClass]) -> std::io::Result<Option<(Vec<u8>, usize)>> {
+    let mut compound = match cfb::CompoundFile::open(Cursor::new(message)) {
+        Ok(compound) => compound,
+        Err(_) => return Ok(None),
+    };
+
+    let Some(headers) = read_msg_headers(&mut compound)? else {
+        return Ok(None);
+    };
+
+    let (filtered, removed) = strip_headers(&headers.text, classes);
+    if removed == 0 {
+        return Ok(Some((compound.into_inner().into_inner(), 0)));
+    }
+
+    let encoded: Vec<u8> = if headers.unicode {
+        String::from_utf8_lossy(&filtered)
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect()
+    } else {
+        filtered
+    };
+
+    compound.create_stream(format!("/{}", headers.stream_name))?.write_all(&encoded)?;
+
+    // Variable-length properties record their size in the property stream, which
+    // must keep matching the stream we just shortened
+    let tag = u32::from_str_radix(&headers.stream_name["__substg1.0_".len()..], 16).unwrap_or_default();
+    let delta = headers.raw.len() as i64 - encoded.len() as i64;
+    update_property_size(&mut compound, tag, delta)?;
+
+    compound.flush()?;
+    Ok(Some((compound.into_inner().into_inner(), removed)))
+}
+
+/// Adjusts the recorded size of a variable-length property by `-delta` bytes.
+fn update_property_size<F: Read + Write + std::io::Seek>(
+    compound: &mut cfb::CompoundFile<F>,
+    tag: u32,
+    delta: i64,
+) -> std::io::Result<()> {
+    let properties_path = format!("/{}", MSG_PROPERTIES);
+    if !compound.is_stream(&properties_path) {
+        return Ok(());
+    }
+
+    let mut properties = Vec::new();
+    compound.open_stream(&properties_path)?.read_to_end(&mut properties)?;
+
+    // Each entry: tag (4), flags (4), size (4), reserved (4)
+    let mut changed = false;
+    for entry in properties
+        .get_mut(MSG_PROPERTIES_HEADER_LEN..)
+        .unwrap_or_default()
+        .chunks_exact_mut(16)
+    {
+        let entry_tag = u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]);
+        if entry_tag == tag {
+            let size = u32::from_le_bytes([entry[8], entry[9], entry[10], entry[11]]) as i64;
+            let new_size = (size - delta).max(0) as u32;
+            entry[8..12].copy_from_slice(&new_size.to_le_bytes());
+            changed = true;
+        }
+    }
+
+    if changed {
+        compound.create_stream(&properties_path)?.write_all(&properties)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_CLASSES: &[EmailHeaderClass] = &[EmailHeaderClass::Routing, EmailHeaderClass::Client, EmailHeaderClass::Vendor];
+
+    /// Builds a `.msg` compound file storing `headers` as UTF-16 transport headers,
+    /// with a property stream recording their size.
+    fn msg_file(headers: &str) -> Vec<u8> {
+        let encoded: Vec<u8> = headers.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        let mut properties = vec![0; MSG_PROPERTIES_HEADER_LEN];
+        properties.extend_from_slice(&0x007D_001Fu32.to_le_bytes());
+        properties.extend_from_slice(&0u32.to_le_bytes());
+        properties.extend_from_slice(&(encoded.len() as u32).to_le_bytes());
+        properties.extend_from_slice(&0u32.to_le_bytes());
+
+        let mut compound = cfb::CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        compound.create_stream(format!("/{}", MSG_HEADERS_UNICODE)).unwrap().write_all(&encoded).unwrap();
+        compound.create_stream(format!("/{}", MSG_PROPERTIES)).unwrap().write_all(&properties).unwrap();
+        compound.flush().unwrap();
+        compound.into_inner().into_inner()
+    }
+
+    /// Reads the transport headers and their recorded size back from a `.msg` file.
+    fn msg_headers(message: Vec<u8>) -> (String, u32) {
+        let mut compound = cfb::CompoundFile::open(Cursor::new(message)).unwrap();
+        let headers = read_msg_headers(&mut compound).unwrap().unwrap();
+        let mut properties = Vec::new();
+        compound.open_stream(format!("/{}", MSG_PROPERTIES)).unwrap().read_to_end(&mut properties).unwrap();
+        let size = &properties[MSG_PROPERTIES_HEADER_LEN + 8..MSG_PROPERTIES_HEADER_LEN + 12];
+        (String::from_utf8(headers.text).unwrap(), u32::from_le_bytes(size.try_into().unwrap()))
+    }
+
+    #[test]
+    fn msg_headers_are_filtered_and_their_size_updated() {
+        let message = msg_file("Received: from relay\r\nSubject: Hi\r\nX-Mailer: Outlook\r\n\r\n");
+
+        let (cleaned, removed) = strip_msg_headers(message, ALL_CLASSES).unwrap().unwrap();
+
+        assert_eq!(removed, 2);
+        let kept = "Subject: Hi\r\n\r\n";
+        assert_eq!(msg_headers(cleaned), (kept.to_string(), 2 * kept.len() as u32));
+    }
+
+    #[test]
+    fn msg_without_identifying_headers_is_returned_unchanged() {
+        let message = msg_file("Subject: Hi\r\n\r\n");
+
+        let (unchanged, removed) = strip_msg_headers(message.clone(), ALL_CLASSES).unwrap().unwrap();
+
+        assert_eq!(removed, 0);
+        assert_eq!(unchanged, message);
+    }
+
+    #[test]
+    fn non_compound_data_is_not_a_msg() {
+        assert!(strip_msg_headers(b"Received: from relay\r\n\r\n".to_vec(), ALL_CLASSES).unwrap().is_none());
+        assert!(strip_msg_headers(Vec::new(), ALL_CLASSES).unwrap().is_none());
+    }
+}
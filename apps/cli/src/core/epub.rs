@@ -0,0 +1,199 @@
+//! Metadata removal for EPUB e-books.
+//!
+//! An EPUB is a ZIP archive whose `META-INF/container.xml` names the package
+//! document (usually `content.opf`). Its `<metadata>` block holds Dublin Core
+//! fields such as the creator, publisher and date, and calibre adds `<meta
+//! name="calibre:*">` entries (library timestamp, series, user ratings). The
+//! identity fields are emptied in place and the calibre entries dropped; the
+//! title, identifier and language the format requires are kept, and every
+//! other event is written back unchanged.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, Writer};
+
+/// Media type of the package document in `container.xml`.
+const PACKAGE_MEDIA_TYPE: &[u8] = b"application/oebps-package+xml";
+
+/// Dublin Core elements emptied from the package metadata, with their labels.
+const DC_FIELDS: &[(&[u8], &str)] = &[
+    (b"creator", "Creator"),
+    (b"contributor", "Contributor"),
+    (b"publisher", "Publisher"),
+    (b"date", "Date"),
+    (b"rights", "Rights"),
+];
+
+/// Returns the attribute's unescaped value, if the element has it.
+fn attribute(start: &BytesStart, name: &[u8]) -> Option<String> {
+    start
+        .attributes()
+        .flatten()
+        .find(|attribute| attribute.key.local_name().as_ref() == name)
+        .and_then(|attribute| attribute.unescape_value().ok().map(|value| value.into_owned()))
+}
+
+/// Returns the archive path of the package document named in `container.xml`.
+pub fn package_path(container: &str) -> Option<String> {
+    let mut reader = Reader::from_str(container);
+    let mut first = None;
+
+    loop {
+        match reader.read_event().ok()? {
+            Event::Start(start) | Event::Empty(start) if start.name().local_name().as_ref() == b"rootfile" => {
+                let Some(path) = attribute(&start, b"full-path") else {
+                    continue;
+                };
+                if attribute(&start, b"media-type").is_some_and(|media| media.as_bytes() == PACKAGE_MEDIA_TYPE) {
+                    return Some(path);
+                }
+                first.get_or_insert(path);
+            }
+            Event::Eof => return first,
+            _ => {}
+        }
+    }
+}
+
+/// Returns the label of a Dublin Core identity field, e.g. `Creator` for `<dc:creator>`.
+fn dc_field(start: &BytesStart) -> Option<&'static str> {
+    let name = start.name();
+    if name.prefix().is_none_or(|prefix| prefix.as_ref() != b"dc") {
+        return None;
+    }
+    DC_FIELDS
+        .iter()
+        .find(|(local, _)| *local == name.local_name().as_ref())
+        .map(|(_, label)| *label)
+}
+
+/// Returns the label of a `<meta>` dropped whole: calibre entries and EPUB 3
+/// `file-as` refinements, which spell out the author's sort name.
+fn dropped_meta(start: &BytesStart) -> Option<String> {
+    if start.name().local_name().as_ref() != b"meta" {
+        return None;
+    }
+    if let Some(name) = attribute(start, b"name").filter(|name| name.starts_with("calibre:")) {
+        return Some(name);
+    }
+    attribute(start, b"property").filter(|property| property == "file-as").map(|_| "file-as".to_string())
+}
+
+/// Copies a Dublin Core element without its `opf:file-as` attribute (the sort name).
+fn without_file_as<'a>(start: &BytesStart<'a>) -> Option<(BytesStart<'a>, bool)> {
+    let mut cleaned = start.clone();
+    cleaned.clear_attributes();
+
+    let mut removed = false;
+    for attribute in start.attributes() {
+        let attribute = attribute.ok()?;
+        if attribute.key.local_name().as_ref() == b"file-as" {
+            removed = true;
+        } else {
+            cleaned.push_attribute(attribute);
+        }
+    }
+    Some((cleaned, removed))
+}
+
+/// Rewrites a package document without its identity fields and calibre entries,
+/// returning the new text and labels of what was removed.
+///
+/// Returns `None` if the text isn't well-formed XML with a `<package>` root.
+fn rewrite(text: &str) -> Option<(String, Vec<String>)> {
+    let mut reader = Reader::from_str(text);
+    let mut writer = Writer::new(Vec::with_capacity(text.len()));
+    let mut removed: Vec<String> = Vec::new();
+    let mut seen_root = false;
+    // Elements open at the current position, to reject truncated documents
+    let mut open = 0usize;
+    // Label and nesting depth of a field being emptied, or depth of a meta being dropped
+    let mut clearing: Option<(Option<&'static str>, usize)> = None;
+
+    let mut note = |label: String| {
+        if !removed.contains(&label) {
+            removed.push(label);
+        }
+    };
+
+    loop {
+        let event = reader.read_event().ok()?;
+
+        if let Event::Start(start) | Event::Empty(start) = &event {
+            if !seen_root {
+                if start.name().local_name().as_ref() != b"package" {
+                    return None;
+                }
+                seen_root = true;
+            }
+        }
+        match &event {
+            Event::Start(_) => open += 1,
+            Event::End(_) => open = open.checked_sub(1)?,
+            _ => {}
+        }
+
+        match (&mut clearing, event) {
+            (_, Event::Eof) => break,
+            (Some((_, depth)), Event::Start(_)) => *depth += 1,
+            (Some((field, 0)), Event::End(end)) => {
+                // Emptied fields keep their element; dropped metas lose it
+                if field.is_some() {
+                    writer.write_event(Event::End(end)).ok()?;
+                }
+                clearing = None;
+            }
+            (Some((_, depth)), Event::End(_)) => *depth -= 1,
+            (Some((Some(label), _)), Event::Text(text)) => {
+                if !text.decode().ok()?.trim().is_empty() {
+                    note(label.to_string());
+                }
+            }
+            (Some((Some(label), _)), Event::CData(_) | Event::GeneralRef(_)) => note(label.to_string()),
+            (Some(_), _) => {}
+            (None, Event::Start(start)) => {
+                if let Some(meta) = dropped_meta(&start) {
+                    note(meta);
+                    clearing = Some((None, 0));
+                } else if let Some(label) = dc_field(&start) {
+                    let (start, had_file_as) = without_file_as(&start)?;
+                    if had_file_as {
+                        note(label.to_string());
+                    }
+                    writer.write_event(Event::Start(start)).ok()?;
+                    clearing = Some((Some(label), 0));
+                } else {
+                    writer.write_event(Event::Start(start)).ok()?;
+                }
+            }
+            (None, Event::Empty(start)) => {
+                if let Some(meta) = dropped_meta(&start) {
+                    note(meta);
+                } else {
+                    writer.write_event(Event::Empty(start)).ok()?;
+                }
+            }
+            (None, event) => writer.write_event(event).ok()?,
+        }
+    }
+
+    if !seen_root || open != 0 {
+        return None;
+    }
+
+    Some((String::from_utf8_lossy(&writer.into_inner()).into_owned(), removed))
+}
+
+/// Returns labels for the identity fields and calibre entries a package document
+/// carries, e.g. `["Creator", "calibre:timestamp"]`, or `None` if it isn't valid.
+pub fn package_metadata(text: &str) -> Option<Vec<String>> {
+    rewrite(text).map(|(_, removed)| removed)
+}
+
+/// Empties the Dublin Core identity fields and drops calibre entries, returning
+/// the new text and labels of what was removed.
+///
+/// Returns `None` if the package document is invalid or has nothing to remove,
+/// in which case the original should be kept.
+pub fn strip_package_metadata(text: &str) -> Option<(String, Vec<String>)> {
+    rewrite(text).filter(|(_, removed)| !removed.is_empty())
+}
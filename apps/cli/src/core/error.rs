@@ -50,6 +50,43 @@ pub enum CleanerError {
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
 
+    /// Another instance is currently rewriting the file.
+    #[error("File is being cleaned by another rs-mahito instance: {0}")]
+    FileLocked(PathBuf),
+
+    /// Another process has the file open without sharing it, or has locked a region of it.
+    #[error("File is in use by another process: {0}")]
+    FileInUse(PathBuf),
+
+    /// The worker thread pool could not be created.
+    #[error("Failed to start worker threads: {0}")]
+    ThreadPool(#[from] rayon::ThreadPoolBuildError),
+
+    /// An `--include`/`--exclude` pattern is not a valid glob.
+    #[error("Invalid glob pattern: {0}")]
+    InvalidGlob(#[from] globset::Error),
+
+    /// The file has no alternate data stream with the requested name.
+    #[error("No stream named '{name}' on '{path}'")]
+    StreamNotFound { path: PathBuf, name: String },
+
+    /// An owner SID is not a valid string SID.
+    #[error("Invalid SID '{0}' (expected a string SID such as S-1-1-0)")]
+    InvalidSid(String),
+
+    /// Cleaning a file took longer than the configured timeout.
+    #[error("Timed out after {timeout:?} cleaning '{path}'")]
+    TimedOut { path: PathBuf, timeout: std::time::Duration },
+
+    /// In-memory data passed to a `clean_*_bytes` method isn't in the expected format.
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    /// The file has the extension of a supported format, but its contents can't
+    /// be parsed as that format (corrupt, encrypted or an unusual variant).
+    #[error("Not a valid {format} file, or a variant that can't be cleaned: {path}")]
+    UnsupportedFormat { path: PathBuf, format: &'static str },
+
     /// Failed to enumerate alternate data streams.
     #[error("Failed to enumerate data streams for '{0}'")]
     StreamEnumerationFailed(PathBuf),
@@ -64,6 +101,29 @@ impl CleanerError {
         }
     }
 
+    /// Creates an error for a file that isn't the `format` its extension claims.
+    pub fn unsupported_format(path: impl Into<PathBuf>, format: &'static str) -> Self {
+        Self::UnsupportedFormat {
+            path: path.into(),
+            format,
+        }
+    }
+
+    /// Creates an error for a failed file operation, reporting sharing and lock
+    /// violations as [`CleanerError::FileInUse`] rather than a generic failure.
+    pub fn file_operation(path: impl Into<PathBuf>, context: &str, error: std::io::Error) -> Self {
+        if is_sharing_violation(&error) {
+            Self::FileInUse(path.into())
+        } else {
+            Self::cleaning_failed(path, format!("{}: {}", context, error))
+        }
+    }
+
+    /// Returns true if the file was held by another process, so trying again later may succeed.
+    pub fn is_in_use(&self) -> bool {
+        matches!(self, Self::FileInUse(_) | Self::FileLocked(_))
+    }
+
     /// Creates a new Windows API error.
     pub fn windows_api_error(path: impl Into<PathBuf>, message: impl Into<String>) -> Self {
         Self::WindowsApiError {
@@ -72,3 +132,23 @@ impl CleanerError {
         }
     }
 }
+
+/// `ERROR_SHARING_VIOLATION`: the file is open elsewhere without the needed share mode.
+#[cfg(windows)]
+const ERROR_SHARING_VIOLATION: i32 = 32;
+
+/// `ERROR_LOCK_VIOLATION`: another process has locked a region of the file.
+#[cfg(windows)]
+const ERROR_LOCK_VIOLATION: i32 = 33;
+
+/// Returns true if an I/O error means another process holds the file.
+#[cfg(windows)]
+pub(crate) fn is_sharing_violation(error: &std::io::Error) -> bool {
+    matches!(error.raw_os_error(), Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION))
+}
+
+/// Files are never opened exclusively outside Windows, so there's nothing to detect.
+#[cfg(not(windows))]
+pub(crate) fn is_sharing_violation(_error: &std::io::Error) -> bool {
+    false
+}
@@ -0,0 +1,836 @@
+//! Registry of format-specific metadata cleaners.
+//!
+//! Each format (Office documents, e-books, emails, images, XMP sidecars, camera RAW, audio, PDFs, shortcuts) is a
+//! [`FormatCleaner`] chosen by the file's signature, or by its extension where
+//! the signature doesn't settle it (see [`super::magic`]). `MetadataCleaner` runs every
+//! entry of [`FORMAT_CLEANERS`] in order, recording one step per format, so a
+//! new format only needs an implementation here and a place in that list.
+
+use std::path::Path;
+
+use super::audio::AudioKind;
+use super::cleaner::{ImageKind, MetadataCleaner};
+use super::error::CleanerResult;
+use super::magic::Signature;
+use super::types::{CleanerStep, RemainingLeak, SizeChange};
+
+/// What a format cleaner did to a file of its format.
+#[derive(Debug)]
+pub enum CleanOutcome {
+    /// Metadata was removed.
+    Cleaned {
+        /// Step detail, e.g. "EXIF removed".
+        detail: String,
+        /// Labels of the cleared property fields, e.g. "Author".
+        properties: Vec<String>,
+        /// File size before and after the rewrite, when the cleaner tracks it.
+        size: Option<SizeChange>,
+        /// Archive entries that were cleaned, e.g. "photos.zip/trip.jpg (exif)".
+        entries: Vec<String>,
+        /// Side effects worth flagging, e.g. a digital signature the rewrite invalidated.
+        warnings: Vec<String>,
+    },
+    /// The cleaner had nothing to do: the file carried nothing to remove, or the
+    /// cleaner doesn't apply to it as configured. The reason is recorded as the
+    /// skipped step's detail. Files that can't be parsed as their format fail
+    /// with [`CleanerError::UnsupportedFormat`](super::error::CleanerError::UnsupportedFormat) instead.
+    NotApplicable(&'static str),
+    /// The file has metadata but was left untouched on purpose, e.g. to keep a
+    /// digital signature valid; the reason is recorded as a warning too.
+    Skipped(String),
+}
+
+impl CleanOutcome {
+    /// Creates a `Cleaned` outcome with no property labels or size change.
+    fn cleaned(detail: impl Into<String>) -> Self {
+        CleanOutcome::Cleaned {
+            detail: detail.into(),
+            properties: Vec::new(),
+            size: None,
+            entries: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Maps a cleaner's "did anything change" flag to an outcome.
+    fn from_changed(changed: bool, detail: &str, not_applicable: &'static str) -> Self {
+        if changed { CleanOutcome::cleaned(detail) } else { CleanOutcome::NotApplicable(not_applicable) }
+    }
+}
+
+/// A cleaner for one family of file formats, chosen by content and extension.
+pub trait FormatCleaner: Sync {
+    /// Step name recorded in reports, and category of the leaks [`verify`](Self::verify)
+    /// finds, e.g. "png".
+    fn name(&self) -> &'static str;
+
+    /// Returns true if files with this lowercase extension are handled.
+    fn handles_extension(&self, extension: &str) -> bool;
+
+    /// Skipped-step detail for files this cleaner doesn't handle, e.g. "not a PNG image".
+    fn not_handled(&self) -> &'static str;
+
+    /// Describes the files [`verify`](Self::verify) flags, for scan summaries,
+    /// e.g. "PNGs with text chunks".
+    fn description(&self) -> &'static str;
+
+    /// Removes the format's metadata from a file it handles.
+    fn clean(&self, cleaner: &MetadataCleaner, path: &Path) -> CleanerResult<CleanOutcome>;
+
+    /// Inspects a file it handles, sniffed as `signature`, for what [`clean`](Self::clean)
+    /// would remove, without modifying it.
+    fn plan(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> PlannedStep;
+
+    /// Re-inspects a file sniffed as `signature`, returning the metadata of this
+    /// format that cleaning should have removed. Files of other formats have none.
+    fn verify(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak>;
+
+    /// Returns false for formats [`verify`](Self::verify) doesn't actually check,
+    /// which aren't reported as verified categories.
+    fn is_verified(&self) -> bool {
+        true
+    }
+
+    /// Returns false for formats with no signature [`Signature`] recognizes,
+    /// which are chosen by extension alone.
+    fn has_signature(&self) -> bool {
+        true
+    }
+
+    /// Returns true if content with `signature` and this lowercase extension is
+    /// handled: by the signature if there is one, otherwise by extension for
+    /// formats without one.
+    fn handles(&self, signature: Option<Signature>, extension: &str) -> bool {
+        match signature {
+            Some(signature) => signature_owner(signature, extension) == Some(self.name()),
+            None => !self.has_signature() && self.handles_extension(extension),
+        }
+    }
+
+    /// Returns true if the file at `path`, already sniffed as `signature`, is handled.
+    fn handles_path(&self, path: &Path, signature: Option<Signature>) -> bool {
+        let extension = path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+
+        self.handles(signature, &extension)
+    }
+}
+
+/// Returns the name of the format cleaner for content with `signature`, or
+/// `None` if no cleaner handles that format. The lowercase `extension` settles
+/// signatures several formats share.
+fn signature_owner(signature: Signature, extension: &str) -> Option<&'static str> {
+    let cleaner: &dyn FormatCleaner = match signature {
+        Signature::OfficeXml => &OfficeXmlCleaner,
+        Signature::Epub => &EpubCleaner,
+        // Writers don't always put the telltale entry first
+        Signature::Zip if OfficeXmlCleaner.handles_extension(extension) => &OfficeXmlCleaner,
+        Signature::Zip if EpubCleaner.handles_extension(extension) => &EpubCleaner,
+        Signature::Zip => &ZipCleaner,
+        Signature::Jpeg => &ExifCleaner,
+        Signature::Tiff if RawCleaner.handles_extension(extension) => &RawCleaner,
+        Signature::Tiff => &ExifCleaner,
+        Signature::Cr2 => &RawCleaner,
+        Signature::Png => &PngCleaner,
+        Signature::Gif => &GifCleaner,
+        Signature::Heif => &HeifCleaner,
+        Signature::M4a | Signature::Mp3 | Signature::Flac => &AudioCleaner,
+        Signature::IsoMedia if AudioCleaner.handles_extension(extension) => &AudioCleaner,
+        Signature::IsoMedia => return None,
+        Signature::Wave => &WavCleaner,
+        Signature::Pdf => &PdfCleaner,
+        Signature::ShellLink => &LnkCleaner,
+    };
+    Some(cleaner.name())
+}
+
+/// A dry run's step for one format, with the action shown to the user when
+/// something would be removed.
+pub type PlannedStep = (CleanerStep, Option<String>);
+
+/// Plans a step from the labels an inspection found: planned with `action` if
+/// there are any, otherwise skipped as `none`, or as `unreadable` if the file
+/// couldn't be parsed.
+fn plan_findings(
+    name: &str,
+    found: CleanerResult<Option<Vec<String>>>,
+    none: &str,
+    unreadable: &str,
+    action: impl FnOnce(&[String]) -> String,
+) -> PlannedStep {
+    match found {
+        Ok(Some(found)) if found.is_empty() => (CleanerStep::skipped(name, none), None),
+        Ok(Some(found)) => (CleanerStep::planned(name, found.join(", ")), Some(action(&found))),
+        Ok(None) => (CleanerStep::skipped(name, unreadable), None),
+        Err(e) => (CleanerStep::failed(name, e.to_string()), None),
+    }
+}
+
+/// Every format cleaner, in the order they run on a file.
+pub static FORMAT_CLEANERS: &[&dyn FormatCleaner] = &[
+    &OfficeXmlCleaner,
+    &EpubCleaner,
+    &EmailCleaner,
+    &ExifCleaner,
+    &PngCleaner,
+    &GifCleaner,
+    &SvgCleaner,
+    &XmpCleaner,
+    &HeifCleaner,
+    &RawCleaner,
+    &AudioCleaner,
+    &WavCleaner,
+    &PdfCleaner,
+    &LnkCleaner,
+    &ZipCleaner,
+];
+
+/// Clears docProps fields (author, company, last modified by) from Office Open XML files.
+pub struct OfficeXmlCleaner;
+
+impl FormatCleaner for OfficeXmlCleaner {
+    fn name(&self) -> &'static str {
+        "office-xml"
+    }
+
+    fn handles_extension(&self, extension: &str) -> bool {
+        matches!(
+            extension,
+            "docx" | "xlsx" | "pptx" | "docm" | "xlsm" | "pptm" | "dotx" | "xltx" | "potx"
+        )
+    }
+
+    fn not_handled(&self) -> &'static str {
+        "not an Office document"
+    }
+
+    fn description(&self) -> &'static str {
+        "Office docs with authorship"
+    }
+
+    fn clean(&self, cleaner: &MetadataCleaner, path: &Path) -> CleanerResult<CleanOutcome> {
+        // Any rewrite of the package breaks its signature, so that takes --force
+        let signed = cleaner.is_signed_office_document(path)?;
+        if signed && !cleaner.options().force {
+            return Ok(CleanOutcome::Skipped(
+                "digitally signed; cleaning would invalidate the signature (use --force to clean anyway)".to_string(),
+            ));
+        }
+
+        Ok(match cleaner.clear_office_xml_properties(path)? {
+            Some((size, fields)) => CleanOutcome::Cleaned {
+                detail: if signed {
+                    format!("docProps cleared, {}, signature invalidated", size)
+                } else {
+                    format!("docProps cleared, {}", size)
+                },
+                properties: fields,
+                size: Some(size),
+                entries: Vec::new(),
+                warnings: if signed { vec!["digital signature invalidated (--force)".to_string()] } else { Vec::new() },
+            },
+            None => CleanOutcome::NotApplicable("no docProps fields"),
+        })
+    }
+
+    fn plan(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> PlannedStep {
+        match cleaner.inspect_office_properties(path, signature) {
+            Ok(Some(fields)) if fields.is_empty() => (CleanerStep::skipped(self.name(), "no populated fields"), None),
+            Ok(Some(fields)) if cleaner.is_signed_office_document(path).unwrap_or(false) && !cleaner.options().force => {
+                let action = format!(
+                    "would keep {} docProps fields ({}): the document is digitally signed (use --force to clean anyway)",
+                    fields.len(),
+                    fields.join(", ")
+                );
+                (CleanerStep::skipped(self.name(), "digitally signed"), Some(action))
+            }
+            Ok(Some(fields)) => {
+                let action = format!("would strip {} docProps fields ({})", fields.len(), fields.join(", "));
+                (CleanerStep::planned(self.name(), fields.join(", ")), Some(action))
+            }
+            Ok(None) => (CleanerStep::skipped(self.name(), "not a valid Office document"), None),
+            Err(e) => (CleanerStep::failed(self.name(), e.to_string()), None),
+        }
+    }
+
+    fn verify(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        cleaner.verify_office_xml(path, signature)
+    }
+}
+
+/// Empties creator, publisher and date fields and drops calibre entries from EPUB package documents.
+pub struct EpubCleaner;
+
+impl FormatCleaner for EpubCleaner {
+    fn name(&self) -> &'static str {
+        "epub"
+    }
+
+    fn handles_extension(&self, extension: &str) -> bool {
+        extension == "epub"
+    }
+
+    fn not_handled(&self) -> &'static str {
+        "not an EPUB"
+    }
+
+    fn description(&self) -> &'static str {
+        "EPUBs with creator details"
+    }
+
+    fn clean(&self, cleaner: &MetadataCleaner, path: &Path) -> CleanerResult<CleanOutcome> {
+        Ok(match cleaner.clean_epub(path)? {
+            Some(fields) => CleanOutcome::Cleaned {
+                detail: "package metadata cleared".to_string(),
+                properties: fields.into_iter().map(|field| format!("EPUB {}", field)).collect(),
+                size: None,
+                entries: Vec::new(),
+                warnings: Vec::new(),
+            },
+            None => CleanOutcome::NotApplicable("no metadata"),
+        })
+    }
+
+    fn plan(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> PlannedStep {
+        plan_findings(self.name(), cleaner.inspect_epub(path, signature), "no metadata", "not a valid EPUB", |found| format!("would clear EPUB metadata ({})", found.join(", ")))
+    }
+
+    fn verify(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        cleaner.verify_epub(path, signature)
+    }
+}
+
+/// Strips Received chains, originating IPs and mail client headers from saved emails.
+pub struct EmailCleaner;
+
+impl FormatCleaner for EmailCleaner {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn handles_extension(&self, extension: &str) -> bool {
+        matches!(extension, "eml" | "msg")
+    }
+
+    fn not_handled(&self) -> &'static str {
+        "not an email"
+    }
+
+    fn description(&self) -> &'static str {
+        "emails with routing or client headers"
+    }
+
+    fn has_signature(&self) -> bool {
+        false
+    }
+
+    fn clean(&self, cleaner: &MetadataCleaner, path: &Path) -> CleanerResult<CleanOutcome> {
+        Ok(match cleaner.clean_email(path)? {
+            Some(0) => CleanOutcome::NotApplicable("no identifying headers"),
+            Some(removed) => CleanOutcome::cleaned(format!("{} headers removed", removed)),
+            None => CleanOutcome::NotApplicable("not an email"),
+        })
+    }
+
+    fn plan(&self, cleaner: &MetadataCleaner, path: &Path, _signature: Option<Signature>) -> PlannedStep {
+        match cleaner.inspect_email(path) {
+            Ok(Some(0)) => (CleanerStep::skipped(self.name(), "no identifying headers"), None),
+            Ok(Some(count)) => (
+                CleanerStep::planned(self.name(), format!("{} headers to remove", count)),
+                Some(format!("would remove {} email headers", count)),
+            ),
+            Ok(None) => (CleanerStep::skipped(self.name(), "not an email"), None),
+            Err(e) => (CleanerStep::failed(self.name(), e.to_string()), None),
+        }
+    }
+
+    fn verify(&self, cleaner: &MetadataCleaner, path: &Path, _signature: Option<Signature>) -> Vec<RemainingLeak> {
+        cleaner.verify_email(path)
+    }
+}
+
+/// Strips EXIF (camera serials, GPS position) from JPEG and TIFF images.
+pub struct ExifCleaner;
+
+impl FormatCleaner for ExifCleaner {
+    fn name(&self) -> &'static str {
+        "exif"
+    }
+
+    fn handles_extension(&self, extension: &str) -> bool {
+        ImageKind::from_extension(extension).is_some()
+    }
+
+    fn not_handled(&self) -> &'static str {
+        "not a JPEG or TIFF image"
+    }
+
+    fn description(&self) -> &'static str {
+        "images with EXIF data"
+    }
+
+    fn clean(&self, cleaner: &MetadataCleaner, path: &Path) -> CleanerResult<CleanOutcome> {
+        Ok(CleanOutcome::from_changed(cleaner.strip_exif(path)?, "EXIF removed", "no EXIF data"))
+    }
+
+    fn plan(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> PlannedStep {
+        match cleaner.inspect_exif(path, signature) {
+            Ok(Some(true)) => (CleanerStep::planned(self.name(), "EXIF to remove"), Some("would strip EXIF data".to_string())),
+            Ok(Some(false)) => (CleanerStep::skipped(self.name(), "no EXIF data"), None),
+            Ok(None) => (CleanerStep::skipped(self.name(), "unreadable"), None),
+            Err(e) => (CleanerStep::failed(self.name(), e.to_string()), None),
+        }
+    }
+
+    fn verify(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        cleaner.verify_exif(path, signature)
+    }
+}
+
+/// Drops text chunks (software, author, comments) and the tIME chunk from PNGs.
+pub struct PngCleaner;
+
+impl FormatCleaner for PngCleaner {
+    fn name(&self) -> &'static str {
+        "png"
+    }
+
+    fn handles_extension(&self, extension: &str) -> bool {
+        extension == "png"
+    }
+
+    fn not_handled(&self) -> &'static str {
+        "not a PNG image"
+    }
+
+    fn description(&self) -> &'static str {
+        "PNGs with text chunks"
+    }
+
+    fn clean(&self, cleaner: &MetadataCleaner, path: &Path) -> CleanerResult<CleanOutcome> {
+        Ok(CleanOutcome::from_changed(
+            cleaner.clean_png(path)?,
+            "text and time chunks removed",
+            "no text chunks",
+        ))
+    }
+
+    fn plan(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> PlannedStep {
+        plan_findings(self.name(), cleaner.inspect_png(path, signature), "no text chunks", "unreadable", |found| format!("would remove {} PNG chunks ({})", found.len(), found.join(", ")))
+    }
+
+    fn verify(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        cleaner.verify_png(path, signature)
+    }
+}
+
+/// Drops comment and application extensions (watermarks, XMP) from GIFs.
+pub struct GifCleaner;
+
+impl FormatCleaner for GifCleaner {
+    fn name(&self) -> &'static str {
+        "gif"
+    }
+
+    fn handles_extension(&self, extension: &str) -> bool {
+        extension == "gif"
+    }
+
+    fn not_handled(&self) -> &'static str {
+        "not a GIF image"
+    }
+
+    fn description(&self) -> &'static str {
+        "GIFs with comments or XMP"
+    }
+
+    fn clean(&self, cleaner: &MetadataCleaner, path: &Path) -> CleanerResult<CleanOutcome> {
+        Ok(CleanOutcome::from_changed(
+            cleaner.clean_gif(path)?,
+            "comment and application extensions removed",
+            "no comments",
+        ))
+    }
+
+    fn plan(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> PlannedStep {
+        plan_findings(self.name(), cleaner.inspect_gif(path, signature), "no comments", "unreadable", |found| format!("would remove GIF blocks ({})", found.join(", ")))
+    }
+
+    fn verify(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        cleaner.verify_gif(path, signature)
+    }
+}
+
+/// Drops the `<metadata>` element, editor attributes and comments from SVGs.
+pub struct SvgCleaner;
+
+impl FormatCleaner for SvgCleaner {
+    fn name(&self) -> &'static str {
+        "svg"
+    }
+
+    fn handles_extension(&self, extension: &str) -> bool {
+        extension == "svg"
+    }
+
+    fn not_handled(&self) -> &'static str {
+        "not an SVG image"
+    }
+
+    fn description(&self) -> &'static str {
+        "SVGs with editor metadata"
+    }
+
+    fn has_signature(&self) -> bool {
+        false
+    }
+
+    fn clean(&self, cleaner: &MetadataCleaner, path: &Path) -> CleanerResult<CleanOutcome> {
+        Ok(CleanOutcome::from_changed(
+            cleaner.clean_svg(path)?,
+            "metadata, editor data and comments removed",
+            "no metadata",
+        ))
+    }
+
+    fn plan(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> PlannedStep {
+        plan_findings(self.name(), cleaner.inspect_svg(path, signature), "no metadata", "not valid XML", |found| format!("would remove SVG {}", found.join(", ")))
+    }
+
+    fn verify(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        cleaner.verify_svg(path, signature)
+    }
+}
+
+/// Drops creator, Photoshop, IPTC and media-management properties from XMP sidecars.
+pub struct XmpCleaner;
+
+impl FormatCleaner for XmpCleaner {
+    fn name(&self) -> &'static str {
+        "xmp"
+    }
+
+    fn handles_extension(&self, extension: &str) -> bool {
+        extension == "xmp"
+    }
+
+    fn not_handled(&self) -> &'static str {
+        "not an XMP sidecar"
+    }
+
+    fn description(&self) -> &'static str {
+        "XMP sidecars with creator or edit history"
+    }
+
+    fn has_signature(&self) -> bool {
+        false
+    }
+
+    fn clean(&self, cleaner: &MetadataCleaner, path: &Path) -> CleanerResult<CleanOutcome> {
+        Ok(CleanOutcome::from_changed(
+            cleaner.clean_xmp(path)?,
+            "creator, IPTC and history properties removed",
+            "no identity properties",
+        ))
+    }
+
+    fn plan(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> PlannedStep {
+        plan_findings(self.name(), cleaner.inspect_xmp(path, signature), "no identity properties", "not valid XMP", |found| format!("would remove XMP {}", found.join(", ")))
+    }
+
+    fn verify(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        cleaner.verify_xmp(path, signature)
+    }
+}
+
+/// Blanks the EXIF and XMP items of HEIC/HEIF images.
+pub struct HeifCleaner;
+
+impl FormatCleaner for HeifCleaner {
+    fn name(&self) -> &'static str {
+        "heif"
+    }
+
+    fn handles_extension(&self, extension: &str) -> bool {
+        matches!(extension, "heic" | "heif" | "hif")
+    }
+
+    fn not_handled(&self) -> &'static str {
+        "not a HEIF image"
+    }
+
+    fn description(&self) -> &'static str {
+        "HEIF images with EXIF or XMP"
+    }
+
+    fn clean(&self, cleaner: &MetadataCleaner, path: &Path) -> CleanerResult<CleanOutcome> {
+        Ok(CleanOutcome::from_changed(
+            cleaner.clean_heif(path)?,
+            "EXIF and XMP items blanked",
+            "no metadata items",
+        ))
+    }
+
+    fn plan(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> PlannedStep {
+        plan_findings(self.name(), cleaner.inspect_heif(path, signature), "no metadata items", "unrecognized container", |found| format!("would blank HEIF items ({})", found.join(", ")))
+    }
+
+    fn verify(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        cleaner.verify_heif(path, signature)
+    }
+}
+
+/// Removes the GPS position and capture dates from camera RAW files, keeping
+/// their maker notes. Experimental: RAW layouts vary by camera.
+pub struct RawCleaner;
+
+impl FormatCleaner for RawCleaner {
+    fn name(&self) -> &'static str {
+        "raw"
+    }
+
+    fn handles_extension(&self, extension: &str) -> bool {
+        matches!(extension, "cr2" | "nef" | "nrw" | "arw" | "dng" | "pef")
+    }
+
+    fn not_handled(&self) -> &'static str {
+        "not a camera RAW file"
+    }
+
+    fn description(&self) -> &'static str {
+        "RAW photos with GPS or capture times"
+    }
+
+    fn clean(&self, cleaner: &MetadataCleaner, path: &Path) -> CleanerResult<CleanOutcome> {
+        Ok(CleanOutcome::from_changed(
+            cleaner.clean_raw(path)?,
+            "GPS and dates removed (experimental)",
+            "no GPS or dates",
+        ))
+    }
+
+    fn plan(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> PlannedStep {
+        plan_findings(self.name(), cleaner.inspect_raw(path, signature), "no GPS or dates", "unexpected TIFF layout", |found| format!("would remove RAW {} (experimental)", found.join(", ")))
+    }
+
+    fn verify(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        cleaner.verify_raw(path, signature)
+    }
+}
+
+/// Drops ID3 tags (artist, album, encoder, comments) from MP3, FLAC and M4A files.
+pub struct AudioCleaner;
+
+impl FormatCleaner for AudioCleaner {
+    fn name(&self) -> &'static str {
+        "audio"
+    }
+
+    fn handles_extension(&self, extension: &str) -> bool {
+        AudioKind::from_extension(extension).is_some()
+    }
+
+    fn not_handled(&self) -> &'static str {
+        "not an MP3, FLAC or M4A file"
+    }
+
+    fn description(&self) -> &'static str {
+        "audio files with ID3 tags"
+    }
+
+    fn clean(&self, cleaner: &MetadataCleaner, path: &Path) -> CleanerResult<CleanOutcome> {
+        Ok(CleanOutcome::from_changed(
+            cleaner.clean_audio(path)?,
+            "ID3 tags removed",
+            "no ID3 tags",
+        ))
+    }
+
+    fn plan(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> PlannedStep {
+        plan_findings(self.name(), cleaner.inspect_audio(path, signature), "no ID3 tags", "unreadable", |found| format!("would remove ID3 tags ({})", found.join(", ")))
+    }
+
+    fn verify(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        cleaner.verify_audio(path, signature)
+    }
+}
+
+/// Drops LIST/INFO (artist, software, comments) and bext chunks from WAVs.
+pub struct WavCleaner;
+
+impl FormatCleaner for WavCleaner {
+    fn name(&self) -> &'static str {
+        "wav"
+    }
+
+    fn handles_extension(&self, extension: &str) -> bool {
+        extension == "wav"
+    }
+
+    fn not_handled(&self) -> &'static str {
+        "not a WAV file"
+    }
+
+    fn description(&self) -> &'static str {
+        "WAV files with INFO or bext chunks"
+    }
+
+    fn clean(&self, cleaner: &MetadataCleaner, path: &Path) -> CleanerResult<CleanOutcome> {
+        Ok(CleanOutcome::from_changed(
+            cleaner.clean_wav(path)?,
+            "INFO and bext chunks removed",
+            "no INFO or bext chunks",
+        ))
+    }
+
+    fn plan(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> PlannedStep {
+        plan_findings(self.name(), cleaner.inspect_wav(path, signature), "no INFO or bext chunks", "unreadable", |found| format!("would remove WAV chunks ({})", found.join(", ")))
+    }
+
+    fn verify(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        cleaner.verify_wav(path, signature)
+    }
+}
+
+/// Removes the Info dictionary and XMP packet from PDFs.
+pub struct PdfCleaner;
+
+impl FormatCleaner for PdfCleaner {
+    fn name(&self) -> &'static str {
+        "pdf"
+    }
+
+    fn handles_extension(&self, extension: &str) -> bool {
+        extension == "pdf"
+    }
+
+    fn not_handled(&self) -> &'static str {
+        "not a PDF"
+    }
+
+    fn description(&self) -> &'static str {
+        "PDFs with document info"
+    }
+
+    fn clean(&self, cleaner: &MetadataCleaner, path: &Path) -> CleanerResult<CleanOutcome> {
+        Ok(match cleaner.clean_pdf(path)? {
+            Some(fields) => CleanOutcome::Cleaned {
+                detail: "document info and XMP removed".to_string(),
+                properties: fields.into_iter().map(|field| format!("PDF {}", field)).collect(),
+                size: None,
+                entries: Vec::new(),
+                warnings: Vec::new(),
+            },
+            None => CleanOutcome::NotApplicable("no metadata"),
+        })
+    }
+
+    fn plan(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> PlannedStep {
+        plan_findings(self.name(), cleaner.inspect_pdf(path, signature), "no metadata", "encrypted or unreadable", |found| format!("would strip {} PDF fields ({})", found.len(), found.join(", ")))
+    }
+
+    fn verify(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        cleaner.verify_pdf(path, signature)
+    }
+}
+
+/// Drops the tracker block and zeroes volume fields in Windows shortcuts.
+pub struct LnkCleaner;
+
+impl FormatCleaner for LnkCleaner {
+    fn name(&self) -> &'static str {
+        "lnk"
+    }
+
+    fn handles_extension(&self, extension: &str) -> bool {
+        extension == "lnk"
+    }
+
+    fn not_handled(&self) -> &'static str {
+        "not a shortcut"
+    }
+
+    fn description(&self) -> &'static str {
+        "shortcuts with tracking data"
+    }
+
+    fn clean(&self, cleaner: &MetadataCleaner, path: &Path) -> CleanerResult<CleanOutcome> {
+        Ok(match cleaner.clean_lnk(path)? {
+            Some(removed) => CleanOutcome::Cleaned {
+                detail: "tracker and volume fields removed".to_string(),
+                properties: removed.into_iter().map(|item| format!("shortcut {}", item)).collect(),
+                size: None,
+                entries: Vec::new(),
+                warnings: Vec::new(),
+            },
+            None => CleanOutcome::NotApplicable("no tracker or volume fields"),
+        })
+    }
+
+    fn plan(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> PlannedStep {
+        plan_findings(self.name(), cleaner.inspect_lnk(path, signature), "no tracker or volume fields", "not a valid shortcut", |found| format!("would remove shortcut {}", found.join(", ")))
+    }
+
+    fn verify(&self, cleaner: &MetadataCleaner, path: &Path, signature: Option<Signature>) -> Vec<RemainingLeak> {
+        cleaner.verify_lnk(path, signature)
+    }
+}
+
+/// Cleans the entries of ZIP archives with the other format cleaners, with `into_archives`.
+pub struct ZipCleaner;
+
+impl FormatCleaner for ZipCleaner {
+    fn name(&self) -> &'static str {
+        "archive"
+    }
+
+    fn handles_extension(&self, extension: &str) -> bool {
+        extension == "zip"
+    }
+
+    fn not_handled(&self) -> &'static str {
+        "not a ZIP archive"
+    }
+
+    fn description(&self) -> &'static str {
+        "archives with metadata in their entries"
+    }
+
+    fn clean(&self, cleaner: &MetadataCleaner, path: &Path) -> CleanerResult<CleanOutcome> {
+        if !cleaner.options().into_archives {
+            return Ok(CleanOutcome::NotApplicable("entries left alone without --into-archives"));
+        }
+
+        Ok(match cleaner.clean_zip_entries(path)? {
+            Some(entries) => CleanOutcome::Cleaned {
+                detail: format!("{} entries cleaned", entries.len()),
+                properties: Vec::new(),
+                size: None,
+                entries,
+                warnings: Vec::new(),
+            },
+            None => CleanOutcome::NotApplicable("no entries with metadata"),
+        })
+    }
+
+    fn plan(&self, cleaner: &MetadataCleaner, _path: &Path, _signature: Option<Signature>) -> PlannedStep {
+        if !cleaner.options().into_archives {
+            return (CleanerStep::skipped(self.name(), "entries left alone without --into-archives"), None);
+        }
+        (
+            CleanerStep::planned(self.name(), "entries would be inspected"),
+            Some("would clean the metadata of archive entries".to_string()),
+        )
+    }
+
+    /// Entries aren't re-inspected once cleaned.
+    fn verify(&self, _cleaner: &MetadataCleaner, _path: &Path, _signature: Option<Signature>) -> Vec<RemainingLeak> {
+        Vec::new()
+    }
+
+    fn is_verified(&self) -> bool {
+        false
+    }
+}
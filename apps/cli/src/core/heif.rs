@@ -0,0 +1,349 @@
+//! Metadata removal for HEIC/HEIF images.
+//!
+//! HEIF is an ISO base media file: a tree of boxes where the top-level `meta`
+//! box lists the file's items (`iinf`) and where their bytes live (`iloc`).
+//! EXIF (camera, capture time, GPS position) and XMP are items of their own,
+//! next to the image tiles. Their bytes are zeroed in place rather than
+//! re-muxing the file, so every offset into the image data stays valid.
+
+use std::ops::Range;
+
+/// Brands (in `ftyp`) of HEIF still images and image sequences.
+const HEIF_BRANDS: &[&[u8; 4]] = &[b"heic", b"heix", b"heim", b"heis", b"hevc", b"hevx", b"mif1", b"msf1"];
+
+/// Content type of XMP packets stored as `mime` items.
+const XMP_CONTENT_TYPE: &[u8] = b"application/rdf+xml";
+
+/// Guards against runaway item counts in malformed files.
+const MAX_ITEMS: usize = 4096;
+
+/// A box's type and the byte range of its payload (after the header).
+struct BoxSpan {
+    kind: [u8; 4],
+    payload: Range<usize>,
+}
+
+/// An EXIF or XMP item and the file bytes holding it.
+struct MetadataItem {
+    label: &'static str,
+    ranges: Vec<Range<usize>>,
+}
+
+/// Reads a big-endian unsigned integer of `len` bytes (0, 2, 4 or 8) at `*pos`.
+fn read_uint(data: &[u8], pos: &mut usize, len: usize) -> Option<u64> {
+    let bytes = data.get(*pos..pos.checked_add(len)?)?;
+    *pos += len;
+    Some(bytes.iter().fold(0u64, |value, &b| (value << 8) | u64::from(b)))
+}
+
+/// Reads a NUL-terminated string at `*pos`, leaving `*pos` after the NUL.
+fn read_cstr<'a>(data: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+    let rest = data.get(*pos..)?;
+    let end = rest.iter().position(|&b| b == 0)?;
+    *pos += end + 1;
+    Some(&rest[..end])
+}
+
+/// Splits `range` of `data` into boxes. Returns `None` if a box overruns it.
+fn boxes(data: &[u8], range: Range<usize>) -> Option<Vec<BoxSpan>> {
+    let mut spans = Vec::new();
+    let mut pos = range.start;
+
+    while pos < range.end {
+        let start = pos;
+        let size = read_uint(data, &mut pos, 4)?;
+        let kind: [u8; 4] = data.get(pos..pos + 4)?.try_into().ok()?;
+        pos += 4;
+
+        let end = match size {
+            0 => range.end,
+            1 => start.checked_add(usize::try_from(read_uint(data, &mut pos, 8)?).ok()?)?,
+            size => start.checked_add(usize::try_from(size).ok()?)?,
+        };
+        if end < pos || end > range.end {
+            return None;
+        }
+
+        spans.push(BoxSpan { kind, payload: pos..end });
+        pos = end;
+    }
+
+    Some(spans)
+}
+
+/// Finds the first box of the given type.
+fn find<'a>(spans: &'a [BoxSpan], kind: &[u8; 4]) -> Option<&'a BoxSpan> {
+    spans.iter().find(|span| &span.kind == kind)
+}
+
+/// Returns the version of a full box, whose payload starts with version and flags.
+fn full_box_version(data: &[u8], span: &BoxSpan) -> Option<u8> {
+    (span.payload.len() >= 4).then(|| data[span.payload.start])
+}
+
+/// Metadata items found in `iinf`, by item ID.
+fn metadata_items(data: &[u8], iinf: &BoxSpan) -> Option<Vec<(u32, &'static str)>> {
+    let version = full_box_version(data, iinf)?;
+    let mut pos = iinf.payload.start + 4;
+    pos += if version == 0 { 2 } else { 4 };
+
+    let mut items = Vec::new();
+    for infe in boxes(data, pos..iinf.payload.end)? {
+        if &infe.kind != b"infe" {
+            continue;
+        }
+
+        // Versions 0 and 1 predate item types and can't describe EXIF or XMP
+        let version = full_box_version(data, &infe)?;
+        if version < 2 {
+            continue;
+        }
+
+        let mut pos = infe.payload.start + 4;
+        let id = read_uint(data, &mut pos, if version == 2 { 2 } else { 4 })? as u32;
+        pos += 2; // item_protection_index
+        let item_type = data.get(pos..pos + 4)?;
+        pos += 4;
+
+        match item_type {
+            b"Exif" => items.push((id, "Exif")),
+            b"mime" => {
+                read_cstr(data, &mut pos)?; // item_name
+                if read_cstr(data, &mut pos)? == XMP_CONTENT_TYPE {
+                    items.push((id, "XMP"));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Some(items)
+}
+
+/// Returns the file byte ranges of the given item, from `iloc`.
+///
+/// Items stored in the `idat` box are resolved against it; items built from
+/// other items can't be located and yield no ranges.
+fn item_extents(data: &[u8], iloc: &BoxSpan, idat: Option<&BoxSpan>, wanted: u32) -> Option<Vec<Range<usize>>> {
+    let version = full_box_version(data, iloc)?;
+    if version > 2 {
+        return None;
+    }
+
+    let mut pos = iloc.payload.start + 4;
+    let sizes = read_uint(data, &mut pos, 1)? as usize;
+    let (offset_size, length_size) = (sizes >> 4, sizes & 0x0F);
+    let sizes = read_uint(data, &mut pos, 1)? as usize;
+    let base_offset_size = sizes >> 4;
+    let index_size = if version == 0 { 0 } else { sizes & 0x0F };
+    let item_count = read_uint(data, &mut pos, if version < 2 { 2 } else { 4 })? as usize;
+    if item_count > MAX_ITEMS {
+        return None;
+    }
+
+    for _ in 0..item_count {
+        let id = read_uint(data, &mut pos, if version < 2 { 2 } else { 4 })? as u32;
+        let construction_method = if version == 0 { 0 } else { read_uint(data, &mut pos, 2)? & 0x0F };
+        pos += 2; // data_reference_index
+        let base_offset = read_uint(data, &mut pos, base_offset_size)?;
+        let extent_count = read_uint(data, &mut pos, 2)?;
+
+        let mut extents = Vec::new();
+        for _ in 0..extent_count {
+            read_uint(data, &mut pos, index_size)?;
+            let offset = base_offset.checked_add(read_uint(data, &mut pos, offset_size)?)?;
+            let length = read_uint(data, &mut pos, length_size)?;
+            extents.push((offset, length));
+        }
+
+        if id != wanted {
+            continue;
+        }
+
+        // 0 = offsets into the file, 1 = offsets into the idat box
+        let region = match construction_method {
+            0 => 0..data.len(),
+            1 => idat?.payload.clone(),
+            _ => return Some(Vec::new()),
+        };
+
+        let mut ranges = Vec::new();
+        for (offset, length) in extents {
+            let start = region.start.checked_add(usize::try_from(offset).ok()?)?;
+            let end = match length {
+                0 => region.end,
+                length => start.checked_add(usize::try_from(length).ok()?)?,
+            };
+            if start > end || end > region.end {
+                return None;
+            }
+            ranges.push(start..end);
+        }
+        return Some(ranges);
+    }
+
+    Some(Vec::new())
+}
+
+/// Returns the labels and byte ranges of the EXIF and XMP items still holding data.
+///
+/// Returns `None` if the data isn't a HEIF file or its boxes can't be parsed.
+fn metadata_ranges(data: &[u8]) -> Option<Vec<MetadataItem>> {
+    let top = boxes(data, 0..data.len())?;
+
+    let ftyp = top.first().filter(|span| &span.kind == b"ftyp")?;
+    let brands = data.get(ftyp.payload.clone())?;
+    let is_heif = brands
+        .chunks_exact(4)
+        .enumerate()
+        .any(|(i, brand)| i != 1 && HEIF_BRANDS.iter().any(|known| &known[..] == brand));
+    if !is_heif {
+        return None;
+    }
+
+    let meta = find(&top, b"meta")?;
+    let children = boxes(data, meta.payload.start + 4..meta.payload.end)?;
+    let (Some(iinf), Some(iloc)) = (find(&children, b"iinf"), find(&children, b"iloc")) else {
+        return Some(Vec::new());
+    };
+    let idat = find(&children, b"idat");
+
+    let mut found = Vec::new();
+    for (id, label) in metadata_items(data, iinf)? {
+        let ranges = item_extents(data, iloc, idat, id)?;
+        if ranges.iter().any(|range| data[range.clone()].iter().any(|&b| b != 0)) {
+            found.push(MetadataItem { label, ranges });
+        }
+    }
+
+    Some(found)
+}
+
+/// Lists the metadata items a HEIF image carries, e.g. `["Exif", "XMP"]`.
+///
+/// Returns `None` if the data isn't a HEIF file or can't be parsed.
+pub fn heif_metadata(data: &[u8]) -> Option<Vec<String>> {
+    metadata_ranges(data).map(|found| found.into_iter().map(|item| item.label.to_string()).collect())
+}
+
+/// Zeroes the bytes of the EXIF and XMP items, leaving the box structure and
+/// image items untouched.
+///
+/// Returns `None` if the file isn't a HEIF image or has no such items, in which
+/// case the original should be kept.
+pub fn strip_heif_metadata(data: &[u8]) -> Option<Vec<u8>> {
+    let found = metadata_ranges(data)?;
+    if found.is_empty() {
+        return None;
+    }
+
+    let mut cleaned = data.to_vec();
+    for range in found.into_iter().flat_map(|item| item.ranges) {
+        cleaned[range].fill(0);
+    }
+    Some(cleaned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Image tile bytes, which must never change.
+    const TILE: &[u8] = b"hevc tile data";
+
+    /// EXIF item payload: offset to the TIFF header, then TIFF data.
+    const EXIF: &[u8] = b"\0\0\0\0II*\0camera and GPS";
+
+    /// XMP packet stored in `idat`.
+    const XMP: &[u8] = b"<x:xmpmeta>creator</x:xmpmeta>";
+
+    /// Builds a box from its type and payload.
+    fn bx(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        [&(payload.len() as u32 + 8).to_be_bytes()[..], kind, payload].concat()
+    }
+
+    /// Builds a version 2 `infe` box.
+    fn infe(id: u16, item_type: &[u8; 4], extra: &[u8]) -> Vec<u8> {
+        bx(b"infe", &[&[2, 0, 0, 0][..], &id.to_be_bytes(), &[0, 0], item_type, b"\0", extra].concat())
+    }
+
+    /// Builds a HEIF with a tile (item 1) and EXIF (item 2) in `mdat`, and XMP
+    /// (item 3) in `idat`, whose `iloc` gives the EXIF length as `exif_length`.
+    /// Returns the file with the byte ranges of the tile and EXIF item.
+    fn heif(exif_length: u32) -> (Vec<u8>, Range<usize>, Range<usize>) {
+        let ftyp = bx(b"ftyp", b"heic\0\0\0\0mif1heic");
+        let iinf = bx(
+            b"iinf",
+            &[
+                &[0, 0, 0, 0, 0, 3][..],
+                &infe(1, b"hvc1", b""),
+                &infe(2, b"Exif", b""),
+                &infe(3, b"mime", b"application/rdf+xml\0"),
+            ]
+            .concat(),
+        );
+        let idat = bx(b"idat", XMP);
+
+        // Version 1 iloc: four-byte offsets and lengths, no base offsets or indexes
+        let iloc = |mdat: u32| {
+            let mut payload = vec![1, 0, 0, 0, 0x44, 0x00, 0, 3];
+            for (id, method, offset, length) in [
+                (1u16, 0u16, mdat, TILE.len() as u32),
+                (2, 0, mdat + TILE.len() as u32, exif_length),
+                (3, 1, 0, XMP.len() as u32),
+            ] {
+                payload.extend_from_slice(&[&id.to_be_bytes()[..], &method.to_be_bytes(), &[0, 0, 0, 1]].concat());
+                payload.extend_from_slice(&[offset.to_be_bytes(), length.to_be_bytes()].concat());
+            }
+            bx(b"iloc", &payload)
+        };
+        let meta = |mdat: u32| bx(b"meta", &[&[0, 0, 0, 0][..], &iinf, &iloc(mdat), &idat].concat());
+
+        let mdat = ftyp.len() + meta(0).len() + 8;
+        let data = [ftyp, meta(mdat as u32), bx(b"mdat", &[TILE, EXIF].concat())].concat();
+        let tile = mdat..mdat + TILE.len();
+        (data, tile.clone(), tile.end..tile.end + EXIF.len())
+    }
+
+    #[test]
+    fn exif_and_xmp_items_are_listed() {
+        let (data, _, _) = heif(EXIF.len() as u32);
+
+        assert_eq!(heif_metadata(&data).unwrap(), ["Exif", "XMP"]);
+    }
+
+    #[test]
+    fn exif_and_xmp_items_are_zeroed_in_place() {
+        let (input, tile, exif) = heif(EXIF.len() as u32);
+        let xmp = input.windows(XMP.len()).position(|window| window == XMP).unwrap();
+
+        let output = strip_heif_metadata(&input).unwrap();
+
+        let mut expected = input.clone();
+        expected[exif].fill(0);
+        expected[xmp..xmp + XMP.len()].fill(0);
+        assert_eq!(output, expected);
+        assert_eq!(output.len(), input.len());
+        assert_eq!(&output[tile], TILE);
+        assert_eq!(heif_metadata(&output).unwrap(), Vec::<String>::new());
+        assert_eq!(strip_heif_metadata(&output), None);
+    }
+
+    #[test]
+    fn malformed_heif_is_not_stripped() {
+        // An EXIF extent running past the end of the file
+        let (data, _, _) = heif(EXIF.len() as u32 + 1);
+        assert_eq!(heif_metadata(&data), None);
+        assert_eq!(strip_heif_metadata(&data), None);
+
+        // A truncated mdat box
+        let (data, _, _) = heif(EXIF.len() as u32);
+        assert_eq!(strip_heif_metadata(&data[..data.len() - 3]), None);
+
+        // An MP4 video is an ISO media file too, but not a HEIF
+        let mut video = data.clone();
+        video[8..12].copy_from_slice(b"isom");
+        video[16..24].copy_from_slice(b"isomiso2");
+        assert_eq!(heif_metadata(&video), None);
+    }
+}
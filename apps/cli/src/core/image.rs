@@ -0,0 +1,849 @@
+//! Metadata removal for JPEG, TIFF, PNG and GIF images.
+//!
+//! JPEG files keep EXIF (camera make and serial number, capture time, GPS
+//! position) in an `APP1` segment that can be dropped as a whole; a photo turned
+//! by its orientation tag gets a minimal segment holding only that tag back, so
+//! viewers still show it upright. TIFF has no
+//! such wrapper: the EXIF and GPS IFDs hang off the first IFD, so they are
+//! unlinked from it and their bytes zeroed in place, leaving the image data and
+//! every offset into it untouched. PNG stores text (software, author, comments)
+//! and a last-modified time in chunks of their own, which are dropped whole.
+//! GIF comments and application extensions (editor watermarks, XMP) are
+//! blocks of their own too, except the animation loop count, which is kept.
+//! Camera RAW files are TIFF-based too, but their EXIF IFD also holds the maker
+//! notes raw converters rely on, so only the GPS IFD is unlinked and the
+//! capture dates blanked.
+
+/// Marker that starts every JPEG file.
+const JPEG_SOI: [u8; 2] = [0xFF, 0xD8];
+
+/// `APP1` segment marker, used by both EXIF and XMP.
+const JPEG_APP1: u8 = 0xE1;
+
+/// Start-of-scan marker; entropy-coded image data follows it.
+const JPEG_SOS: u8 = 0xDA;
+
+/// Identifier at the start of an EXIF `APP1` payload.
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+
+/// IFD0 tag pointing at the EXIF IFD.
+const TAG_EXIF_IFD: u16 = 0x8769;
+
+/// IFD0 tag pointing at the GPS IFD.
+const TAG_GPS_IFD: u16 = 0x8825;
+
+/// EXIF IFD tag pointing at the interoperability IFD.
+const TAG_INTEROP_IFD: u16 = 0xA005;
+
+/// Size of one IFD entry: tag (2), type (2), count (4), value or offset (4).
+const IFD_ENTRY_LEN: usize = 12;
+
+/// Guards against IFD pointer cycles in malformed files.
+const MAX_IFD_DEPTH: usize = 4;
+
+/// IFD0 tag of the orientation viewers rotate a photo by.
+const TAG_ORIENTATION: u16 = 0x0112;
+
+/// TIFF field type of unsigned 16-bit integers.
+const TIFF_SHORT: u16 = 3;
+
+/// Returns the byte ranges of the EXIF `APP1` segments in a JPEG, including
+/// markers, each with the range of its TIFF data after the `Exif` header.
+///
+/// Returns `None` if the data isn't a JPEG or its segment structure is truncated.
+fn jpeg_exif_segments(data: &[u8]) -> Option<Vec<(std::ops::Range<usize>, std::ops::Range<usize>)>> {
+    if !data.starts_with(&JPEG_SOI) {
+        return None;
+    }
+
+    let mut segments = Vec::new();
+    let mut offset = JPEG_SOI.len();
+
+    loop {
+        if *data.get(offset)? != 0xFF {
+            return None;
+        }
+
+        // Any number of 0xFF fill bytes may precede a marker
+        let mut marker_at = offset;
+        while *data.get(marker_at + 1)? == 0xFF {
+            marker_at += 1;
+        }
+        let marker = data[marker_at + 1];
+
+        // Standalone markers carry no length
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            offset = marker_at + 2;
+            continue;
+        }
+
+        if marker == JPEG_SOS || marker == 0xD9 {
+            return Some(segments);
+        }
+
+        let length = u16::from_be_bytes([*data.get(marker_at + 2)?, *data.get(marker_at + 3)?]) as usize;
+        let end = marker_at + 2 + length;
+        if length < 2 || end > data.len() {
+            return None;
+        }
+
+        if marker == JPEG_APP1 && data[marker_at + 4..end].starts_with(EXIF_HEADER) {
+            segments.push((offset..end, marker_at + 4 + EXIF_HEADER.len()..end));
+        }
+
+        offset = end;
+    }
+}
+
+/// Returns the byte order and a rotated or mirrored orientation (2 to 8) from
+/// the IFD0 of EXIF TIFF data, or `None` if it has no such orientation.
+fn exif_orientation(tiff: &[u8]) -> Option<(ByteOrder, u16)> {
+    let (order, ifd0) = tiff_header(tiff)?;
+    let count = order.u16(tiff, ifd0)? as usize;
+    (0..count).find_map(|index| {
+        let entry = ifd0 + 2 + index * IFD_ENTRY_LEN;
+        if order.u16(tiff, entry)? != TAG_ORIENTATION || order.u16(tiff, entry + 2)? != TIFF_SHORT {
+            return None;
+        }
+        let orientation = order.u16(tiff, entry + 8)?;
+        (2..=8).contains(&orientation).then_some((order, orientation))
+    })
+}
+
+/// Builds an EXIF `APP1` segment whose IFD0 holds only an orientation tag.
+fn orientation_segment(order: ByteOrder, orientation: u16) -> Vec<u8> {
+    let mut tiff = match order {
+        ByteOrder::Little => b"II*\0".to_vec(),
+        ByteOrder::Big => b"MM\0*".to_vec(),
+    };
+    tiff.extend_from_slice(&order.u32_bytes(8));
+    tiff.extend_from_slice(&order.u16_bytes(1));
+    tiff.extend_from_slice(&order.u16_bytes(TAG_ORIENTATION));
+    tiff.extend_from_slice(&order.u16_bytes(TIFF_SHORT));
+    tiff.extend_from_slice(&order.u32_bytes(1));
+    // A single short is left-justified in the four-byte value field
+    tiff.extend_from_slice(&order.u16_bytes(orientation));
+    tiff.extend_from_slice(&[0, 0]);
+    // No next IFD
+    tiff.extend_from_slice(&[0; 4]);
+
+    let length = (2 + EXIF_HEADER.len() + tiff.len()) as u16;
+    let mut segment = vec![0xFF, JPEG_APP1];
+    segment.extend_from_slice(&length.to_be_bytes());
+    segment.extend_from_slice(EXIF_HEADER);
+    segment.extend_from_slice(&tiff);
+    segment
+}
+
+/// Returns true if EXIF TIFF data is exactly what [`orientation_segment`] writes,
+/// i.e. a segment already stripped down to its orientation.
+fn is_orientation_only(tiff: &[u8]) -> bool {
+    exif_orientation(tiff)
+        .is_some_and(|(order, orientation)| orientation_segment(order, orientation)[4 + EXIF_HEADER.len()..] == *tiff)
+}
+
+/// Returns true if a JPEG carries EXIF beyond a bare orientation tag.
+pub fn jpeg_has_exif(data: &[u8]) -> bool {
+    jpeg_exif_segments(data).is_some_and(|segments| {
+        segments.iter().any(|(_, tiff)| !is_orientation_only(&data[tiff.clone()]))
+    })
+}
+
+/// Removes EXIF `APP1` segments from a JPEG, copying everything else byte for byte.
+///
+/// If the photo is rotated or mirrored, the first segment removed is replaced by
+/// one holding only its orientation, unless such a segment is already there.
+/// Returns `None` if the data isn't a parseable JPEG or has no EXIF beyond that.
+pub fn strip_jpeg_exif(data: &[u8]) -> Option<Vec<u8>> {
+    let segments = jpeg_exif_segments(data)?;
+    let (kept, removed): (Vec<_>, Vec<_>) =
+        segments.into_iter().partition(|(_, tiff)| is_orientation_only(&data[tiff.clone()]));
+    if removed.is_empty() {
+        return None;
+    }
+
+    let mut orientation = if kept.is_empty() {
+        removed.iter().find_map(|(_, tiff)| exif_orientation(&data[tiff.clone()]))
+    } else {
+        None
+    };
+
+    let mut output = Vec::with_capacity(data.len());
+    let mut copied = 0;
+    for (segment, _) in removed {
+        output.extend_from_slice(&data[copied..segment.start]);
+        if let Some((order, value)) = orientation.take() {
+            output.extend_from_slice(&orientation_segment(order, value));
+        }
+        copied = segment.end;
+    }
+    output.extend_from_slice(&data[copied..]);
+
+    Some(output)
+}
+
+/// Byte order of a TIFF file.
+#[derive(Clone, Copy)]
+enum ByteOrder {
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    fn u16(self, data: &[u8], offset: usize) -> Option<u16> {
+        let bytes = [*data.get(offset)?, *data.get(offset + 1)?];
+        Some(match self {
+            ByteOrder::Little => u16::from_le_bytes(bytes),
+            ByteOrder::Big => u16::from_be_bytes(bytes),
+        })
+    }
+
+    fn u32(self, data: &[u8], offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(match self {
+            ByteOrder::Little => u32::from_le_bytes(bytes),
+            ByteOrder::Big => u32::from_be_bytes(bytes),
+        })
+    }
+
+    fn u16_bytes(self, value: u16) -> [u8; 2] {
+        match self {
+            ByteOrder::Little => value.to_le_bytes(),
+            ByteOrder::Big => value.to_be_bytes(),
+        }
+    }
+
+    fn u32_bytes(self, value: u32) -> [u8; 4] {
+        match self {
+            ByteOrder::Little => value.to_le_bytes(),
+            ByteOrder::Big => value.to_be_bytes(),
+        }
+    }
+
+    fn put_u16(self, data: &mut [u8], offset: usize, value: u16) {
+        data[offset..offset + 2].copy_from_slice(&self.u16_bytes(value));
+    }
+}
+
+/// Reads the byte order and IFD0 offset from a classic (non-Big) TIFF header.
+fn tiff_header(data: &[u8]) -> Option<(ByteOrder, usize)> {
+    let order = match data.get(..4)? {
+        [b'I', b'I', 42, 0] => ByteOrder::Little,
+        [b'M', b'M', 0, 42] => ByteOrder::Big,
+        _ => return None,
+    };
+    Some((order, order.u32(data, 4)? as usize))
+}
+
+/// Size in bytes of one value of a TIFF field type.
+fn tiff_type_size(field_type: u16) -> usize {
+    match field_type {
+        3 | 8 => 2,
+        4 | 9 | 11 | 13 => 4,
+        5 | 10 | 12 => 8,
+        _ => 1,
+    }
+}
+
+/// Returns the `(tag, offset)` of each EXIF/GPS sub-IFD pointer in IFD0.
+fn tiff_exif_pointers(data: &[u8], order: ByteOrder, ifd0: usize) -> Option<Vec<(u16, usize)>> {
+    let count = order.u16(data, ifd0)? as usize;
+
+    let mut pointers = Vec::new();
+    for index in 0..count {
+        let entry = ifd0 + 2 + index * IFD_ENTRY_LEN;
+        let tag = order.u16(data, entry)?;
+        if tag == TAG_EXIF_IFD || tag == TAG_GPS_IFD {
+            pointers.push((tag, order.u32(data, entry + 8)? as usize));
+        }
+    }
+
+    Some(pointers)
+}
+
+/// Returns true if a TIFF's first IFD links to an EXIF or GPS IFD.
+pub fn tiff_has_exif(data: &[u8]) -> bool {
+    tiff_header(data)
+        .and_then(|(order, ifd0)| tiff_exif_pointers(data, order, ifd0))
+        .is_some_and(|pointers| !pointers.is_empty())
+}
+
+/// Zeroes an IFD, the out-of-line values its entries point to, and any nested
+/// interoperability IFD.
+fn zero_ifd(data: &mut [u8], order: ByteOrder, ifd: usize, depth: usize) {
+    if depth > MAX_IFD_DEPTH {
+        return;
+    }
+    let Some(count) = order.u16(data, ifd).map(usize::from) else {
+        return;
+    };
+
+    for index in 0..count {
+        let entry = ifd + 2 + index * IFD_ENTRY_LEN;
+        let (Some(tag), Some(field_type), Some(values), Some(offset)) = (
+            order.u16(data, entry),
+            order.u16(data, entry + 2),
+            order.u32(data, entry + 4),
+            order.u32(data, entry + 8),
+        ) else {
+            return;
+        };
+
+        if tag == TAG_INTEROP_IFD {
+            zero_ifd(data, order, offset as usize, depth + 1);
+        }
+
+        // Values of four bytes or fewer live inside the entry itself
+        let size = (values as usize).saturating_mul(tiff_type_size(field_type));
+        let start = offset as usize;
+        if size > 4 && start.saturating_add(size) <= data.len() {
+            data[start..start + size].fill(0);
+        }
+    }
+
+    let end = (ifd + 2 + count * IFD_ENTRY_LEN + 4).min(data.len());
+    data[ifd..end].fill(0);
+}
+
+/// Removes the entries with the given tags from IFD0, moving the next-IFD
+/// offset up. Returns false if the IFD0 table runs past the end of the data.
+fn unlink_ifd0_entries(data: &mut [u8], order: ByteOrder, ifd0: usize, tags: &[u16]) -> bool {
+    let count = order.u16(data, ifd0).unwrap_or_default() as usize;
+    let entries_start = ifd0 + 2;
+    let table_end = entries_start + count * IFD_ENTRY_LEN + 4;
+    if table_end > data.len() {
+        return false;
+    }
+
+    let mut kept = Vec::with_capacity(count * IFD_ENTRY_LEN);
+    for index in 0..count {
+        let entry = &data[entries_start + index * IFD_ENTRY_LEN..entries_start + (index + 1) * IFD_ENTRY_LEN];
+        let tag = order.u16(entry, 0).unwrap_or_default();
+        if !tags.contains(&tag) {
+            kept.extend_from_slice(entry);
+        }
+    }
+    let next_ifd: [u8; 4] = data[table_end - 4..table_end].try_into().unwrap_or_default();
+
+    order.put_u16(data, ifd0, (kept.len() / IFD_ENTRY_LEN) as u16);
+    data[entries_start..entries_start + kept.len()].copy_from_slice(&kept);
+    let next_at = entries_start + kept.len();
+    data[next_at..next_at + 4].copy_from_slice(&next_ifd);
+    data[next_at + 4..table_end].fill(0);
+
+    true
+}
+
+/// Unlinks and zeroes the EXIF and GPS IFDs of a TIFF in place.
+///
+/// The file keeps its length and every other offset, so strips and tiles are
+/// untouched. Returns false if the data isn't a classic TIFF or has neither IFD.
+pub fn strip_tiff_exif(data: &mut [u8]) -> bool {
+    let Some((order, ifd0)) = tiff_header(data) else {
+        return false;
+    };
+    let Some(pointers) = tiff_exif_pointers(data, order, ifd0) else {
+        return false;
+    };
+    if pointers.is_empty() {
+        return false;
+    }
+
+    let count = order.u16(data, ifd0).unwrap_or_default() as usize;
+    if ifd0 + 2 + count * IFD_ENTRY_LEN + 4 > data.len() {
+        return false;
+    }
+
+    for &(_, offset) in &pointers {
+        zero_ifd(data, order, offset, 0);
+    }
+
+    unlink_ifd0_entries(data, order, ifd0, &[TAG_EXIF_IFD, TAG_GPS_IFD])
+}
+
+/// IFD0 tag of the file's last-modified date and time.
+const TAG_DATE_TIME: u16 = 0x0132;
+
+/// EXIF IFD tags of the capture and digitization dates, their UTC offsets and
+/// sub-second parts, with the names reported for them.
+const EXIF_DATE_TAGS: &[(u16, &str)] = &[
+    (0x9003, "DateTimeOriginal"),
+    (0x9004, "DateTimeDigitized"),
+    (0x9010, "OffsetTime"),
+    (0x9011, "OffsetTimeOriginal"),
+    (0x9012, "OffsetTimeDigitized"),
+    (0x9290, "SubSecTime"),
+    (0x9291, "SubSecTimeOriginal"),
+    (0x9292, "SubSecTimeDigitized"),
+];
+
+/// TIFF field type of NUL-terminated ASCII strings.
+const TIFF_ASCII: u16 = 2;
+
+/// Where a RAW file's GPS IFD and non-blank date strings sit.
+struct RawMetadata {
+    order: ByteOrder,
+    ifd0: usize,
+    gps: Option<usize>,
+    dates: Vec<(&'static str, std::ops::Range<usize>)>,
+}
+
+/// Returns the entry count of the IFD at `ifd` if its whole table lies within the data.
+fn ifd_entry_count(data: &[u8], order: ByteOrder, ifd: usize) -> Option<usize> {
+    let count = order.u16(data, ifd)? as usize;
+    (ifd + 2 + count * IFD_ENTRY_LEN + 4 <= data.len()).then_some(count)
+}
+
+/// Returns the byte range of an ASCII entry's value, or `None` if the entry
+/// isn't ASCII or its value lies outside the data.
+fn ascii_value(data: &[u8], order: ByteOrder, entry: usize) -> Option<std::ops::Range<usize>> {
+    if order.u16(data, entry + 2)? != TIFF_ASCII {
+        return None;
+    }
+
+    // Values of four bytes or fewer live inside the entry itself
+    let len = order.u32(data, entry + 4)? as usize;
+    let start = if len <= 4 { entry + 8 } else { order.u32(data, entry + 8)? as usize };
+    let end = start.checked_add(len)?;
+    (end <= data.len()).then_some(start..end)
+}
+
+/// Finds the GPS IFD and the date strings of a RAW file's TIFF structure.
+///
+/// Returns `None` if the structure isn't as expected (not a classic TIFF, an
+/// IFD table or value out of bounds, a date that isn't ASCII), in which case
+/// the file must not be edited.
+fn raw_metadata_fields(data: &[u8]) -> Option<RawMetadata> {
+    let (order, ifd0) = tiff_header(data)?;
+
+    let mut gps = None;
+    let mut exif = None;
+    let mut dates = Vec::new();
+    for index in 0..ifd_entry_count(data, order, ifd0)? {
+        let entry = ifd0 + 2 + index * IFD_ENTRY_LEN;
+        match order.u16(data, entry)? {
+            TAG_GPS_IFD => {
+                let offset = order.u32(data, entry + 8)? as usize;
+                ifd_entry_count(data, order, offset)?;
+                gps = Some(offset);
+            }
+            TAG_EXIF_IFD => exif = Some(order.u32(data, entry + 8)? as usize),
+            TAG_DATE_TIME => dates.push(("DateTime", ascii_value(data, order, entry)?)),
+            _ => {}
+        }
+    }
+
+    if let Some(exif) = exif {
+        for index in 0..ifd_entry_count(data, order, exif)? {
+            let entry = exif + 2 + index * IFD_ENTRY_LEN;
+            let tag = order.u16(data, entry)?;
+            if let Some(&(_, name)) = EXIF_DATE_TAGS.iter().find(|(date_tag, _)| *date_tag == tag) {
+                dates.push((name, ascii_value(data, order, entry)?));
+            }
+        }
+    }
+
+    // Dates already blanked (spaces or NULs, as the EXIF spec allows for unknown ones)
+    dates.retain(|(_, range)| data[range.clone()].iter().any(|&b| b != b' ' && b != 0));
+
+    Some(RawMetadata { order, ifd0, gps, dates })
+}
+
+/// Lists what a camera RAW file still carries, e.g. `["GPS", "DateTimeOriginal"]`.
+///
+/// Returns `None` if its TIFF structure isn't as expected.
+pub fn raw_metadata(data: &[u8]) -> Option<Vec<String>> {
+    let found = raw_metadata_fields(data)?;
+    let gps = found.gps.map(|_| "GPS".to_string());
+    Some(gps.into_iter().chain(found.dates.iter().map(|(name, _)| name.to_string())).collect())
+}
+
+/// Unlinks and zeroes the GPS IFD of a camera RAW file and blanks its date
+/// strings with spaces, in place.
+///
+/// The EXIF IFD, its maker notes and the image data are left as they are, and
+/// the file keeps its length. Returns false, without changing anything, if the
+/// TIFF structure isn't as expected or there is nothing to remove.
+pub fn strip_raw_metadata(data: &mut [u8]) -> bool {
+    let Some(found) = raw_metadata_fields(data) else {
+        return false;
+    };
+    if found.gps.is_none() && found.dates.is_empty() {
+        return false;
+    }
+
+    for (_, range) in &found.dates {
+        // Keep the terminating NUL so the string stays well-formed
+        let end = if range.end > range.start && data[range.end - 1] == 0 { range.end - 1 } else { range.end };
+        data[range.start..end].fill(b' ');
+    }
+
+    if let Some(gps) = found.gps {
+        zero_ifd(data, found.order, gps, 0);
+        unlink_ifd0_entries(data, found.order, found.ifd0, &[TAG_GPS_IFD]);
+    }
+
+    true
+}
+
+/// Signature that starts every PNG file.
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// PNG chunks carrying text or a timestamp, all ancillary and safe to drop.
+const PNG_METADATA_CHUNKS: &[&[u8; 4]] = &[b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+/// Returns each PNG chunk's type and byte range (length, type, data and CRC).
+///
+/// Returns `None` if the data isn't a PNG or a chunk runs past the end of the file.
+fn png_chunks(data: &[u8]) -> Option<Vec<([u8; 4], std::ops::Range<usize>)>> {
+    if !data.starts_with(PNG_SIGNATURE) {
+        return None;
+    }
+
+    let mut chunks = Vec::new();
+    let mut offset = PNG_SIGNATURE.len();
+
+    while offset < data.len() {
+        let length = u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize;
+        let chunk_type: [u8; 4] = data.get(offset + 4..offset + 8)?.try_into().ok()?;
+        // Length and type, data, then a four-byte CRC
+        let end = offset.checked_add(12)?.checked_add(length)?;
+        if end > data.len() {
+            return None;
+        }
+
+        chunks.push((chunk_type, offset..end));
+        offset = end;
+
+        if &chunk_type == b"IEND" {
+            break;
+        }
+    }
+
+    Some(chunks)
+}
+
+/// Returns the names of the text and time chunks in a PNG, or `None` if it isn't a valid PNG.
+pub fn png_metadata_chunks(data: &[u8]) -> Option<Vec<String>> {
+    Some(
+        png_chunks(data)?
+            .into_iter()
+            .filter(|(chunk_type, _)| PNG_METADATA_CHUNKS.contains(&chunk_type))
+            .map(|(chunk_type, _)| String::from_utf8_lossy(&chunk_type).into_owned())
+            .collect(),
+    )
+}
+
+/// Drops `tEXt`, `zTXt`, `iTXt` and `tIME` chunks from a PNG, copying every other
+/// chunk (and anything after `IEND`) byte for byte. CRCs are per chunk, so none change.
+///
+/// Returns `None` if the data isn't a parseable PNG or has none of those chunks.
+pub fn strip_png_metadata(data: &[u8]) -> Option<Vec<u8>> {
+    let chunks = png_chunks(data)?;
+    if !chunks.iter().any(|(chunk_type, _)| PNG_METADATA_CHUNKS.contains(&chunk_type)) {
+        return None;
+    }
+
+    let mut output = Vec::with_capacity(data.len());
+    output.extend_from_slice(PNG_SIGNATURE);
+    let mut copied = PNG_SIGNATURE.len();
+    for (chunk_type, range) in chunks {
+        if !PNG_METADATA_CHUNKS.contains(&&chunk_type) {
+            output.extend_from_slice(&data[range.clone()]);
+        }
+        copied = range.end;
+    }
+    output.extend_from_slice(&data[copied..]);
+
+    Some(output)
+}
+
+/// Signatures of the two GIF versions.
+const GIF_SIGNATURES: &[&[u8]] = &[b"GIF87a", b"GIF89a"];
+
+/// Introduces an extension block; the next byte is its label.
+const GIF_EXTENSION: u8 = 0x21;
+
+/// Introduces an image descriptor.
+const GIF_IMAGE: u8 = 0x2C;
+
+/// Ends the GIF data stream.
+const GIF_TRAILER: u8 = 0x3B;
+
+/// Comment extension label.
+const GIF_COMMENT: u8 = 0xFE;
+
+/// Application extension label.
+const GIF_APPLICATION: u8 = 0xFF;
+
+/// Application extensions that affect playback (the animation loop count), never dropped.
+const GIF_PLAYBACK_APPLICATIONS: &[&[u8]] = &[b"NETSCAPE2.0", b"ANIMEXTS1.0"];
+
+/// Returns the end of the data sub-blocks starting at `offset`, after the zero-length terminator.
+fn gif_sub_blocks_end(data: &[u8], mut offset: usize) -> Option<usize> {
+    loop {
+        let len = usize::from(*data.get(offset)?);
+        offset += 1 + len;
+        if len == 0 {
+            return Some(offset);
+        }
+    }
+}
+
+/// Returns the size of a color table from a packed flags byte (0 if there is none).
+fn gif_color_table_len(flags: u8) -> usize {
+    if flags & 0x80 == 0 { 0 } else { 3 << ((flags & 0x07) + 1) }
+}
+
+/// Returns a label for each comment or non-playback application extension in a
+/// GIF, with its byte range.
+///
+/// Returns `None` if the data isn't a GIF or its block structure is truncated.
+fn gif_metadata_blocks(data: &[u8]) -> Option<Vec<(String, std::ops::Range<usize>)>> {
+    if !GIF_SIGNATURES.iter().any(|signature| data.starts_with(signature)) {
+        return None;
+    }
+
+    // Header (6) and logical screen descriptor (7), then the global color table
+    let mut offset = 13 + gif_color_table_len(*data.get(10)?);
+    let mut blocks = Vec::new();
+
+    loop {
+        match *data.get(offset)? {
+            GIF_TRAILER => return Some(blocks),
+            GIF_EXTENSION => {
+                let label = *data.get(offset + 1)?;
+                let end = gif_sub_blocks_end(data, offset + 2)?;
+                match label {
+                    GIF_COMMENT => blocks.push(("comment".to_string(), offset..end)),
+                    GIF_APPLICATION => {
+                        // The first sub-block holds the 8-byte identifier and 3-byte authentication code
+                        let size = usize::from(*data.get(offset + 2)?);
+                        let id = data.get(offset + 3..offset + 3 + size.min(11))?;
+                        if !GIF_PLAYBACK_APPLICATIONS.contains(&id) {
+                            let name = String::from_utf8_lossy(id).trim_end_matches('\0').to_string();
+                            blocks.push((format!("application {}", name), offset..end));
+                        }
+                    }
+                    _ => {}
+                }
+                offset = end;
+            }
+            GIF_IMAGE => {
+                // Descriptor (10), local color table, LZW code size (1), then the image data
+                let flags = *data.get(offset + 9)?;
+                offset = gif_sub_blocks_end(data, offset + 10 + gif_color_table_len(flags) + 1)?;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Returns labels for the comment and application extensions in a GIF
+/// (e.g. "comment", "application XMP DataXMP"), or `None` if it isn't a valid GIF.
+pub fn gif_metadata(data: &[u8]) -> Option<Vec<String>> {
+    gif_metadata_blocks(data).map(|blocks| blocks.into_iter().map(|(label, _)| label).collect())
+}
+
+/// Drops comment and application extensions from a GIF, keeping graphic
+/// control extensions, image data, the animation loop count and the trailer.
+///
+/// Returns `None` if the data isn't a parseable GIF or has none of those blocks.
+pub fn strip_gif_metadata(data: &[u8]) -> Option<Vec<u8>> {
+    let blocks = gif_metadata_blocks(data)?;
+    if blocks.is_empty() {
+        return None;
+    }
+
+    let mut output = Vec::with_capacity(data.len());
+    let mut copied = 0;
+    for (_, range) in blocks {
+        output.extend_from_slice(&data[copied..range.start]);
+        copied = range.end;
+    }
+    output.extend_from_slice(&data[copied..]);
+
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds little-endian TIFF data with IFD0 at offset 8 holding the given
+    /// `(tag, type, count, value)` entries, followed by `extra` bytes.
+    fn tiff(entries: &[(u16, u16, u32, u32)], extra: &[u8]) -> Vec<u8> {
+        let mut data = b"II*\0\x08\0\0\0".to_vec();
+        data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        for &(tag, field_type, count, value) in entries {
+            data.extend_from_slice(&tag.to_le_bytes());
+            data.extend_from_slice(&field_type.to_le_bytes());
+            data.extend_from_slice(&count.to_le_bytes());
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(extra);
+        data
+    }
+
+    /// Wraps a payload in a JPEG marker segment.
+    fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let mut data = vec![0xFF, marker];
+        data.extend_from_slice(&(payload.len() as u16 + 2).to_be_bytes());
+        data.extend_from_slice(payload);
+        data
+    }
+
+    /// Wraps TIFF data in an EXIF `APP1` segment.
+    fn exif_segment(tiff: &[u8]) -> Vec<u8> {
+        segment(JPEG_APP1, &[EXIF_HEADER, tiff].concat())
+    }
+
+    /// Builds a JPEG from its header segments, followed by a scan and `EOI`.
+    fn jpeg(segments: &[&[u8]]) -> Vec<u8> {
+        let mut data = JPEG_SOI.to_vec();
+        for segment in segments {
+            data.extend_from_slice(segment);
+        }
+        data.extend_from_slice(&[0xFF, JPEG_SOS, 0x00, 0x02, 0x12, 0x34, 0xFF, 0xD9]);
+        data
+    }
+
+    /// Builds a PNG chunk; CRCs aren't checked, so they are left zero.
+    fn chunk(chunk_type: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        [&(body.len() as u32).to_be_bytes()[..], chunk_type, body, &[0; 4]].concat()
+    }
+
+    /// The `JFIF` `APP0` segment most JPEGs start with.
+    fn jfif() -> Vec<u8> {
+        segment(0xE0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0")
+    }
+
+    #[test]
+    fn jpeg_exif_segment_is_dropped_byte_for_byte() {
+        // Make is inline ("Cam\0"); orientation 1 is upright, so it isn't kept
+        let exif = exif_segment(&tiff(&[(0x010F, TIFF_ASCII, 4, u32::from_le_bytes(*b"Cam\0")), (TAG_ORIENTATION, TIFF_SHORT, 1, 1)], &[]));
+        let input = jpeg(&[&jfif(), &exif]);
+        assert!(jpeg_has_exif(&input));
+
+        let output = strip_jpeg_exif(&input).unwrap();
+
+        assert_eq!(output, jpeg(&[&jfif()]));
+        assert_eq!(output.len(), input.len() - exif.len());
+        assert!(!jpeg_has_exif(&output));
+        assert_eq!(strip_jpeg_exif(&output), None);
+    }
+
+    #[test]
+    fn rotated_jpeg_keeps_only_its_orientation() {
+        let exif = exif_segment(&tiff(&[(0x010F, TIFF_ASCII, 4, u32::from_le_bytes(*b"Cam\0")), (TAG_ORIENTATION, TIFF_SHORT, 1, 6)], &[]));
+        let input = jpeg(&[&jfif(), &exif]);
+
+        let output = strip_jpeg_exif(&input).unwrap();
+
+        let orientation: &[u8] = &[
+            0xFF, 0xE1, 0x00, 0x22, b'E', b'x', b'i', b'f', 0, 0, // APP1, length 34, EXIF header
+            b'I', b'I', 42, 0, 8, 0, 0, 0, // little-endian TIFF header, IFD0 at 8
+            1, 0, // one entry
+            0x12, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0, // Orientation, SHORT, 1 value: 6
+            0, 0, 0, 0, // no next IFD
+        ];
+        assert_eq!(output, jpeg(&[&jfif(), orientation]));
+        assert_eq!(output.len(), input.len() - exif.len() + 36);
+        assert!(!jpeg_has_exif(&output));
+        assert_eq!(strip_jpeg_exif(&output), None);
+    }
+
+    #[test]
+    fn big_endian_orientation_is_kept_in_big_endian() {
+        let mut tiff = b"MM\0*\0\0\0\x08\0\x01".to_vec();
+        tiff.extend_from_slice(&[0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 8, 0, 0, 0, 0, 0, 0]);
+        tiff.extend_from_slice(b"extra EXIF data");
+        let input = jpeg(&[&exif_segment(&tiff)]);
+
+        let output = strip_jpeg_exif(&input).unwrap();
+
+        assert_eq!(output, jpeg(&[&exif_segment(&tiff[..26])]));
+        assert_eq!(strip_jpeg_exif(&output), None);
+    }
+
+    #[test]
+    fn jpeg_xmp_segment_is_not_exif() {
+        let xmp = segment(JPEG_APP1, b"http://ns.adobe.com/xap/1.0/\0<x:xmpmeta/>");
+        let input = jpeg(&[&jfif(), &xmp]);
+
+        assert!(!jpeg_has_exif(&input));
+        assert_eq!(strip_jpeg_exif(&input), None);
+    }
+
+    #[test]
+    fn truncated_jpeg_is_not_stripped() {
+        let exif = exif_segment(&tiff(&[(0x010F, TIFF_ASCII, 4, 0)], &[]));
+        let input = jpeg(&[&jfif(), &exif]);
+        let truncated = &input[..JPEG_SOI.len() + jfif().len() + 10];
+
+        assert!(!jpeg_has_exif(truncated));
+        assert_eq!(strip_jpeg_exif(truncated), None);
+        assert_eq!(strip_jpeg_exif(b"not a jpeg"), None);
+        // A segment length below the two length bytes themselves
+        assert_eq!(strip_jpeg_exif(&[0xFF, 0xD8, 0xFF, 0xE1, 0x00, 0x01]), None);
+    }
+
+    #[test]
+    fn tiff_exif_ifd_is_unlinked_and_zeroed_in_place() {
+        // IFD0 (8..38) links the EXIF IFD at 38, whose date string lives at 56..76
+        let mut exif_ifd = vec![1, 0];
+        exif_ifd.extend_from_slice(&[0x03, 0x90, 2, 0, 20, 0, 0, 0, 56, 0, 0, 0]);
+        exif_ifd.extend_from_slice(&[0; 4]);
+        exif_ifd.extend_from_slice(b"2024:01:02 03:04:05\0");
+        let mut data = tiff(&[(0x0100, TIFF_SHORT, 1, 16), (TAG_EXIF_IFD, 4, 1, 38)], &exif_ifd);
+        assert_eq!(data.len(), 76);
+        assert!(tiff_has_exif(&data));
+
+        assert!(strip_tiff_exif(&mut data));
+
+        let mut expected = tiff(&[(0x0100, TIFF_SHORT, 1, 16)], &[]);
+        expected.resize(76, 0);
+        assert_eq!(data, expected);
+        assert!(!tiff_has_exif(&data));
+        assert!(!strip_tiff_exif(&mut data));
+    }
+
+    #[test]
+    fn truncated_tiff_is_left_unchanged() {
+        let full = tiff(&[(0x0100, TIFF_SHORT, 1, 16), (TAG_EXIF_IFD, 4, 1, 38)], &[0; 38]);
+        let mut data = full[..30].to_vec();
+
+        assert!(!tiff_has_exif(&data));
+        assert!(!strip_tiff_exif(&mut data));
+        assert_eq!(data, full[..30]);
+    }
+
+    #[test]
+    fn png_text_and_time_chunks_are_dropped() {
+        let ihdr = chunk(b"IHDR", &[0; 13]);
+        let idat = chunk(b"IDAT", b"pixels");
+        let iend = chunk(b"IEND", b"");
+        let text = chunk(b"tEXt", b"Software\0Editor 1.0");
+        let time = chunk(b"tIME", &[0x07, 0xE8, 1, 2, 3, 4, 5]);
+        let input = [PNG_SIGNATURE, &ihdr, &text, &idat, &time, &iend].concat();
+        assert_eq!(png_metadata_chunks(&input).unwrap(), ["tEXt", "tIME"]);
+
+        let output = strip_png_metadata(&input).unwrap();
+
+        assert_eq!(output, [PNG_SIGNATURE, &ihdr, &idat, &iend].concat());
+        assert_eq!(output.len(), input.len() - text.len() - time.len());
+        assert_eq!(png_metadata_chunks(&output).unwrap(), Vec::<String>::new());
+        assert_eq!(strip_png_metadata(&output), None);
+    }
+
+    #[test]
+    fn truncated_png_is_not_stripped() {
+        let input = [PNG_SIGNATURE, &chunk(b"IHDR", &[0; 13]), &chunk(b"tEXt", b"Author\0Alice")].concat();
+        let truncated = &input[..input.len() - 6];
+
+        assert_eq!(png_metadata_chunks(truncated), None);
+        assert_eq!(strip_png_metadata(truncated), None);
+        assert_eq!(strip_png_metadata(b"\x89PNG"), None);
+    }
+}
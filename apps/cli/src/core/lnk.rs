@@ -0,0 +1,347 @@
+//! Metadata removal for Windows shortcut (`.lnk`) files.
+//!
+//! A Shell Link starts with a fixed 76-byte header carrying the link CLSID,
+//! followed by the optional sections its flags announce: the target's ID list,
+//! a `LinkInfo` structure and counted strings, then a list of extra data
+//! blocks. The `TrackerDataBlock` among those records the NetBIOS name of the
+//! machine the link was made on and object IDs whose node field is usually
+//! that machine's MAC address; it only helps the link tracking service find
+//! moved targets, so it is dropped. The `VolumeID` inside `LinkInfo` holds the
+//! source drive's serial number and label, which are zeroed in place. The ID
+//! list, paths and other blocks (including the known-folder block, which names
+//! a shell folder rather than the machine) are kept so the shortcut still
+//! opens its target.
+
+use std::ops::Range;
+
+/// Size of the Shell Link header, also stored as its first field.
+const HEADER_LEN: usize = 0x4C;
+
+/// `00021401-0000-0000-C000-000000000046`, as stored in the header.
+const LINK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+const HAS_LINK_TARGET_ID_LIST: u32 = 0x01;
+const HAS_LINK_INFO: u32 = 0x02;
+const IS_UNICODE: u32 = 0x80;
+
+/// Flags of the counted strings after `LinkInfo` (name, relative path, working
+/// directory, arguments, icon location), in file order.
+const STRING_FLAGS: [u32; 5] = [0x04, 0x08, 0x10, 0x20, 0x40];
+
+/// `LinkInfo` flag set when it carries a `VolumeID` and local base path.
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x01;
+
+/// `VolumeID` label offset meaning a Unicode label offset follows.
+const UNICODE_LABEL_OFFSET: usize = 0x14;
+
+const TRACKER_SIGNATURE: u32 = 0xA000_0003;
+
+/// Size of a `TrackerDataBlock`, through the end of its birth object IDs.
+const TRACKER_BLOCK_LEN: usize = 0x60;
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(offset..offset.checked_add(2)?)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(offset..offset.checked_add(4)?)?.try_into().ok()?))
+}
+
+/// Where the identifying fields sit in a parsed shortcut.
+struct LinkLayout {
+    /// Byte range of the `VolumeID` structure, if `LinkInfo` has one.
+    volume_id: Option<Range<usize>>,
+    /// Byte ranges of the `TrackerDataBlock`s.
+    trackers: Vec<Range<usize>>,
+}
+
+/// Returns the range of the `VolumeID` within a `LinkInfo` structure.
+fn volume_id_range(info: &[u8]) -> Option<Range<usize>> {
+    if u32_at(info, 8)? & VOLUME_ID_AND_LOCAL_BASE_PATH == 0 {
+        return None;
+    }
+    let start = u32_at(info, 12)? as usize;
+    let end = start.checked_add(u32_at(info, start)? as usize)?;
+    (end >= start + 16 && end <= info.len()).then_some(start..end)
+}
+
+/// Returns the range of the volume label's characters, without the terminator,
+/// within a `VolumeID`.
+fn volume_label_range(volume: &[u8]) -> Option<Range<usize>> {
+    let offset = u32_at(volume, 12)? as usize;
+    let (start, char_len) = if offset == UNICODE_LABEL_OFFSET {
+        (u32_at(volume, 16)? as usize, 2)
+    } else {
+        (offset, 1)
+    };
+
+    let chars = volume.get(start..)?.chunks_exact(char_len);
+    let len = chars.take_while(|c| c.iter().any(|&byte| byte != 0)).count() * char_len;
+    Some(start..start + len)
+}
+
+/// Returns true if `data` starts with a Shell Link header size and CLSID.
+pub fn has_link_header(data: &[u8]) -> bool {
+    u32_at(data, 0) == Some(HEADER_LEN as u32) && data.get(4..20) == Some(&LINK_CLSID[..])
+}
+
+/// Walks a shortcut's sections to find its `VolumeID` and tracker blocks.
+///
+/// Returns `None` if the header or LNK CLSID is wrong or a section overruns the file.
+fn parse(data: &[u8]) -> Option<LinkLayout> {
+    if !has_link_header(data) {
+        return None;
+    }
+    let flags = u32_at(data, 0x14)?;
+    let mut offset = HEADER_LEN;
+
+    if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+        offset += 2 + u16_at(data, offset)? as usize;
+    }
+
+    let mut volume_id = None;
+    if flags & HAS_LINK_INFO != 0 {
+        let size = u32_at(data, offset)? as usize;
+        let info = data.get(offset..offset.checked_add(size)?)?;
+        volume_id = volume_id_range(info).map(|range| offset + range.start..offset + range.end);
+        offset += size;
+    }
+
+    let char_len = if flags & IS_UNICODE != 0 { 2 } else { 1 };
+    for flag in STRING_FLAGS {
+        if flags & flag != 0 {
+            offset += 2 + u16_at(data, offset)? as usize * char_len;
+        }
+    }
+
+    // Extra data blocks run until a terminal block smaller than four bytes
+    let mut trackers = Vec::new();
+    while offset < data.len() {
+        let size = u32_at(data, offset)? as usize;
+        if size < 4 {
+            break;
+        }
+        let end = offset.checked_add(size)?;
+        if size < 8 || end > data.len() {
+            return None;
+        }
+        if u32_at(data, offset + 4)? == TRACKER_SIGNATURE && size >= TRACKER_BLOCK_LEN {
+            trackers.push(offset..end);
+        }
+        offset = end;
+    }
+
+    Some(LinkLayout { volume_id, trackers })
+}
+
+/// Describes a tracker block, e.g. "tracker (machine DESKTOP-1, MAC 00:15:5d:01:02:03)".
+fn tracker_label(block: &[u8]) -> String {
+    let mut details = Vec::new();
+
+    let machine: String = block[16..32].iter().take_while(|&&byte| byte != 0).map(|&byte| byte as char).collect();
+    if !machine.is_empty() {
+        details.push(format!("machine {}", machine));
+    }
+
+    // The file object ID is a version 1 UUID whose node is the creating machine's MAC
+    let object_id = &block[48..64];
+    if u16_at(object_id, 6).is_some_and(|data3| data3 >> 12 == 1) {
+        let mac: Vec<String> = object_id[10..16].iter().map(|byte| format!("{:02x}", byte)).collect();
+        details.push(format!("MAC {}", mac.join(":")));
+    }
+
+    if details.is_empty() { "tracker".to_string() } else { format!("tracker ({})", details.join(", ")) }
+}
+
+/// Returns labels for the identifying fields in a parsed shortcut.
+fn metadata_labels(data: &[u8], layout: &LinkLayout) -> Vec<String> {
+    let mut labels = Vec::new();
+
+    if let Some(range) = &layout.volume_id {
+        let volume = &data[range.clone()];
+        if u32_at(volume, 8).is_some_and(|serial| serial != 0) {
+            labels.push("volume serial number".to_string());
+        }
+        if volume_label_range(volume).is_some_and(|label| !label.is_empty()) {
+            labels.push("volume label".to_string());
+        }
+    }
+
+    labels.extend(layout.trackers.iter().map(|range| tracker_label(&data[range.clone()])));
+    labels
+}
+
+/// Returns labels for the tracker block and volume fields a shortcut carries,
+/// e.g. `["volume serial number", "tracker (machine DESKTOP-1)"]`, or `None`
+/// if it isn't a valid Shell Link.
+pub fn lnk_metadata(data: &[u8]) -> Option<Vec<String>> {
+    let layout = parse(data)?;
+    Some(metadata_labels(data, &layout))
+}
+
+/// Drops tracker blocks and zeroes the volume serial number and label,
+/// returning the new file and labels of what was removed.
+///
+/// Returns `None` if the file isn't a valid Shell Link or has nothing to remove.
+pub fn strip_lnk_metadata(data: &[u8]) -> Option<(Vec<u8>, Vec<String>)> {
+    let layout = parse(data)?;
+    let labels = metadata_labels(data, &layout);
+    if labels.is_empty() {
+        return None;
+    }
+
+    let mut cleaned = data.to_vec();
+    if let Some(range) = &layout.volume_id {
+        cleaned[range.start + 8..range.start + 12].fill(0);
+        if let Some(label) = volume_label_range(&data[range.clone()]) {
+            cleaned[range.start + label.start..range.start + label.end].fill(0);
+        }
+    }
+
+    // Later blocks go first so the earlier ranges stay valid
+    for range in layout.trackers.iter().rev() {
+        cleaned.drain(range.clone());
+    }
+
+    Some((cleaned, labels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a Shell Link header with the given flags.
+    fn header(flags: u32) -> Vec<u8> {
+        let mut header = vec![0; HEADER_LEN];
+        header[0..4].copy_from_slice(&(HEADER_LEN as u32).to_le_bytes());
+        header[4..20].copy_from_slice(&LINK_CLSID);
+        header[0x14..0x18].copy_from_slice(&flags.to_le_bytes());
+        header
+    }
+
+    /// Builds a `LinkInfo` whose `VolumeID` has serial number `DEADBEEF` and an
+    /// ANSI label "DATA", with local base path `C:\x`.
+    fn link_info() -> Vec<u8> {
+        let volume = [&21u32.to_le_bytes()[..], &3u32.to_le_bytes(), &0xDEAD_BEEFu32.to_le_bytes(), &16u32.to_le_bytes(), b"DATA\0"].concat();
+        let base_path = b"C:\\x\0";
+        let size = 0x1C + volume.len() + base_path.len() + 1;
+        [
+            &(size as u32).to_le_bytes()[..],
+            &0x1Cu32.to_le_bytes(),
+            &VOLUME_ID_AND_LOCAL_BASE_PATH.to_le_bytes(),
+            &0x1Cu32.to_le_bytes(),
+            &(0x1C + volume.len() as u32).to_le_bytes(),
+            &0u32.to_le_bytes(),
+            &(size as u32 - 1).to_le_bytes(),
+            &volume,
+            base_path,
+            b"\0",
+        ]
+        .concat()
+    }
+
+    /// Builds a `TrackerDataBlock` naming `machine`, whose object ID is a
+    /// version 1 UUID with node `00:15:5d:01:02:03`.
+    fn tracker(machine: &[u8]) -> Vec<u8> {
+        let mut block = vec![0; TRACKER_BLOCK_LEN];
+        block[0..4].copy_from_slice(&(TRACKER_BLOCK_LEN as u32).to_le_bytes());
+        block[4..8].copy_from_slice(&TRACKER_SIGNATURE.to_le_bytes());
+        block[8..12].copy_from_slice(&0x58u32.to_le_bytes());
+        block[16..16 + machine.len()].copy_from_slice(machine);
+        block[48 + 6..48 + 8].copy_from_slice(&0x11D1u16.to_le_bytes());
+        block[48 + 10..48 + 16].copy_from_slice(&[0x00, 0x15, 0x5D, 0x01, 0x02, 0x03]);
+        block
+    }
+
+    /// A `KnownFolderDataBlock`, which is kept.
+    fn known_folder() -> Vec<u8> {
+        let mut block = vec![0xAB; 0x1C];
+        block[0..4].copy_from_slice(&0x1Cu32.to_le_bytes());
+        block[4..8].copy_from_slice(&0xA000_000Bu32.to_le_bytes());
+        block
+    }
+
+    /// Builds a shortcut with an ID list, `LinkInfo`, a Unicode name and the
+    /// given extra data blocks, followed by the terminal block.
+    fn shortcut(blocks: &[&[u8]]) -> Vec<u8> {
+        let flags = HAS_LINK_TARGET_ID_LIST | HAS_LINK_INFO | 0x04 | IS_UNICODE;
+        let id_list: &[u8] = &[2, 0, 0, 0];
+        let name: &[u8] = &[2, 0, b'h', 0, b'i', 0];
+        [&header(flags)[..], id_list, &link_info(), name, &blocks.concat(), &[0; 4]].concat()
+    }
+
+    #[test]
+    fn tracker_and_volume_fields_are_listed() {
+        let data = shortcut(&[&tracker(b"DESKTOP-1")]);
+
+        assert_eq!(
+            lnk_metadata(&data).unwrap(),
+            ["volume serial number", "volume label", "tracker (machine DESKTOP-1, MAC 00:15:5d:01:02:03)"]
+        );
+    }
+
+    #[test]
+    fn trackers_are_drained_and_other_blocks_kept() {
+        let input = shortcut(&[&tracker(b"DESKTOP-1"), &known_folder(), &tracker(b"LAPTOP")]);
+
+        let (output, labels) = strip_lnk_metadata(&input).unwrap();
+
+        let mut expected = shortcut(&[&known_folder()]);
+        let volume = HEADER_LEN + 4 + 0x1C;
+        expected[volume + 8..volume + 12].fill(0);
+        expected[volume + 16..volume + 20].fill(0);
+        assert_eq!(output, expected);
+        assert_eq!(output.len(), input.len() - 2 * TRACKER_BLOCK_LEN);
+        assert_eq!(
+            labels,
+            [
+                "volume serial number",
+                "volume label",
+                "tracker (machine DESKTOP-1, MAC 00:15:5d:01:02:03)",
+                "tracker (machine LAPTOP, MAC 00:15:5d:01:02:03)",
+            ]
+        );
+        assert_eq!(lnk_metadata(&output).unwrap(), Vec::<String>::new());
+        assert_eq!(strip_lnk_metadata(&output), None);
+    }
+
+    #[test]
+    fn unicode_volume_label_is_found_past_its_offset() {
+        let mut volume = [&0u32.to_le_bytes()[..], &3u32.to_le_bytes(), &0u32.to_le_bytes(), &0x14u32.to_le_bytes(), &0x18u32.to_le_bytes()]
+            .concat();
+        volume.extend_from_slice(&[0; 4]);
+        volume.extend_from_slice(&[b'U', 0, b'S', 0, b'B', 0, 0, 0]);
+        let size = volume.len() as u32;
+        volume[0..4].copy_from_slice(&size.to_le_bytes());
+
+        assert_eq!(volume_label_range(&volume), Some(0x18..0x1E));
+    }
+
+    #[test]
+    fn malformed_shortcut_is_not_stripped() {
+        let input = shortcut(&[&tracker(b"DESKTOP-1")]);
+
+        // Cut inside the tracker block
+        assert_eq!(lnk_metadata(&input[..input.len() - 40]), None);
+        assert_eq!(strip_lnk_metadata(&input[..input.len() - 40]), None);
+
+        // An extra data block too small for its own signature
+        let mut short_block = input.clone();
+        let tracker_at = input.len() - 4 - TRACKER_BLOCK_LEN;
+        short_block[tracker_at..tracker_at + 4].copy_from_slice(&6u32.to_le_bytes());
+        assert_eq!(strip_lnk_metadata(&short_block), None);
+
+        // A LinkInfo size running past the end of the file
+        let mut overrun = input.clone();
+        overrun[HEADER_LEN + 4..HEADER_LEN + 8].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(strip_lnk_metadata(&overrun), None);
+
+        // Not the Shell Link CLSID
+        let mut other = input;
+        other[4] ^= 0xFF;
+        assert!(!has_link_header(&other));
+        assert_eq!(lnk_metadata(&other), None);
+    }
+}
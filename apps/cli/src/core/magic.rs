@@ -0,0 +1,113 @@
+//! Format detection from a file's leading bytes.
+//!
+//! Extensions are only a label: a `.docx` renamed `.dat` is still a document,
+//! and a text file named `.jpg` is not an image. Where a format starts with a
+//! recognizable signature, that signature decides which cleaner the file gets;
+//! the extension only settles containers several formats share (a plain ZIP
+//! holding an Office document, a TIFF that is really a camera RAW file) and
+//! picks the cleaner for formats with no signature at all (SVG, XMP, email).
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use super::lnk;
+
+/// Leading bytes read to recognize a file, enough to see the first ZIP
+/// entry's name and an EPUB's `mimetype` contents.
+const SNIFF_LEN: u64 = 64;
+
+/// Major brands of ISO base media files that hold HEIF images.
+const HEIF_BRANDS: &[&[u8; 4]] = &[b"heic", b"heix", b"heim", b"heis", b"hevc", b"hevx", b"hevm", b"hevs", b"mif1", b"msf1"];
+
+/// Major brands of ISO base media files that hold AAC audio.
+const M4A_BRANDS: &[&[u8; 4]] = &[b"M4A ", b"M4B "];
+
+/// A file format recognized from its signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signature {
+    /// A ZIP archive whose first entry doesn't give away what it holds.
+    Zip,
+    /// A ZIP archive starting with an Office Open XML `[Content_Types].xml`.
+    OfficeXml,
+    /// A ZIP archive starting with an EPUB `mimetype` entry.
+    Epub,
+    Jpeg,
+    /// TIFF, and the camera RAW formats built on it.
+    Tiff,
+    /// A Canon CR2, which is TIFF with a `CR` marker after the header.
+    Cr2,
+    Png,
+    Gif,
+    /// ISO base media with a HEIF image brand.
+    Heif,
+    /// ISO base media with an M4A or M4B audio brand.
+    M4a,
+    /// Any other ISO base media (MP4 video, QuickTime, AVIF).
+    IsoMedia,
+    /// An MP3 frame, or an ID3v2 tag in front of MP3 or FLAC audio.
+    Mp3,
+    Flac,
+    /// A RIFF container of WAVE audio.
+    Wave,
+    Pdf,
+    /// A Windows Shell Link (`.lnk`).
+    ShellLink,
+}
+
+impl Signature {
+    /// Recognizes the format of data from its first bytes.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        let signature = match data {
+            [b'P', b'K', 3, 4, ..] => return Some(zip_signature(data)),
+            [0xFF, 0xD8, 0xFF, ..] => Signature::Jpeg,
+            [b'I', b'I', b'*', 0, _, _, _, _, b'C', b'R', ..] => Signature::Cr2,
+            [b'I', b'I', b'*', 0, ..] | [b'M', b'M', 0, b'*', ..] => Signature::Tiff,
+            [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n', ..] => Signature::Png,
+            [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Signature::Gif,
+            [b'I', b'D', b'3', ..] => Signature::Mp3,
+            // Frame sync (eleven set bits) of a Layer III frame; UTF-16 byte order marks don't match
+            [0xFF, second, ..] if second & 0xE6 == 0xE2 => Signature::Mp3,
+            [b'f', b'L', b'a', b'C', ..] => Signature::Flac,
+            [b'%', b'P', b'D', b'F', b'-', ..] => Signature::Pdf,
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => Signature::Wave,
+            [_, _, _, _, b'f', b't', b'y', b'p', brand @ ..] => {
+                let brand = brand.get(..4)?;
+                if HEIF_BRANDS.iter().any(|b| &b[..] == brand) {
+                    Signature::Heif
+                } else if M4A_BRANDS.iter().any(|b| &b[..] == brand) {
+                    Signature::M4a
+                } else {
+                    Signature::IsoMedia
+                }
+            }
+            _ if lnk::has_link_header(data) => Signature::ShellLink,
+            _ => return None,
+        };
+        Some(signature)
+    }
+
+    /// Recognizes the format of a file from its first bytes, or `None` if it has
+    /// no known signature or can't be read.
+    pub fn of_file(path: &Path) -> Option<Self> {
+        let mut data = Vec::with_capacity(SNIFF_LEN as usize);
+        File::open(path).ok()?.take(SNIFF_LEN).read_to_end(&mut data).ok()?;
+        Self::detect(&data)
+    }
+}
+
+/// Tells an EPUB or Office document from other ZIP archives by its first
+/// local entry, which both formats conventionally put first.
+fn zip_signature(data: &[u8]) -> Signature {
+    let field = |offset: usize| data.get(offset..offset + 2).map_or(0, |b| u16::from_le_bytes([b[0], b[1]]) as usize);
+    let name_end = 30 + field(26);
+    let contents = name_end + field(28);
+
+    match data.get(30..name_end) {
+        Some(b"[Content_Types].xml") => Signature::OfficeXml,
+        Some(b"mimetype") if data.get(contents..).is_some_and(|c| c.starts_with(b"application/epub+zip")) => {
+            Signature::Epub
+        }
+        _ => Signature::Zip,
+    }
+}
@@ -3,11 +3,27 @@
 //! This module provides the fundamental operations for clearing file metadata
 //! on Windows systems, including NTFS alternate data streams and file timestamps.
 
+mod audio;
 mod cleaner;
+mod email;
+mod epub;
 mod error;
+mod formats;
+mod heif;
+mod image;
+mod lnk;
+mod magic;
+mod pdf;
+mod svg;
 mod types;
+mod xml_encoding;
+mod xmp;
 
-pub use cleaner::MetadataCleaner;
+pub use cleaner::{dedupe_paths, extended_length_path, validate_sid, MetadataCleaner};
 #[allow(unused_imports)]
 pub use error::{CleanerError, CleanerResult};
-pub use types::{CleanMode, CleanOptions, CleanReport, FileResult};
+pub use formats::FORMAT_CLEANERS;
+pub use types::{
+    CapabilityStatus, CleanMode, CleanOptions, CleanReport, EmailHeaderClass, FileResult,
+    ScanSummary, StepStatus, TimestampTarget,
+};
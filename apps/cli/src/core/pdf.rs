@@ -0,0 +1,213 @@
+//! Document metadata removal for PDF files.
+//!
+//! PDFs describe themselves twice: in the trailer's Info dictionary (author,
+//! producing software, creation date) and in an XMP packet referenced from the
+//! document catalog. Both are removed and the file is written back out with a
+//! fresh cross-reference table, so the result opens like the original.
+
+use lopdf::{Document, Object};
+
+/// Info dictionary entries removed from PDFs, with their display labels.
+const INFO_FIELDS: &[(&str, &str)] = &[
+    ("Author", "Author"),
+    ("Creator", "Creator"),
+    ("Producer", "Producer"),
+    ("Title", "Title"),
+    ("Subject", "Subject"),
+    ("Keywords", "Keywords"),
+    ("CreationDate", "Creation Date"),
+    ("ModDate", "Modification Date"),
+];
+
+/// Label reported for an embedded XMP metadata stream.
+const XMP_LABEL: &str = "XMP Metadata";
+
+/// Parses a PDF, returning `None` if it's invalid or encrypted.
+///
+/// Encrypted documents are left alone: rewriting them would either drop the
+/// encryption or require re-encrypting with keys we don't have.
+fn load(data: &[u8]) -> Option<Document> {
+    Document::load_mem(data).ok().filter(|document| !document.is_encrypted())
+}
+
+/// Returns the labels of the metadata a PDF carries, or `None` if it can't be cleaned.
+pub fn pdf_metadata_fields(data: &[u8]) -> Option<Vec<String>> {
+    let document = load(data)?;
+    let mut fields = Vec::new();
+
+    let info = match document.trailer.get(b"Info") {
+        Ok(Object::Reference(id)) => document.get_dictionary(*id).ok(),
+        Ok(Object::Dictionary(info)) => Some(info),
+        _ => None,
+    };
+    if let Some(info) = info {
+        fields.extend(
+            INFO_FIELDS
+                .iter()
+                .filter(|(key, _)| info.has(key.as_bytes()))
+                .map(|(_, label)| label.to_string()),
+        );
+    }
+
+    if document.catalog().is_ok_and(|catalog| catalog.has(b"Metadata")) {
+        fields.push(XMP_LABEL.to_string());
+    }
+
+    Some(fields)
+}
+
+/// Removes the Info entries and the catalog's XMP stream from a PDF.
+///
+/// Returns `None` if the PDF is invalid, encrypted, or has nothing to remove,
+/// in which case the original bytes should be kept.
+pub fn strip_pdf_metadata(data: &[u8]) -> Option<Vec<u8>> {
+    let mut document = load(data)?;
+    let mut changed = false;
+
+    let info = match document.trailer.get(b"Info") {
+        Ok(Object::Reference(id)) => {
+            let id = *id;
+            document.get_dictionary_mut(id).ok()
+        }
+        Ok(Object::Dictionary(_)) => document.trailer.get_mut(b"Info").and_then(Object::as_dict_mut).ok(),
+        _ => None,
+    };
+    if let Some(info) = info {
+        for (key, _) in INFO_FIELDS {
+            changed |= info.remove(key.as_bytes()).is_some();
+        }
+    }
+
+    let metadata = document
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"Metadata").ok())
+        .and_then(|metadata| metadata.as_reference().ok());
+    if let Some(id) = metadata {
+        // Also drops the catalog's reference to the stream
+        document.delete_object(id);
+        changed = true;
+    }
+    if let Ok(catalog) = document.catalog_mut() {
+        changed |= catalog.remove(b"Metadata").is_some();
+    }
+
+    if !changed {
+        return None;
+    }
+
+    let mut output = Vec::with_capacity(data.len());
+    document.save_to(&mut output).ok()?;
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Page content drawn by every test document.
+    const CONTENT: &[u8] = b"BT /F1 12 Tf (Hello) Tj ET";
+
+    /// Assembles a PDF from numbered object bodies, with an exact xref table
+    /// and the given extra trailer entries.
+    fn pdf<T: AsRef<[u8]>>(objects: &[T], trailer: &str) -> Vec<u8> {
+        let mut data = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (index, body) in objects.iter().enumerate() {
+            offsets.push(data.len());
+            data.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+            data.extend_from_slice(body.as_ref());
+            data.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref = data.len();
+        data.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+        for offset in offsets {
+            data.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        data.extend_from_slice(
+            format!("trailer\n<< /Size {} /Root 1 0 R {} >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, trailer, xref).as_bytes(),
+        );
+        data
+    }
+
+    /// The catalog (object 1, with `catalog` entries), page tree, page and
+    /// content stream of a one-page document.
+    fn page_objects(catalog: &str) -> Vec<Vec<u8>> {
+        vec![
+            format!("<< /Type /Catalog /Pages 2 0 R {}>>", catalog).into_bytes(),
+            b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+            b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 200 200] /Contents 4 0 R >>".to_vec(),
+            [format!("<< /Length {} >>\nstream\n", CONTENT.len()).as_bytes(), CONTENT, b"\nendstream"].concat(),
+        ]
+    }
+
+    /// Info dictionary of the test documents.
+    const INFO: &[u8] = b"<< /Author (Alice) /Producer (Writer 7.1) /Title (Quarterly) /CreationDate (D:20240102030405Z) >>";
+
+    /// A one-page document with an Info dictionary (object 5) and, if `xmp`,
+    /// a catalog XMP stream (object 6).
+    fn document(xmp: bool) -> Vec<u8> {
+        let mut objects = page_objects(if xmp { "/Metadata 6 0 R " } else { "" });
+        objects.push(INFO.to_vec());
+        if xmp {
+            objects.push(b"<< /Type /Metadata /Subtype /XML /Length 13 >>\nstream\n<x:xmpmeta/>\n\nendstream".to_vec());
+        }
+        pdf(&objects, "/Info 5 0 R")
+    }
+
+    #[test]
+    fn info_and_xmp_are_listed() {
+        assert_eq!(
+            pdf_metadata_fields(&document(true)).unwrap(),
+            ["Author", "Producer", "Title", "Creation Date", "XMP Metadata"]
+        );
+        assert_eq!(pdf_metadata_fields(&document(false)).unwrap(), ["Author", "Producer", "Title", "Creation Date"]);
+    }
+
+    #[test]
+    fn info_and_xmp_are_removed_and_the_page_kept() {
+        let input = document(true);
+
+        let output = strip_pdf_metadata(&input).unwrap();
+
+        assert_eq!(pdf_metadata_fields(&output).unwrap(), Vec::<String>::new());
+        assert!(output.len() < input.len());
+        for leaked in [&b"Alice"[..], b"Writer 7.1", b"Quarterly", b"D:2024", b"xmpmeta"] {
+            assert!(!output.windows(leaked.len()).any(|window| window == leaked));
+        }
+
+        let document = Document::load_mem(&output).unwrap();
+        let page = *document.get_pages().get(&1).unwrap();
+        assert_eq!(document.get_page_content(page).unwrap(), CONTENT);
+        assert_eq!(strip_pdf_metadata(&output), None);
+    }
+
+    #[test]
+    fn pdf_without_metadata_is_left_alone() {
+        let input = pdf(&page_objects(""), "");
+
+        assert_eq!(pdf_metadata_fields(&input).unwrap(), Vec::<String>::new());
+        assert_eq!(strip_pdf_metadata(&input), None);
+    }
+
+    #[test]
+    fn encrypted_pdf_is_not_cleaned() {
+        let mut objects = page_objects("");
+        objects.push(INFO.to_vec());
+        objects.push(b"<< /Filter /Standard /V 1 /R 2 /O (owner) /U (user) /P -4 >>".to_vec());
+        let input = pdf(&objects, "/Info 5 0 R /Encrypt 6 0 R /ID [(a) (a)]");
+
+        assert_eq!(pdf_metadata_fields(&input), None);
+        assert_eq!(strip_pdf_metadata(&input), None);
+    }
+
+    #[test]
+    fn malformed_pdf_is_not_cleaned() {
+        let input = document(true);
+
+        assert_eq!(strip_pdf_metadata(&input[..20]), None);
+        assert_eq!(pdf_metadata_fields(b"%PDF-1.4\n1 0 obj\n<< /Author"), None);
+        assert_eq!(strip_pdf_metadata(b"not a pdf"), None);
+    }
+}
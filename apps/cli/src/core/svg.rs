@@ -0,0 +1,169 @@
+//! Metadata removal for SVG images.
+//!
+//! SVG is XML, so authoring details live in ordinary markup: a `<metadata>`
+//! element holding RDF (creator, rights, source), editor bookkeeping in the
+//! Inkscape and Sodipodi namespaces, and comments such as "Generator: Adobe
+//! Illustrator". None of it affects rendering, so it is dropped while every
+//! other event is written back unchanged.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::QName;
+use quick_xml::{Reader, Writer};
+
+/// Namespace prefixes of editor-only elements and attributes
+/// (Inkscape, Sodipodi, Sketch and Affinity Designer).
+const EDITOR_PREFIXES: &[&[u8]] = &[b"sodipodi", b"inkscape", b"sketch", b"serif"];
+
+/// Counts of what [`rewrite`] removed.
+#[derive(Default)]
+struct Removed {
+    metadata: usize,
+    editor_elements: usize,
+    editor_attributes: usize,
+    comments: usize,
+}
+
+impl Removed {
+    /// Describes the removed items, e.g. `["metadata element", "2 comments"]`.
+    fn labels(&self) -> Vec<String> {
+        let mut labels = Vec::new();
+        if self.metadata > 0 {
+            labels.push("metadata element".to_string());
+        }
+        if self.editor_elements > 0 {
+            labels.push(counted(self.editor_elements, "editor element"));
+        }
+        if self.editor_attributes > 0 {
+            labels.push(counted(self.editor_attributes, "editor attribute"));
+        }
+        if self.comments > 0 {
+            labels.push(counted(self.comments, "comment"));
+        }
+        labels
+    }
+}
+
+/// Formats a count with its noun, e.g. `1 comment` or `3 comments`.
+fn counted(count: usize, noun: &str) -> String {
+    format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+}
+
+/// Returns true for names bound to an editor prefix, e.g. `inkscape:label`.
+fn is_editor_name(name: QName) -> bool {
+    name.prefix().is_some_and(|prefix| EDITOR_PREFIXES.contains(&prefix.as_ref()))
+}
+
+/// Returns true for elements dropped together with their content.
+fn is_dropped_element(start: &BytesStart, removed: &mut Removed) -> bool {
+    let name = start.name();
+    if name.local_name().as_ref() == b"metadata" && name.prefix().is_none_or(|p| p.as_ref() == b"svg") {
+        removed.metadata += 1;
+        true
+    } else if is_editor_name(name) {
+        removed.editor_elements += 1;
+        true
+    } else {
+        false
+    }
+}
+
+/// Copies an element without editor attributes or the declarations of their prefixes.
+fn without_editor_attributes<'a>(start: &BytesStart<'a>, removed: &mut Removed) -> Option<BytesStart<'a>> {
+    let mut cleaned = start.clone();
+    cleaned.clear_attributes();
+
+    for attribute in start.attributes() {
+        let attribute = attribute.ok()?;
+        let key = attribute.key;
+        let declares_editor = key.prefix().is_some_and(|p| p.as_ref() == b"xmlns")
+            && EDITOR_PREFIXES.contains(&key.local_name().as_ref());
+
+        if is_editor_name(key) || declares_editor {
+            removed.editor_attributes += 1;
+        } else {
+            cleaned.push_attribute(attribute);
+        }
+    }
+
+    Some(cleaned)
+}
+
+/// Rewrites an SVG without its metadata, returning the new text and what was removed.
+///
+/// Returns `None` if the text isn't well-formed XML with an `<svg>` root, so
+/// binary files misnamed `.svg` are never rewritten.
+fn rewrite(text: &str) -> Option<(String, Removed)> {
+    let mut reader = Reader::from_str(text);
+    let mut writer = Writer::new(Vec::with_capacity(text.len()));
+    let mut removed = Removed::default();
+    let mut seen_root = false;
+    // Elements open at the current position, to reject truncated documents
+    let mut open = 0usize;
+    // Nesting depth inside an element being dropped
+    let mut dropping: Option<usize> = None;
+
+    loop {
+        let event = reader.read_event().ok()?;
+
+        if let Event::Start(start) | Event::Empty(start) = &event {
+            if !seen_root {
+                if start.name().local_name().as_ref() != b"svg" {
+                    return None;
+                }
+                seen_root = true;
+            }
+        }
+        match &event {
+            Event::Start(_) => open += 1,
+            Event::End(_) => open = open.checked_sub(1)?,
+            _ => {}
+        }
+
+        match (&mut dropping, event) {
+            (_, Event::Eof) => break,
+            (Some(depth), Event::Start(_)) => *depth += 1,
+            (Some(0), Event::End(_)) => dropping = None,
+            (Some(depth), Event::End(_)) => *depth -= 1,
+            (Some(_), _) => {}
+            (None, Event::Start(start)) => {
+                if is_dropped_element(&start, &mut removed) {
+                    dropping = Some(0);
+                } else {
+                    let start = without_editor_attributes(&start, &mut removed)?;
+                    writer.write_event(Event::Start(start)).ok()?;
+                }
+            }
+            (None, Event::Empty(start)) => {
+                if !is_dropped_element(&start, &mut removed) {
+                    let start = without_editor_attributes(&start, &mut removed)?;
+                    writer.write_event(Event::Empty(start)).ok()?;
+                }
+            }
+            (None, Event::Comment(_)) => removed.comments += 1,
+            (None, event) => writer.write_event(event).ok()?,
+        }
+    }
+
+    if !seen_root || open != 0 {
+        return None;
+    }
+
+    Some((String::from_utf8_lossy(&writer.into_inner()).into_owned(), removed))
+}
+
+/// Returns labels for the metadata an SVG carries, or `None` if it isn't a valid SVG.
+pub fn svg_metadata(text: &str) -> Option<Vec<String>> {
+    rewrite(text).map(|(_, removed)| removed.labels())
+}
+
+/// Removes the `<metadata>` element, editor elements and attributes, and comments.
+///
+/// Returns `None` if the SVG is invalid or has nothing to remove, in which case
+/// the original should be kept.
+pub fn strip_svg_metadata(text: &str) -> Option<String> {
+    let (cleaned, removed) = rewrite(text)?;
+    if removed.labels().is_empty() {
+        return None;
+    }
+    Some(cleaned)
+}
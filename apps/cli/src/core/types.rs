@@ -4,7 +4,15 @@
 
 #![allow(dead_code)]
 
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 /// Specifies how deeply to clean files in a directory.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -28,6 +36,80 @@ impl std::fmt::Display for CleanMode {
     }
 }
 
+/// Groups of email headers that can be stripped from saved messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailHeaderClass {
+    /// Delivery path: `Received` chains, originating IPs, authentication results.
+    Routing,
+    /// The composing client: `User-Agent`, `X-Mailer`, etc.
+    Client,
+    /// Any other `X-` vendor header (spam scores, tenant IDs, tracking IDs).
+    Vendor,
+}
+
+/// The neutral timestamp (2000-01-01 00:00:00 UTC) as seconds since the Unix epoch.
+const NEUTRAL_UNIX_SECS: u64 = 946_684_800;
+
+/// How far a file's timestamp may be from its target and still match it:
+/// FAT and exFAT store modification times to the nearest two seconds.
+const TIMESTAMP_TOLERANCE: Duration = Duration::from_secs(2);
+
+/// What file timestamps are set to when they are reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimestampTarget {
+    /// A specific point in time.
+    Fixed(SystemTime),
+    /// The time of the clean.
+    Now,
+    /// January 1, 2000 00:00:00 UTC, a neutral date that reveals nothing.
+    #[default]
+    Epoch2000,
+    /// Leave timestamps as they are.
+    Unchanged,
+    /// A separate random time for each file, up to `range` before the clean.
+    /// Each file's time is drawn from `seed` and its path, so a run seeded
+    /// afresh shares no times with earlier ones.
+    Random { range: Duration, seed: u64 },
+}
+
+impl TimestampTarget {
+    /// Returns the time to write to `path`, or `None` if timestamps are left unchanged.
+    pub fn resolve(self, path: &Path) -> Option<SystemTime> {
+        match self {
+            TimestampTarget::Fixed(time) => Some(time),
+            TimestampTarget::Now => Some(SystemTime::now()),
+            TimestampTarget::Epoch2000 => Some(UNIX_EPOCH + Duration::from_secs(NEUTRAL_UNIX_SECS)),
+            TimestampTarget::Unchanged => None,
+            TimestampTarget::Random { range, seed } => {
+                let mut hasher = DefaultHasher::new();
+                path.hash(&mut hasher);
+                let offset = fastrand::Rng::with_seed(seed ^ hasher.finish()).u64(0..=range.as_secs());
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+                // Even whole seconds, which FAT and exFAT store without rounding
+                Some(UNIX_EPOCH + Duration::from_secs(now.saturating_sub(offset) & !1))
+            }
+        }
+    }
+
+    /// Returns true if `time` is what cleaning `path` sets: any time within the
+    /// range for `Random`, otherwise the resolved time give or take the two
+    /// seconds FAT rounds to.
+    pub fn matches(self, path: &Path, time: SystemTime) -> bool {
+        let distance = |a: SystemTime, b: SystemTime| a.duration_since(b).unwrap_or_else(|e| e.duration());
+        match self {
+            TimestampTarget::Random { range, .. } => match SystemTime::now().duration_since(time) {
+                Ok(age) => age <= range + TIMESTAMP_TOLERANCE,
+                Err(e) => e.duration() <= TIMESTAMP_TOLERANCE,
+            },
+            _ => self.resolve(path).is_none_or(|target| distance(target, time) <= TIMESTAMP_TOLERANCE),
+        }
+    }
+}
+
+/// Owner given to files when clearing ownership: `S-1-1-0` (Everyone), which
+/// names no user, group or machine.
+pub const DEFAULT_OWNER_SID: &str = "S-1-1-0";
+
 /// Options for controlling the cleaning behavior.
 #[derive(Debug, Clone, Default)]
 pub struct CleanOptions {
@@ -39,12 +121,88 @@ pub struct CleanOptions {
     pub clear_attributes: bool,
     /// Whether to clear file owner information.
     pub clear_owner: bool,
+    /// Whether to delete the NTFS object ID, whose birth volume and object GUIDs track the file.
+    pub clear_object_id: bool,
+    /// String SID that replaces the owner when `clear_owner` is set ([`DEFAULT_OWNER_SID`] if `None`).
+    pub owner_sid: Option<String>,
     /// Whether to clear file properties (author, computer, etc.).
     pub clear_properties: bool,
+    /// What timestamps are set to when they are cleared.
+    pub timestamp_target: TimestampTarget,
+    /// Whether to also reset the NTFS change time, which `SetFileTime` can't reach,
+    /// through `NtSetInformationFile` (set by admin mode).
+    pub set_change_time: bool,
+    /// Whether to move the creation time back so it is never later than the modification time.
+    pub fix_timestamp_order: bool,
+    /// Which header classes to strip from saved emails (.eml/.msg).
+    pub email_header_classes: Vec<EmailHeaderClass>,
+    /// Encoding assumed for XML parts that declare none (UTF-8 when unset).
+    pub xml_fallback_encoding: Option<&'static encoding_rs::Encoding>,
+    /// Whether to overwrite files through their existing handle instead of
+    /// writing a temp file and renaming it over the original.
+    pub in_place: bool,
+    /// Directory the originals are copied to before they are modified.
+    pub backup_dir: Option<PathBuf>,
+    /// Directory cleaned copies are written to; the originals are only read.
+    pub output_dir: Option<PathBuf>,
+    /// Folder whose layout is mirrored below `output_dir` (files outside it
+    /// mirror their full path instead).
+    pub output_root: Option<PathBuf>,
+    /// Number of files cleaned in parallel (`None` uses one thread per CPU).
+    pub jobs: Option<usize>,
+    /// Most archives (Office documents, EPUBs, ZIPs) rewritten at once, within
+    /// `jobs`; `None` leaves it to `jobs` alone.
+    pub parallel_archives: Option<usize>,
+    /// Set (e.g. from a Ctrl-C handler) to stop a run once the files in progress finish.
+    pub cancel_flag: Option<Arc<AtomicBool>>,
+    /// Whether read-only files get their read-only attribute back after cleaning.
+    pub preserve_readonly: bool,
+    /// Whether every named stream is removed, not just known metadata streams.
+    pub all_streams: bool,
+    /// Stream names never removed, even with `all_streams`.
+    pub keep_streams: Vec<String>,
+    /// Patterns of stream names that are never removed, for streams with varying names.
+    pub keep_stream_patterns: Vec<regex_lite::Regex>,
+    /// Whether streams are overwritten with zeros before they are deleted.
+    pub shred_streams: bool,
+    /// Whether Office rewrites copy every entry but the property parts byte for byte.
+    pub metadata_only: bool,
+    /// How many times a file held open by another process is retried, with backoff.
+    pub retries: u32,
+    /// How long one file may take to clean before it's abandoned and marked failed.
+    pub timeout: Option<Duration>,
+    /// Whether files are cleaned even when a pre-check finds nothing to remove.
+    pub force: bool,
+    /// Whether each file's SHA-256 is recorded before and after cleaning.
+    pub hash: bool,
+    /// Whether each cleaned file is re-inspected, and failed if metadata remains.
+    pub verify: bool,
+    /// Whether the entries of ZIP archives are cleaned too.
+    pub into_archives: bool,
+    /// How many levels of archives nested in archives are opened (1 is the archive itself).
+    pub max_archive_depth: usize,
+    /// How many folder levels recursive mode descends (1 is the folder itself); unlimited if `None`.
+    pub max_depth: Option<usize>,
+    /// Whether directory modes follow symlinks instead of skipping them.
+    pub follow_symlinks: bool,
+    /// Globs a file must match to be cleaned in directory modes (all files when empty).
+    pub include_globs: Vec<String>,
+    /// Globs for files and directories skipped in directory modes; wins over `include_globs`.
+    pub exclude_globs: Vec<String>,
+    /// Directory modes only clean files last modified at least this long ago.
+    pub older_than: Option<Duration>,
+    /// Directory modes only clean files last modified at most this long ago.
+    pub newer_than: Option<Duration>,
+    /// Whether directory modes also reset the timestamps of the folders they walk.
+    pub clean_dirs: bool,
+    /// Whether directory modes delete OS-generated folder files (`Thumbs.db`, `desktop.ini`, `.DS_Store`).
+    pub remove_junk: bool,
     /// Whether to run in dry-run mode (no actual changes).
     pub dry_run: bool,
     /// Whether to show verbose output.
     pub verbose: bool,
+    /// Paths that must never be modified (the running executable, log and state files).
+    pub protected_paths: Vec<PathBuf>,
 }
 
 impl CleanOptions {
@@ -55,12 +213,60 @@ impl CleanOptions {
             clear_streams: true,
             clear_attributes: true,
             clear_owner: false, // Requires admin, disabled by default
+            clear_object_id: true,
+            set_change_time: false,
+            owner_sid: None,
             clear_properties: true,
+            timestamp_target: TimestampTarget::Epoch2000,
+            fix_timestamp_order: false,
+            email_header_classes: vec![EmailHeaderClass::Routing, EmailHeaderClass::Client],
+            xml_fallback_encoding: None,
+            in_place: false,
+            backup_dir: None,
+            output_dir: None,
+            output_root: None,
+            jobs: None,
+            parallel_archives: None,
+            cancel_flag: None,
+            preserve_readonly: false,
+            retries: 0,
+            timeout: None,
+            force: false,
+            hash: false,
+            verify: false,
+            into_archives: false,
+            max_archive_depth: 2,
+            max_depth: None,
+            metadata_only: false,
+            all_streams: false,
+            keep_streams: Vec::new(),
+            keep_stream_patterns: Vec::new(),
+            shred_streams: false,
+            follow_symlinks: false,
+            include_globs: Vec::new(),
+            exclude_globs: Vec::new(),
+            older_than: None,
+            newer_than: None,
+            clean_dirs: false,
+            remove_junk: false,
             dry_run: false,
             verbose: false,
+            protected_paths: Vec::new(),
         }
     }
 
+    /// Returns true if at least one cleaning step is enabled.
+    pub fn has_enabled_step(&self) -> bool {
+        self.clear_timestamps
+            || self.clear_streams
+            || self.clear_attributes
+            || self.clear_owner
+            || self.clear_object_id
+            || self.set_change_time
+            || self.clear_properties
+            || self.fix_timestamp_order
+    }
+
     /// Sets dry-run mode.
     pub fn with_dry_run(mut self, dry_run: bool) -> Self {
         self.dry_run = dry_run;
@@ -73,18 +279,374 @@ impl CleanOptions {
         self
     }
 
-    /// Sets admin mode (enables owner clearing which requires elevated privileges).
+    /// Sets admin mode (enables owner clearing and change time resetting, which may
+    /// require elevated privileges).
     pub fn with_admin(mut self, admin: bool) -> Self {
         self.clear_owner = admin;
+        self.set_change_time = admin;
+        self
+    }
+
+    /// Sets whether the file owner is cleared, independently of the change time
+    /// that [`with_admin`](Self::with_admin) also resets.
+    pub fn with_clear_owner(mut self, clear_owner: bool) -> Self {
+        self.clear_owner = clear_owner;
+        self
+    }
+
+    /// Sets the SID given ownership when the owner is cleared (see [`DEFAULT_OWNER_SID`]).
+    pub fn with_owner_sid(mut self, sid: Option<String>) -> Self {
+        self.owner_sid = sid;
+        self
+    }
+
+    /// Sets whether timestamps are reset.
+    pub fn with_clear_timestamps(mut self, clear_timestamps: bool) -> Self {
+        self.clear_timestamps = clear_timestamps;
+        self
+    }
+
+    /// Sets whether the NTFS object ID is deleted (Windows only).
+    pub fn with_clear_object_id(mut self, clear_object_id: bool) -> Self {
+        self.clear_object_id = clear_object_id;
         self
     }
+
+    /// Sets whether alternate data streams (provenance extended attributes elsewhere) are removed.
+    pub fn with_clear_streams(mut self, clear_streams: bool) -> Self {
+        self.clear_streams = clear_streams;
+        self
+    }
+
+    /// Sets whether document, email, image and PDF properties are cleared.
+    pub fn with_clear_properties(mut self, clear_properties: bool) -> Self {
+        self.clear_properties = clear_properties;
+        self
+    }
+
+    /// Sets whether inverted timestamps (created after modified) are normalized.
+    pub fn with_fix_timestamp_order(mut self, fix: bool) -> Self {
+        self.fix_timestamp_order = fix;
+        self
+    }
+
+    /// Sets what timestamps are reset to.
+    pub fn with_timestamp_target(mut self, target: TimestampTarget) -> Self {
+        self.timestamp_target = target;
+        self
+    }
+
+    /// Enables in-place overwrites, which keep the file's identity (inode, file ID)
+    /// but can leave a truncated file behind if interrupted.
+    pub fn with_in_place(mut self, in_place: bool) -> Self {
+        self.in_place = in_place;
+        self
+    }
+
+    /// Copies each original into `dir` (mirroring its full path) before it is cleaned.
+    pub fn with_backup_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.backup_dir = dir;
+        self
+    }
+
+    /// Cleans copies written into `dir` instead of the originals, mirroring the
+    /// files' paths relative to `root`.
+    pub fn with_output_dir(mut self, dir: Option<PathBuf>, root: Option<PathBuf>) -> Self {
+        self.output_dir = dir;
+        self.output_root = root;
+        self
+    }
+
+    /// Caps how many files are cleaned at once.
+    pub fn with_jobs(mut self, jobs: Option<usize>) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Caps how many archives are rewritten at once, bounding memory and disk
+    /// load on trees full of large documents.
+    pub fn with_parallel_archives(mut self, limit: Option<usize>) -> Self {
+        self.parallel_archives = limit;
+        self
+    }
+
+    /// Stops directory and batch runs between files once `flag` is set.
+    ///
+    /// Files already being cleaned always finish, so an interrupt never leaves
+    /// one half-written; the rest are counted in [`CleanReport::not_processed`].
+    pub fn with_cancel_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.cancel_flag = Some(flag);
+        self
+    }
+
+    /// Restores the read-only attribute on files that had it once they are cleaned.
+    pub fn with_preserve_readonly(mut self, preserve_readonly: bool) -> Self {
+        self.preserve_readonly = preserve_readonly;
+        self
+    }
+
+    /// Removes every named stream instead of only those known to hold metadata.
+    pub fn with_all_streams(mut self, all_streams: bool) -> Self {
+        self.all_streams = all_streams;
+        self
+    }
+
+    /// Protects streams with these names (case-insensitive) from removal.
+    pub fn with_keep_streams(mut self, keep_streams: Vec<String>) -> Self {
+        self.keep_streams = keep_streams;
+        self
+    }
+
+    /// Protects streams whose names match any of these patterns from removal.
+    ///
+    /// The patterns are compiled by the caller, so an invalid one fails before
+    /// any file is touched; build them case-insensitive to match how NTFS
+    /// compares stream names.
+    pub fn with_keep_stream_patterns(mut self, patterns: Vec<regex_lite::Regex>) -> Self {
+        self.keep_stream_patterns = patterns;
+        self
+    }
+
+    /// Overwrites each stream's content with zeros before deleting it, so it
+    /// can't be recovered from the freed clusters.
+    ///
+    /// SSDs remap writes for wear leveling, so there the old content may
+    /// survive in cells the overwrite never reaches.
+    pub fn with_shred_streams(mut self, shred_streams: bool) -> Self {
+        self.shred_streams = shred_streams;
+        self
+    }
+
+    /// Copies untouched Office archive entries raw instead of recompressing them,
+    /// so only `docProps/core.xml` and `docProps/app.xml` differ after cleaning.
+    pub fn with_metadata_only(mut self, metadata_only: bool) -> Self {
+        self.metadata_only = metadata_only;
+        self
+    }
+
+    /// Records each file's SHA-256 before and after cleaning (not in dry runs).
+    pub fn with_hash(mut self, hash: bool) -> Self {
+        self.hash = hash;
+        self
+    }
+
+    /// Re-inspects each file after cleaning and fails it if metadata remains
+    /// (not in dry runs).
+    pub fn with_verify(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// Retries files that are in use by another process up to `retries` times.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Abandons files that take longer than `timeout` to clean, marking them failed.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Rewrites files even when they already look clean, instead of skipping them,
+    /// and signed Office documents despite breaking their signature.
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Cleans the entries of ZIP archives, opening nested archives up to `max_depth` levels deep.
+    pub fn with_into_archives(mut self, into_archives: bool, max_depth: usize) -> Self {
+        self.into_archives = into_archives;
+        self.max_archive_depth = max_depth;
+        self
+    }
+
+    /// Limits how deep recursive mode descends; `Some(1)` behaves like shallow mode.
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Makes directory modes follow symlinks (cycles are still detected and skipped).
+    pub fn with_follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Limits directory modes to files matching one of these globs.
+    pub fn with_include_globs(mut self, globs: Vec<String>) -> Self {
+        self.include_globs = globs;
+        self
+    }
+
+    /// Skips files and directories matching one of these globs in directory modes.
+    pub fn with_exclude_globs(mut self, globs: Vec<String>) -> Self {
+        self.exclude_globs = globs;
+        self
+    }
+
+    /// Limits directory modes to files last modified at least `age` ago.
+    pub fn with_older_than(mut self, age: Option<Duration>) -> Self {
+        self.older_than = age;
+        self
+    }
+
+    /// Limits directory modes to files last modified at most `age` ago.
+    pub fn with_newer_than(mut self, age: Option<Duration>) -> Self {
+        self.newer_than = age;
+        self
+    }
+
+    /// Makes directory modes reset folder timestamps too, after the files inside them.
+    pub fn with_clean_dirs(mut self, clean_dirs: bool) -> Self {
+        self.clean_dirs = clean_dirs;
+        self
+    }
+
+    /// Makes directory modes delete `Thumbs.db`, `desktop.ini` and similar files instead of cleaning them.
+    pub fn with_remove_junk(mut self, remove_junk: bool) -> Self {
+        self.remove_junk = remove_junk;
+        self
+    }
+
+    /// Sets which email header classes are stripped.
+    pub fn with_email_header_classes(mut self, classes: Vec<EmailHeaderClass>) -> Self {
+        self.email_header_classes = classes;
+        self
+    }
+
+    /// Sets the encoding used for XML parts without a BOM or encoding declaration.
+    pub fn with_xml_fallback_encoding(mut self, encoding: Option<&'static encoding_rs::Encoding>) -> Self {
+        self.xml_fallback_encoding = encoding;
+        self
+    }
+
+    /// Adds a path that is skipped during cleaning because the tool itself is using it.
+    pub fn with_protected_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.protected_paths.push(path.into());
+        self
+    }
+}
+
+/// Whether a cleaner ran on a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    /// The cleaner ran successfully.
+    Ran,
+    /// The cleaner was disabled or didn't apply to this file.
+    Skipped,
+    /// The cleaner ran and failed.
+    Failed,
+    /// Dry run: the cleaner would run.
+    Planned,
+}
+
+impl std::fmt::Display for StepStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StepStatus::Ran => write!(f, "ran"),
+            StepStatus::Skipped => write!(f, "skipped"),
+            StepStatus::Failed => write!(f, "failed"),
+            StepStatus::Planned => write!(f, "would run"),
+        }
+    }
+}
+
+/// Record of a single cleaner module's run on a file.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CleanerStep {
+    /// Short name of the cleaner (e.g. "streams", "office-xml").
+    pub name: String,
+    /// Whether the cleaner ran, was skipped, or failed.
+    pub status: StepStatus,
+    /// What the cleaner reported (e.g. "2 removed", "not an Office document").
+    pub detail: Option<String>,
+}
+
+impl CleanerStep {
+    /// Creates a record for a cleaner that ran.
+    pub fn ran(name: &str) -> Self {
+        Self::new(name, StepStatus::Ran, None)
+    }
+
+    /// Creates a record for a cleaner that ran and reported something.
+    pub fn ran_with(name: &str, detail: impl Into<String>) -> Self {
+        Self::new(name, StepStatus::Ran, Some(detail.into()))
+    }
+
+    /// Creates a record for a cleaner that was skipped.
+    pub fn skipped(name: &str, reason: impl Into<String>) -> Self {
+        Self::new(name, StepStatus::Skipped, Some(reason.into()))
+    }
+
+    /// Creates a dry-run record for a cleaner that would run.
+    pub fn planned(name: &str, detail: impl Into<String>) -> Self {
+        Self::new(name, StepStatus::Planned, Some(detail.into()))
+    }
+
+    /// Creates a record for a cleaner that failed.
+    pub fn failed(name: &str, error: impl Into<String>) -> Self {
+        Self::new(name, StepStatus::Failed, Some(error.into()))
+    }
+
+    fn new(name: &str, status: StepStatus, detail: Option<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            detail,
+        }
+    }
+}
+
+impl std::fmt::Display for CleanerStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.detail {
+            Some(detail) => write!(f, "{}: {} ({})", self.name, self.status, detail),
+            None => write!(f, "{}: {}", self.name, self.status),
+        }
+    }
+}
+
+/// File size before and after a cleaner rewrote it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SizeChange {
+    /// Size in bytes before the rewrite.
+    pub before: u64,
+    /// Size in bytes after the rewrite.
+    pub after: u64,
+}
+
+impl SizeChange {
+    /// Creates a size change record.
+    pub fn new(before: u64, after: u64) -> Self {
+        Self { before, after }
+    }
+
+    /// Returns the change in bytes (positive when the file grew).
+    pub fn delta(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+}
+
+impl std::fmt::Display for SizeChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} -> {} bytes, {:+}", self.before, self.after, self.delta())
+    }
 }
 
 /// Result of cleaning a single file.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FileResult {
-    /// Path to the file that was processed.
+    /// Path to the file that was processed, canonicalized (or the cleaned copy
+    /// with `output_dir`).
     pub path: PathBuf,
+    /// The file's path as the caller gave it, for messages and logs; the same
+    /// as `path` when none was given (and empty in older reports).
+    #[serde(default)]
+    pub display_path: PathBuf,
     /// Whether the cleaning was successful.
     pub success: bool,
     /// Error message if cleaning failed.
@@ -93,35 +655,190 @@ pub struct FileResult {
     pub streams_removed: usize,
     /// Whether timestamps were reset.
     pub timestamps_reset: bool,
+    /// Which cleaners ran on the file and what each reported, in order.
+    pub steps: Vec<CleanerStep>,
+    /// Archive size before and after the Office rewrite, if one happened.
+    pub office_size: Option<SizeChange>,
+    /// Whether an EXIF segment (or EXIF/GPS IFDs) was stripped from an image.
+    #[serde(default)]
+    pub exif_stripped: bool,
+    /// In dry-run mode, what cleaning would change (e.g. "would remove 2 streams").
+    #[serde(default)]
+    pub planned_actions: Vec<String>,
+    /// Names of the alternate data streams (extended attributes elsewhere) removed.
+    #[serde(default)]
+    pub streams_removed_names: Vec<String>,
+    /// Property fields and property streams that were cleared, e.g. "Author", "PDF Producer".
+    #[serde(default)]
+    pub properties_cleared: Vec<String>,
+    /// Whether the file owner was replaced.
+    #[serde(default)]
+    pub owner_cleared: bool,
+    /// Whether the file had an NTFS object ID that was deleted.
+    #[serde(default)]
+    pub object_id_removed: bool,
+    /// Whether the file failed because another process had it open or locked.
+    #[serde(default)]
+    pub in_use: bool,
+    /// Whether the file was left untouched because a pre-check found nothing to clean.
+    #[serde(default)]
+    pub already_clean: bool,
+    /// Size of the file and its alternate data streams before and after cleaning.
+    #[serde(default)]
+    pub size: Option<SizeChange>,
+    /// Hex SHA-256 of the original's contents before cleaning, with `hash`.
+    #[serde(default)]
+    pub sha256_before: Option<String>,
+    /// Hex SHA-256 of the cleaned file's contents, with `hash`.
+    #[serde(default)]
+    pub sha256_after: Option<String>,
+    /// Entries cleaned inside the file when it is an archive, e.g. "photos.zip/trip.jpg (exif)".
+    #[serde(default)]
+    pub archive_entries_cleaned: Vec<String>,
+    /// Cleaners that failed without failing the file, e.g. a document whose
+    /// properties couldn't be parsed after its streams and timestamps were cleaned.
+    #[serde(default)]
+    pub warnings: Vec<String>,
 }
 
 impl FileResult {
     /// Creates a successful file result.
     pub fn success(path: PathBuf, streams_removed: usize, timestamps_reset: bool) -> Self {
         Self {
+            display_path: path.clone(),
             path,
             success: true,
             error: None,
             streams_removed,
             timestamps_reset,
+            steps: Vec::new(),
+            office_size: None,
+            exif_stripped: false,
+            planned_actions: Vec::new(),
+            streams_removed_names: Vec::new(),
+            properties_cleared: Vec::new(),
+            owner_cleared: false,
+            object_id_removed: false,
+            in_use: false,
+            already_clean: false,
+            size: None,
+            sha256_before: None,
+            sha256_after: None,
+            archive_entries_cleaned: Vec::new(),
+            warnings: Vec::new(),
         }
     }
 
     /// Creates a failed file result.
     pub fn failure(path: PathBuf, error: impl Into<String>) -> Self {
         Self {
+            display_path: path.clone(),
             path,
             success: false,
             error: Some(error.into()),
             streams_removed: 0,
             timestamps_reset: false,
+            steps: Vec::new(),
+            office_size: None,
+            exif_stripped: false,
+            planned_actions: Vec::new(),
+            streams_removed_names: Vec::new(),
+            properties_cleared: Vec::new(),
+            owner_cleared: false,
+            object_id_removed: false,
+            in_use: false,
+            already_clean: false,
+            size: None,
+            sha256_before: None,
+            sha256_after: None,
+            archive_entries_cleaned: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Creates a result for a file skipped because it carried nothing to clean.
+    pub fn unchanged(path: PathBuf) -> Self {
+        Self {
+            already_clean: true,
+            ..Self::success(path, 0, false)
         }
     }
+
+    /// Attaches the record of which cleaners ran.
+    pub fn with_steps(mut self, steps: Vec<CleanerStep>) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    /// Names the file as the caller gave it, in `display_path` and in the error,
+    /// warnings and step details, which otherwise show the canonical path (with
+    /// its `\\?\` prefix on Windows) and every folder above the file.
+    pub fn with_display_path(mut self, display_path: &Path) -> Self {
+        let canonical = self.path.display().to_string();
+        let given = display_path.display().to_string();
+        let relabel = |text: &mut String| {
+            if text.contains(&canonical) {
+                *text = text.replace(&canonical, &given);
+            }
+        };
+
+        self.error.iter_mut().for_each(relabel);
+        self.warnings.iter_mut().for_each(relabel);
+        self.steps.iter_mut().filter_map(|step| step.detail.as_mut()).for_each(relabel);
+        self.display_path = display_path.to_path_buf();
+        self
+    }
+
+    /// Attaches the changes a dry run found it would make.
+    pub fn with_planned_actions(mut self, actions: Vec<String>) -> Self {
+        self.planned_actions = actions;
+        self
+    }
+
+    /// Returns one line per kind of specific change, e.g. `streams: Zone.Identifier`
+    /// or `properties: Author, Company`; empty if nothing was recorded.
+    pub fn changes_summary(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if !self.streams_removed_names.is_empty() {
+            lines.push(format!("streams: {}", self.streams_removed_names.join(", ")));
+        }
+        if !self.properties_cleared.is_empty() {
+            lines.push(format!("properties: {}", self.properties_cleared.join(", ")));
+        }
+        if self.owner_cleared {
+            lines.push("owner: cleared".to_string());
+        }
+        if self.object_id_removed {
+            lines.push("object ID: removed".to_string());
+        }
+        for entry in &self.archive_entries_cleaned {
+            lines.push(format!("archive entry: {}", entry));
+        }
+        lines
+    }
+
+    /// Returns a one-line summary of the cleaners that ran, e.g.
+    /// `streams: ran (2 removed), office-xml: skipped (not an Office document)`.
+    pub fn steps_summary(&self) -> String {
+        self.steps
+            .iter()
+            .map(|step| step.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
 }
 
+/// Version of the [`CleanReport`] JSON layout, bumped when fields are renamed
+/// or change meaning (new fields alone don't bump it).
+pub const REPORT_SCHEMA_VERSION: u32 = 1;
+
 /// Summary report of a cleaning operation.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct CleanReport {
+    /// Version of this report's layout ([`REPORT_SCHEMA_VERSION`]); 0 in
+    /// reports written before it was recorded.
+    #[serde(default)]
+    pub schema_version: u32,
     /// Total number of files processed.
     pub total_files: usize,
     /// Number of files successfully cleaned.
@@ -132,6 +849,30 @@ pub struct CleanReport {
     pub skipped: usize,
     /// Total alternate data streams removed.
     pub total_streams_removed: usize,
+    /// Number of images whose EXIF data was stripped.
+    #[serde(default)]
+    pub total_exif_stripped: usize,
+    /// Number of files cleaned with warnings (counted in `successful` too).
+    #[serde(default)]
+    pub with_warnings: usize,
+    /// Combined size of the processed files (with their streams) before cleaning.
+    #[serde(default)]
+    pub bytes_before: u64,
+    /// Combined size of the processed files (with their streams) after cleaning.
+    #[serde(default)]
+    pub bytes_after: u64,
+    /// Number of folders whose timestamps were reset (`clean_dirs`).
+    #[serde(default)]
+    pub directories_reset: usize,
+    /// Number of junk files deleted (`remove_junk`).
+    #[serde(default)]
+    pub junk_removed: usize,
+    /// Paths of the junk files deleted, or in a dry run that would be.
+    #[serde(default)]
+    pub junk_files: Vec<PathBuf>,
+    /// Number of files left untouched because the run was interrupted.
+    #[serde(default)]
+    pub not_processed: usize,
     /// Individual file results.
     pub file_results: Vec<FileResult>,
 }
@@ -139,28 +880,322 @@ pub struct CleanReport {
 impl CleanReport {
     /// Creates a new empty report.
     pub fn new() -> Self {
-        Self::default()
+        Self {
+            schema_version: REPORT_SCHEMA_VERSION,
+            ..Self::default()
+        }
     }
 
     /// Adds a file result to the report.
+    ///
+    /// Files found to be already clean are counted as skipped.
     pub fn add_result(&mut self, result: FileResult) {
+        if result.already_clean {
+            self.add_skipped();
+            return;
+        }
+
         self.total_files += 1;
         if result.success {
             self.successful += 1;
             self.total_streams_removed += result.streams_removed;
+            self.total_exif_stripped += usize::from(result.exif_stripped);
+            self.with_warnings += usize::from(!result.warnings.is_empty());
         } else {
             self.failed += 1;
         }
+        if let Some(size) = result.size {
+            self.bytes_before += size.before;
+            self.bytes_after += size.after;
+        }
         self.file_results.push(result);
     }
 
+    /// Adds the result of resetting a folder's timestamps.
+    ///
+    /// Folders are counted separately from files; a folder that fails is
+    /// recorded as a failure so it still shows up and affects the exit code.
+    pub fn add_directory_result(&mut self, result: FileResult) {
+        if result.success {
+            self.directories_reset += 1;
+        } else {
+            self.add_result(result);
+        }
+    }
+
+    /// Adds the result of deleting a junk file.
+    ///
+    /// Like folders, junk files are counted apart from cleaned files; one that
+    /// can't be deleted is recorded as a failure.
+    pub fn add_junk_result(&mut self, result: FileResult) {
+        if result.success {
+            self.junk_removed += 1;
+            self.junk_files.push(result.path);
+        } else {
+            self.add_result(result);
+        }
+    }
+
+    /// Returns how many bytes cleaning freed across all files (negative if they grew).
+    pub fn bytes_reclaimed(&self) -> i64 {
+        self.bytes_before as i64 - self.bytes_after as i64
+    }
+
     /// Marks a file as skipped.
     pub fn add_skipped(&mut self) {
         self.skipped += 1;
     }
 
+    /// Sorts the file results by path, so reports from different runs (or
+    /// different traversal and thread orders) can be diffed line by line.
+    pub fn sort_results(&mut self) {
+        self.file_results.sort_by(|a, b| a.path.cmp(&b.path));
+        self.junk_files.sort();
+    }
+
+    /// Returns the paths of files that failed to clean.
+    pub fn failed_paths(&self) -> Vec<PathBuf> {
+        self.file_results
+            .iter()
+            .filter(|result| !result.success)
+            .map(|result| result.path.clone())
+            .collect()
+    }
+
+    /// Marks a file as not processed because the run was interrupted.
+    pub fn add_not_processed(&mut self) {
+        self.not_processed += 1;
+    }
+
     /// Returns true if all files were successfully cleaned.
+    ///
+    /// An interrupted run never counts, even if every file it reached was cleaned.
     pub fn is_complete_success(&self) -> bool {
-        self.failed == 0
+        self.failed == 0 && self.not_processed == 0
+    }
+}
+
+/// Metadata a format verifier still found in a file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RemainingLeak {
+    /// Verifier that found it (e.g. "streams", "office-xml").
+    pub category: String,
+    /// What was found.
+    pub detail: String,
+}
+
+impl RemainingLeak {
+    /// Creates a new leak record.
+    pub fn new(category: &str, detail: impl Into<String>) -> Self {
+        Self {
+            category: category.to_string(),
+            detail: detail.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for RemainingLeak {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.category, self.detail)
+    }
+}
+
+/// Read-only overview of the metadata in a set of files, from
+/// [`MetadataCleaner::preview_scan`](crate::core::MetadataCleaner::preview_scan).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ScanSummary {
+    /// Number of files scanned.
+    pub total_files: usize,
+    /// Number of files with at least one finding.
+    pub with_metadata: usize,
+    /// Number of files each verifier category found something in (e.g. "streams", "office-xml").
+    pub categories: BTreeMap<String, usize>,
+}
+
+impl ScanSummary {
+    /// Counts one file's findings, each category once however often it appears.
+    pub fn add_file(&mut self, leaks: &[RemainingLeak]) {
+        self.total_files += 1;
+        if leaks.is_empty() {
+            return;
+        }
+
+        self.with_metadata += 1;
+        let mut categories: Vec<&str> = leaks.iter().map(|leak| leak.category.as_str()).collect();
+        categories.sort_unstable();
+        categories.dedup();
+        for category in categories {
+            *self.categories.entry(category.to_string()).or_default() += 1;
+        }
+    }
+}
+
+/// Heuristic estimate of how much sensitive metadata a file carries.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SensitivityScore {
+    /// Total score; higher means more likely to leak identifying information.
+    pub score: u32,
+    /// What contributed to the score (e.g. "Author", "downloaded from cloud storage").
+    pub reasons: Vec<String>,
+}
+
+impl SensitivityScore {
+    /// Adds `points` to the score for the given reason.
+    pub fn add(&mut self, points: u32, reason: impl Into<String>) {
+        self.score += points;
+        self.reasons.push(reason.into());
+    }
+}
+
+impl std::fmt::Display for SensitivityScore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.reasons.is_empty() {
+            write!(f, "{}", self.score)
+        } else {
+            write!(f, "{} ({})", self.score, self.reasons.join(", "))
+        }
+    }
+}
+
+/// An alternate data stream (extended attribute outside Windows) and its size.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StreamInfo {
+    /// Stream name without the leading `:` and `:$DATA` type suffix.
+    pub name: String,
+    /// Size of the stream's contents in bytes.
+    pub size: u64,
+}
+
+/// A populated Office document property and its value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OfficeProperty {
+    /// Display label (e.g. "Author", "Company").
+    pub field: String,
+    /// Text the document stores for it.
+    pub value: String,
+}
+
+/// Read-only snapshot of the metadata a file carries, as shown by `info`.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileInfo {
+    /// Path that was inspected.
+    pub path: PathBuf,
+    /// File size in bytes.
+    pub size: u64,
+    /// Whether the read-only attribute is set.
+    pub readonly: bool,
+    /// Creation time, where the platform records one.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub created: Option<time::OffsetDateTime>,
+    /// Last modification time.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub modified: Option<time::OffsetDateTime>,
+    /// Last access time.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub accessed: Option<time::OffsetDateTime>,
+    /// Whether the file claims to be created after it was last modified.
+    pub inverted_timestamps: bool,
+    /// Alternate data streams other than the main one.
+    pub streams: Vec<StreamInfo>,
+    /// NTFS object ID as a GUID, if the file has one (always `None` outside Windows).
+    pub object_id: Option<String>,
+    /// Populated docProps fields, or `None` if the file isn't an Office document.
+    pub office_properties: Option<Vec<OfficeProperty>>,
+    /// How likely the file is to carry sensitive metadata.
+    pub sensitivity: SensitivityScore,
+    /// Metadata the enabled cleaners would still remove.
+    pub remaining_metadata: Vec<RemainingLeak>,
+}
+
+/// Result of probing one cleaning capability on the current system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CapabilityStatus {
+    /// The operation works.
+    Available,
+    /// The operation works with reduced effect.
+    Limited(String),
+    /// The operation requires Administrator privileges.
+    NeedsAdmin,
+    /// The platform or filesystem doesn't support the operation.
+    Unsupported(String),
+    /// The operation was attempted and failed.
+    Failed(String),
+}
+
+impl std::fmt::Display for CapabilityStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CapabilityStatus::Available => write!(f, "yes"),
+            CapabilityStatus::Limited(reason) => write!(f, "limited ({})", reason),
+            CapabilityStatus::NeedsAdmin => write!(f, "needs admin"),
+            CapabilityStatus::Unsupported(reason) => write!(f, "unsupported ({})", reason),
+            CapabilityStatus::Failed(reason) => write!(f, "failed ({})", reason),
+        }
+    }
+}
+
+/// A cleaning capability and whether it works here.
+#[derive(Debug, Clone)]
+pub struct Capability {
+    /// Short name of the cleaner (matches the step names in `FileResult`).
+    pub name: String,
+    /// Result of the probe.
+    pub status: CapabilityStatus,
+}
+
+impl Capability {
+    /// Creates a capability entry.
+    pub fn new(name: &str, status: CapabilityStatus) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes_or_object_id_alone_is_something_to_clean() {
+        let none = CleanOptions::all()
+            .with_clear_timestamps(false)
+            .with_clear_streams(false)
+            .with_clear_properties(false)
+            .with_clear_object_id(false);
+        let none = CleanOptions { clear_attributes: false, ..none };
+        assert!(!none.has_enabled_step());
+
+        assert!(CleanOptions { clear_attributes: true, ..none.clone() }.has_enabled_step());
+        assert!(none.clone().with_clear_object_id(true).has_enabled_step());
+        assert!(none.with_clear_owner(true).has_enabled_step());
+    }
+
+    #[test]
+    fn random_target_differs_per_file_and_stays_in_range() {
+        let range = Duration::from_secs(365 * 86_400);
+        let target = TimestampTarget::Random { range, seed: 42 };
+
+        let first = target.resolve(Path::new("a.txt")).unwrap();
+        let second = target.resolve(Path::new("b.txt")).unwrap();
+
+        assert_ne!(first, second);
+        for time in [first, second] {
+            let secs = time.duration_since(UNIX_EPOCH).unwrap();
+            assert_eq!(secs.subsec_nanos(), 0);
+            assert_eq!(secs.as_secs() % 2, 0);
+            assert!(target.matches(Path::new("a.txt"), time));
+        }
+        assert!(!target.matches(Path::new("a.txt"), SystemTime::now() - range * 2));
+    }
+
+    #[test]
+    fn fixed_target_allows_fat_rounding() {
+        let target = TimestampTarget::Epoch2000;
+        let neutral = UNIX_EPOCH + Duration::from_secs(NEUTRAL_UNIX_SECS);
+
+        assert!(target.matches(Path::new("a.txt"), neutral + Duration::from_millis(1500)));
+        assert!(!target.matches(Path::new("a.txt"), neutral + Duration::from_secs(3)));
     }
 }
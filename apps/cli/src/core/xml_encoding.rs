@@ -0,0 +1,100 @@
+//! Character encoding detection for XML parts.
+//!
+//! Office and other XML-based formats are usually UTF-8, but older or localized
+//! producers may declare legacy encodings such as windows-1252 or write UTF-16.
+//! Decoding them as UTF-8 would either fail or mangle accented names, so the
+//! encoding is detected from the BOM or the XML declaration instead.
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// XML text decoded from its original encoding, remembering how to re-encode it.
+#[derive(Debug, Clone)]
+pub struct DecodedXml {
+    /// The decoded document text.
+    pub text: String,
+    /// The encoding the document was stored in.
+    pub encoding: &'static Encoding,
+    /// Whether the original bytes started with a byte order mark.
+    pub had_bom: bool,
+}
+
+impl DecodedXml {
+    /// Decodes raw XML bytes, using `fallback` when neither a BOM nor a declaration is present.
+    pub fn decode(bytes: &[u8], fallback: &'static Encoding) -> Self {
+        if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+            let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+            return Self {
+                text: text.into_owned(),
+                encoding,
+                had_bom: true,
+            };
+        }
+
+        let encoding = sniff_utf16(bytes)
+            .or_else(|| declared_encoding(bytes))
+            .unwrap_or(fallback);
+        let (text, _) = encoding.decode_without_bom_handling(bytes);
+
+        Self {
+            text: text.into_owned(),
+            encoding,
+            had_bom: false,
+        }
+    }
+
+    /// Encodes `text` back into the original encoding (including the BOM if there was one).
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        // encoding_rs never encodes to UTF-16, so it's handled by hand
+        if self.encoding == UTF_16LE || self.encoding == UTF_16BE {
+            let little_endian = self.encoding == UTF_16LE;
+            let mut out = Vec::with_capacity(text.len() * 2 + 2);
+            if self.had_bom {
+                out.extend_from_slice(if little_endian { &[0xFF, 0xFE] } else { &[0xFE, 0xFF] });
+            }
+            for unit in text.encode_utf16() {
+                let bytes = if little_endian { unit.to_le_bytes() } else { unit.to_be_bytes() };
+                out.extend_from_slice(&bytes);
+            }
+            return out;
+        }
+
+        let mut out = Vec::with_capacity(text.len() + 3);
+        if self.had_bom && self.encoding == UTF_8 {
+            out.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
+        }
+        let (encoded, _, _) = self.encoding.encode(text);
+        out.extend_from_slice(&encoded);
+        out
+    }
+}
+
+/// Detects BOM-less UTF-16 from the leading `<?` of an XML declaration.
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    match bytes {
+        [0x3C, 0x00, 0x3F, 0x00, ..] => Some(UTF_16LE),
+        [0x00, 0x3C, 0x00, 0x3F, ..] => Some(UTF_16BE),
+        _ => None,
+    }
+}
+
+/// Reads the `encoding="..."` pseudo-attribute from the XML declaration, if any.
+fn declared_encoding(bytes: &[u8]) -> Option<&'static Encoding> {
+    // The declaration is ASCII-compatible in every encoding we can decode here
+    let head = &bytes[..bytes.len().min(256)];
+    let head = String::from_utf8_lossy(head);
+
+    if !head.starts_with("<?xml") {
+        return None;
+    }
+
+    let declaration = &head[..head.find("?>")?];
+    let start = declaration.find("encoding")? + "encoding".len();
+    let rest = declaration[start..].trim_start().strip_prefix('=')?.trim_start();
+
+    let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let label = &rest[1..];
+    let label = &label[..label.find(quote)?];
+
+    // A UTF-16 label on ASCII-compatible bytes is wrong; trust the bytes instead
+    Encoding::for_label(label.trim().as_bytes()).filter(|e| *e != UTF_16LE && *e != UTF_16BE)
+}
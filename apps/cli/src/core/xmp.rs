@@ -0,0 +1,139 @@
+//! Metadata removal for XMP sidecar files.
+//!
+//! Lightroom, Bridge and darktable keep edits next to an image in an `.xmp`
+//! file: an RDF/XML packet whose properties are either child elements or
+//! attributes of `rdf:Description`. Alongside the develop settings it records
+//! who made the image (`dc:creator`), IPTC contact and location details
+//! (`Iptc4xmpCore`), Photoshop's credit, city and caption fields, and the
+//! media-management IDs (`xmpMM`) that tie every derived copy back to the same
+//! original. Those properties are dropped wherever they appear; the develop
+//! settings and everything else are written back unchanged, so the edits
+//! still apply.
+//!
+//! Properties are matched by their conventional prefixes, which every XMP
+//! writer in practice uses.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::QName;
+use quick_xml::{Reader, Writer};
+
+/// Prefixes whose every property identifies the author, place or document history.
+const IDENTITY_PREFIXES: &[&[u8]] = &[b"photoshop", b"xmpMM", b"Iptc4xmpCore"];
+
+/// Single identity properties in otherwise harmless namespaces.
+const IDENTITY_NAMES: &[&[u8]] = &[b"dc:creator"];
+
+/// Returns true for identity properties, e.g. `xmpMM:DocumentID` or `dc:creator`.
+fn is_identity_name(name: QName) -> bool {
+    IDENTITY_NAMES.contains(&name.as_ref())
+        || name.prefix().is_some_and(|prefix| IDENTITY_PREFIXES.contains(&prefix.as_ref()))
+}
+
+/// Records a removed property's name once, keeping first-seen order.
+fn record(removed: &mut Vec<String>, name: QName) {
+    let name = String::from_utf8_lossy(name.as_ref()).into_owned();
+    if !removed.contains(&name) {
+        removed.push(name);
+    }
+}
+
+/// Copies an element without identity attributes.
+fn without_identity_attributes<'a>(start: &BytesStart<'a>, removed: &mut Vec<String>) -> Option<BytesStart<'a>> {
+    let mut cleaned = start.clone();
+    cleaned.clear_attributes();
+
+    for attribute in start.attributes() {
+        let attribute = attribute.ok()?;
+        if is_identity_name(attribute.key) {
+            record(removed, attribute.key);
+        } else {
+            cleaned.push_attribute(attribute);
+        }
+    }
+
+    Some(cleaned)
+}
+
+/// Rewrites an XMP packet without identity properties, returning the new text
+/// and the names of the properties removed.
+///
+/// Returns `None` if the text isn't well-formed XML with an `x:xmpmeta` or
+/// `rdf:RDF` root, so other files named `.xmp` are never rewritten.
+fn rewrite(text: &str) -> Option<(String, Vec<String>)> {
+    let mut reader = Reader::from_str(text);
+    let mut writer = Writer::new(Vec::with_capacity(text.len()));
+    let mut removed = Vec::new();
+    let mut seen_root = false;
+    // Elements open at the current position, to reject truncated documents
+    let mut open = 0usize;
+    // Nesting depth inside a property being dropped
+    let mut dropping: Option<usize> = None;
+
+    loop {
+        let event = reader.read_event().ok()?;
+
+        if let Event::Start(start) | Event::Empty(start) = &event {
+            if !seen_root {
+                if !matches!(start.name().local_name().as_ref(), b"xmpmeta" | b"RDF") {
+                    return None;
+                }
+                seen_root = true;
+            }
+        }
+        match &event {
+            Event::Start(_) => open += 1,
+            Event::End(_) => open = open.checked_sub(1)?,
+            _ => {}
+        }
+
+        match (&mut dropping, event) {
+            (_, Event::Eof) => break,
+            (Some(depth), Event::Start(_)) => *depth += 1,
+            (Some(0), Event::End(_)) => dropping = None,
+            (Some(depth), Event::End(_)) => *depth -= 1,
+            (Some(_), _) => {}
+            (None, Event::Start(start)) => {
+                if is_identity_name(start.name()) {
+                    record(&mut removed, start.name());
+                    dropping = Some(0);
+                } else {
+                    let start = without_identity_attributes(&start, &mut removed)?;
+                    writer.write_event(Event::Start(start)).ok()?;
+                }
+            }
+            (None, Event::Empty(start)) => {
+                if is_identity_name(start.name()) {
+                    record(&mut removed, start.name());
+                } else {
+                    let start = without_identity_attributes(&start, &mut removed)?;
+                    writer.write_event(Event::Empty(start)).ok()?;
+                }
+            }
+            (None, event) => writer.write_event(event).ok()?,
+        }
+    }
+
+    if !seen_root || open != 0 {
+        return None;
+    }
+
+    Some((String::from_utf8_lossy(&writer.into_inner()).into_owned(), removed))
+}
+
+/// Returns the names of the identity properties an XMP packet carries, e.g.
+/// `["dc:creator", "xmpMM:DocumentID"]`, or `None` if it isn't valid XMP.
+pub fn xmp_metadata(text: &str) -> Option<Vec<String>> {
+    rewrite(text).map(|(_, removed)| removed)
+}
+
+/// Removes `dc:creator` and the `photoshop`, `xmpMM` and `Iptc4xmpCore` properties.
+///
+/// Returns `None` if the packet is invalid or has nothing to remove, in which
+/// case the original should be kept.
+pub fn strip_xmp_metadata(text: &str) -> Option<String> {
+    let (cleaned, removed) = rewrite(text)?;
+    if removed.is_empty() {
+        return None;
+    }
+    Some(cleaned)
+}
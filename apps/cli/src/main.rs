@@ -19,6 +19,12 @@
 //!
 //! # View file metadata info
 //! rs-mahito info path/to/file.txt
+//!
+//! # Fail if a file still carries metadata
+//! rs-mahito verify path/to/file.txt
+//!
+//! # Check which cleaning operations work on this system
+//! rs-mahito doctor
 //! ```
 
 mod cli;
@@ -26,18 +32,43 @@ mod core;
 
 use clap::Parser;
 use colored::Colorize;
+use console::Term;
 
-use cli::{Cli, Runner};
+use cli::{Cli, Config, Runner};
 
 fn main() {
-    // Parse command-line arguments
-    let cli = Cli::parse();
+    // Plain output when piped or redirected, or when NO_COLOR (https://no-color.org)
+    // asks for it; CLICOLOR_FORCE keeps colors on regardless
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let force_color = std::env::var_os("CLICOLOR_FORCE").is_some_and(|value| !value.is_empty() && value != "0");
+    if !force_color && (no_color || !Term::stdout().is_term()) {
+        colored::control::set_override(false);
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    }
+
+    // Parse command-line arguments, then fill in defaults from the config file
+    let mut cli = Cli::parse();
+
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{} {:#}", "Error:".red().bold(), e);
+            std::process::exit(1);
+        }
+    };
+    config.apply(&mut cli.global);
+    cli.global.dry_run |= cli.global.dry_run_diff;
 
     // Create and run the command runner
-    let runner = Runner::new(cli);
+    let runner = Runner::new(cli, config);
 
-    if let Err(e) = runner.run() {
-        eprintln!("{} {}", "Error:".red().bold(), e);
-        std::process::exit(1);
+    // 0: success, 1: hard error, 2: some files failed (or `verify` found metadata)
+    match runner.run() {
+        Ok(outcome) => std::process::exit(outcome.exit_code()),
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            std::process::exit(1);
+        }
     }
 }